@@ -0,0 +1,451 @@
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{timeout_at, Instant};
+use uuid::Uuid;
+
+use hang_client::constants::LOCAL_WS_URL;
+use hang_client::protocol::{Message, SyncCommand};
+use hang_client::sync::{NewRoomOptions, SyncClient};
+
+/// Timestamps and rates closer than this count as equal when matching `--expect`
+const MATCH_TOLERANCE: f64 = 1e-3;
+/// Longest `--timeout`, `--listen`, or `sleep:` a script may ask for
+const MAX_WAIT_SECS: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+
+/// Scriptable Hang client. Every message received from the server is printed to stdout
+/// as one JSON line; the exit code is non-zero if joining fails or an expectation is not met.
+#[derive(Parser)]
+#[command(name = "hang-cli", version)]
+struct Cli {
+    /// WebSocket endpoint of the sync server
+    #[arg(long, env = "HANG_SERVER_URL", default_value = LOCAL_WS_URL)]
+    server: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a room and stay in it as the host
+    Create {
+        /// Hash the other members must load; any string works for scripted sessions
+        #[arg(long)]
+        file_hash: String,
+        #[arg(long)]
+        passcode: Option<String>,
+        #[arg(long)]
+        capacity: Option<usize>,
+        /// Vanity room code, if the server allows them
+        #[arg(long)]
+        code: Option<String>,
+        #[command(flatten)]
+        session: SessionArgs,
+    },
+    /// Join an existing room
+    Join {
+        room: String,
+        #[arg(long)]
+        file_hash: String,
+        #[arg(long)]
+        passcode: Option<String>,
+        /// Invite token from an invite link, instead of the passcode
+        #[arg(long)]
+        invite: Option<String>,
+        /// Take a spectator seat; spectators can't send sync commands
+        #[arg(long)]
+        spectator: bool,
+        #[command(flatten)]
+        session: SessionArgs,
+    },
+    /// Rejoin a room with the resume token from an earlier `RoomCreated` or `RoomJoined`
+    Resume {
+        token: String,
+        #[command(flatten)]
+        session: SessionArgs,
+    },
+    /// Print the public room directory and exit
+    Rooms,
+}
+
+#[derive(Args)]
+struct SessionArgs {
+    /// Display name shown to the room
+    #[arg(long)]
+    name: Option<String>,
+    /// Wait until the room has at least this many members before running any step
+    #[arg(long, value_name = "COUNT")]
+    wait_for_members: Option<usize>,
+    /// Step to run once in the room, in order: play:SECS, pause:SECS, seek:SECS,
+    /// speed:RATE, stop, invite (host only), or sleep:SECS
+    #[arg(long = "send", value_name = "STEP", value_parser = parse_step)]
+    steps: Vec<Step>,
+    /// Sync command another member must broadcast, such as play:12.5; may be repeated
+    #[arg(long, value_name = "COMMAND", value_parser = parse_command)]
+    expect: Vec<SyncCommand>,
+    /// Keep printing messages for this long after the last step
+    #[arg(long, value_name = "SECS", default_value = "0", value_parser = parse_wait)]
+    listen: Duration,
+    /// Give up on joining, `--wait-for-members`, and `--expect` after this long
+    #[arg(long, value_name = "SECS", default_value = "10", value_parser = parse_wait)]
+    timeout: Duration,
+}
+
+#[derive(Clone)]
+enum Step {
+    Sync(SyncCommand),
+    Invite,
+    Sleep(Duration),
+}
+
+fn parse_seconds(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .ok_or_else(|| format!("invalid number of seconds: {value}"))
+}
+
+fn parse_rate(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .ok_or_else(|| format!("invalid playback rate: {value} (must be greater than 0)"))
+}
+
+/// Seconds to wait, capped so that adding them to the current time cannot overflow
+fn parse_wait(value: &str) -> Result<Duration, String> {
+    let secs = parse_seconds(value)?;
+    if secs > MAX_WAIT_SECS {
+        return Err(format!(
+            "{value} seconds is too long; the limit is {MAX_WAIT_SECS}"
+        ));
+    }
+    Ok(Duration::from_secs_f64(secs))
+}
+
+fn parse_command(input: &str) -> Result<SyncCommand, String> {
+    let (action, value) = match input.split_once(':') {
+        Some((action, value)) => (action, Some(value)),
+        None => (input, None),
+    };
+    let required = |example: &str| {
+        value.ok_or_else(|| format!("{action} needs a value, e.g. {action}:{example}"))
+    };
+    let number = || required("12.5").and_then(parse_seconds);
+    match action.to_ascii_lowercase().as_str() {
+        "play" => Ok(SyncCommand::Play {
+            timestamp: number()?,
+        }),
+        "pause" => Ok(SyncCommand::Pause {
+            timestamp: number()?,
+        }),
+        "seek" => Ok(SyncCommand::Seek {
+            timestamp: number()?,
+        }),
+        "speed" => Ok(SyncCommand::Speed {
+            rate: required("1.5").and_then(parse_rate)?,
+        }),
+        "stop" => Ok(SyncCommand::Stop),
+        other => Err(format!("unknown sync command: {other}")),
+    }
+}
+
+fn parse_step(input: &str) -> Result<Step, String> {
+    match input.split_once(':') {
+        Some(("sleep", secs)) => Ok(Step::Sleep(parse_wait(secs)?)),
+        None if input == "invite" => Ok(Step::Invite),
+        _ => parse_command(input).map(Step::Sync),
+    }
+}
+
+fn same_command(a: &SyncCommand, b: &SyncCommand) -> bool {
+    let close = |x: f64, y: f64| (x - y).abs() < MATCH_TOLERANCE;
+    match (a, b) {
+        (SyncCommand::Play { timestamp: x }, SyncCommand::Play { timestamp: y })
+        | (SyncCommand::Pause { timestamp: x }, SyncCommand::Pause { timestamp: y })
+        | (SyncCommand::Seek { timestamp: x }, SyncCommand::Seek { timestamp: y })
+        | (SyncCommand::Speed { rate: x }, SyncCommand::Speed { rate: y }) => close(*x, *y),
+        (SyncCommand::Stop, SyncCommand::Stop) => true,
+        _ => false,
+    }
+}
+
+/// Connection plus everything the script is waiting for
+struct Session {
+    sync: SyncClient,
+    inbox: mpsc::UnboundedReceiver<Message>,
+    disconnected: oneshot::Receiver<()>,
+    client_id: Option<Uuid>,
+    member_count: usize,
+    pending: Vec<SyncCommand>,
+}
+
+impl Session {
+    async fn connect(server: &str) -> Result<Self> {
+        let sync = SyncClient::new();
+        let (tx, inbox) = mpsc::unbounded_channel();
+        let disconnected = sync
+            .connect(server, move |message| {
+                let _ = tx.send(message);
+            })
+            .await
+            .with_context(|| format!("Could not reach {server}"))?;
+        Ok(Self {
+            sync,
+            inbox,
+            disconnected,
+            client_id: None,
+            member_count: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Waits for the next message until `deadline`, printing and recording it.
+    /// Returns `None` when the deadline passes.
+    async fn next(&mut self, deadline: Instant) -> Result<Option<Message>> {
+        let message = tokio::select! {
+            message = timeout_at(deadline, self.inbox.recv()) => match message {
+                Ok(Some(message)) => message,
+                Ok(None) => bail!("Connection closed"),
+                Err(_) => return Ok(None),
+            },
+            _ = &mut self.disconnected => bail!("Connection closed by the server"),
+        };
+        println!("{}", serde_json::to_string(&message)?);
+        match &message {
+            Message::RoomCreated { client_id, .. } | Message::RoomJoined { client_id, .. } => {
+                self.client_id = Some(*client_id);
+                self.member_count = self.member_count.max(1);
+            }
+            Message::RoomMemberUpdate { members, .. } => self.member_count = members.len(),
+            Message::SyncBroadcast {
+                from_client,
+                command,
+            } if Some(*from_client) != self.client_id => {
+                if let Some(index) = self
+                    .pending
+                    .iter()
+                    .position(|expected| same_command(expected, command))
+                {
+                    self.pending.remove(index);
+                }
+            }
+            _ => {}
+        }
+        Ok(Some(message))
+    }
+
+    /// Reads messages until the server confirms the room, failing on any rejection
+    async fn wait_until_joined(&mut self, deadline: Instant) -> Result<()> {
+        loop {
+            match self.next(deadline).await? {
+                Some(Message::RoomCreated { .. } | Message::RoomJoined { .. }) => return Ok(()),
+                Some(Message::RoomNotFound) => bail!("Room not found"),
+                Some(Message::RoomFull { capacity }) => bail!("Room is full ({capacity} seats)"),
+                Some(Message::FileHashMismatch { expected }) => {
+                    bail!("File hash mismatch; the room expects {expected}")
+                }
+                Some(Message::Error { message }) => bail!("Server rejected the request: {message}"),
+                Some(_) => {}
+                None => bail!("Timed out waiting to enter the room"),
+            }
+        }
+    }
+
+    async fn wait_for_members(&mut self, count: usize, deadline: Instant) -> Result<()> {
+        while self.member_count < count {
+            if self.next(deadline).await?.is_none() {
+                bail!(
+                    "Timed out waiting for {count} members ({} present)",
+                    self.member_count
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints messages until `until`, without failing when it passes
+    async fn drain(&mut self, until: Instant) -> Result<()> {
+        while self.next(until).await?.is_some() {}
+        Ok(())
+    }
+
+    async fn run_steps(&mut self, steps: &[Step]) -> Result<()> {
+        for step in steps {
+            match step {
+                Step::Sync(command) => self.sync.send_sync_command(command.clone())?,
+                Step::Invite => self.sync.create_invite(None, None)?,
+                Step::Sleep(duration) => self.drain(Instant::now() + *duration).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the scripted part of a session once the room has been entered
+    async fn run(mut self, args: &SessionArgs, deadline: Instant) -> Result<()> {
+        self.wait_until_joined(deadline).await?;
+        if let Some(count) = args.wait_for_members {
+            self.wait_for_members(count, deadline).await?;
+        }
+        self.pending = args.expect.clone();
+        let expect_deadline = Instant::now() + args.timeout;
+        self.run_steps(&args.steps).await?;
+
+        let listen_until = Instant::now() + args.listen;
+        while !self.pending.is_empty() {
+            if self.next(expect_deadline).await?.is_none() {
+                let missing: Vec<String> = self
+                    .pending
+                    .iter()
+                    .map(|command| serde_json::to_string(command).unwrap_or_default())
+                    .collect();
+                bail!(
+                    "Expected sync commands never arrived: {}",
+                    missing.join(", ")
+                );
+            }
+        }
+        self.drain(listen_until).await
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()),
+        )
+        .init();
+
+    let cli = Cli::parse();
+    let mut session = Session::connect(&cli.server).await?;
+
+    match cli.command {
+        Command::Create {
+            file_hash,
+            passcode,
+            capacity,
+            code,
+            session: args,
+        } => {
+            session.sync.create_room(
+                file_hash,
+                NewRoomOptions {
+                    passcode,
+                    display_name: args.name.clone(),
+                    capacity,
+                    requested_code: code,
+                    ..NewRoomOptions::default()
+                },
+            )?;
+            session.run(&args, deadline(&args)).await
+        }
+        Command::Join {
+            room,
+            file_hash,
+            passcode,
+            invite,
+            spectator,
+            session: args,
+        } => {
+            session.sync.join_room(
+                room,
+                file_hash,
+                passcode,
+                invite,
+                args.name.clone(),
+                spectator,
+            )?;
+            session.run(&args, deadline(&args)).await
+        }
+        Command::Resume {
+            token,
+            session: args,
+        } => {
+            session.sync.resume_session(token, args.name.clone())?;
+            session.run(&args, deadline(&args)).await
+        }
+        Command::Rooms => {
+            session.sync.list_public_rooms()?;
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                match session.next(deadline).await? {
+                    Some(Message::PublicRoomList { .. }) => return Ok(()),
+                    Some(_) => {}
+                    None => bail!("Timed out waiting for the room list"),
+                }
+            }
+        }
+    }
+}
+
+fn deadline(args: &SessionArgs) -> Instant {
+    Instant::now() + args.timeout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_and_commands() {
+        assert!(matches!(
+            parse_step("play:12.5"),
+            Ok(Step::Sync(SyncCommand::Play { timestamp })) if timestamp == 12.5
+        ));
+        assert!(matches!(
+            parse_step("stop"),
+            Ok(Step::Sync(SyncCommand::Stop))
+        ));
+        assert!(matches!(parse_step("invite"), Ok(Step::Invite)));
+        assert!(matches!(
+            parse_step("sleep:0.5"),
+            Ok(Step::Sleep(duration)) if duration == Duration::from_millis(500)
+        ));
+        assert!(parse_step("seek").is_err());
+        assert!(parse_step("rewind:3").is_err());
+        assert!(parse_command("pause:-1").is_err());
+        assert!(parse_step("sleep:1e300").is_err());
+    }
+
+    #[test]
+    fn speeds_must_be_finite_and_positive() {
+        assert!(matches!(
+            parse_command("speed:1.5"),
+            Ok(SyncCommand::Speed { rate }) if rate == 1.5
+        ));
+        assert_eq!(
+            parse_command("speed:0").unwrap_err(),
+            "invalid playback rate: 0 (must be greater than 0)"
+        );
+        assert!(parse_command("speed:-2").is_err());
+        assert!(parse_command("speed:inf").is_err());
+        assert!(parse_command("speed").is_err());
+    }
+
+    #[test]
+    fn waits_must_be_finite_non_negative_and_bounded() {
+        assert_eq!(parse_wait("2.5"), Ok(Duration::from_millis(2500)));
+        assert_eq!(parse_wait("0"), Ok(Duration::ZERO));
+        assert!(parse_wait("-1").is_err());
+        assert!(parse_wait("inf").is_err());
+        assert!(parse_wait("NaN").is_err());
+        assert!(parse_wait("1e12").is_err());
+    }
+
+    #[test]
+    fn expectations_tolerate_float_noise() {
+        assert!(same_command(
+            &SyncCommand::Seek { timestamp: 30.0 },
+            &SyncCommand::Seek { timestamp: 30.0004 }
+        ));
+        assert!(!same_command(
+            &SyncCommand::Seek { timestamp: 30.0 },
+            &SyncCommand::Play { timestamp: 30.0 }
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
-use urlencoding::{decode, encode};
+use urlencoding::encode;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InviteSignal {
@@ -10,14 +10,26 @@ pub struct InviteSignal {
 #[derive(Debug, Clone)]
 pub struct InviteLink {
     pub room_id: String,
+    /// Only present in links made by older clients; new links carry an invite token instead
     pub passcode: Option<String>,
+    /// Server-issued token that admits the holder without the passcode
+    pub invite: Option<String>,
     pub file_name: Option<String>,
 }
 
-pub fn build_invite_url(room_id: &str, passcode: Option<&str>, file_name: Option<&str>) -> String {
+/// Invite token the host minted for the current room
+#[derive(Debug, Clone)]
+pub struct IssuedInvite {
+    pub token: String,
+    pub url: String,
+    pub max_uses: Option<u32>,
+    pub expires_at_ms: Option<u64>,
+}
+
+pub fn build_invite_url(room_id: &str, invite: Option<&str>, file_name: Option<&str>) -> String {
     let mut params = vec![format!("room={}", encode(room_id))];
-    if let Some(code) = passcode.filter(|c| !c.is_empty()) {
-        params.push(format!("code={}", encode(code)));
+    if let Some(token) = invite.filter(|t| !t.is_empty()) {
+        params.push(format!("invite={}", encode(token)));
     }
     if let Some(name) = file_name.filter(|n| !n.is_empty()) {
         params.push(format!("file={}", encode(name)));
@@ -32,9 +44,10 @@ pub fn parse_invite_url(input: &str) -> Option<InviteLink> {
         return None;
     }
 
-    let normalized = if trimmed.starts_with("hang://") {
-        trimmed.to_string()
-    } else if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+    let normalized = if trimmed.starts_with("hang://")
+        || trimmed.starts_with("http://")
+        || trimmed.starts_with("https://")
+    {
         trimmed.to_string()
     } else {
         format!("hang://join?{}", trimmed)
@@ -43,13 +56,16 @@ pub fn parse_invite_url(input: &str) -> Option<InviteLink> {
     let url = Url::parse(&normalized).ok()?;
     let mut room_id = None;
     let mut passcode = None;
+    let mut invite = None;
     let mut file_name = None;
 
+    // `query_pairs` already percent-decodes; decoding again would mangle names containing `%`
     for (key, value) in url.query_pairs() {
         match key.as_ref() {
-            "room" => room_id = decode(&value).ok().map(|v| v.into_owned()),
-            "code" => passcode = decode(&value).ok().map(|v| v.into_owned()),
-            "file" => file_name = decode(&value).ok().map(|v| v.into_owned()),
+            "room" => room_id = Some(value.into_owned()),
+            "code" => passcode = Some(value.into_owned()),
+            "invite" => invite = Some(value.into_owned()),
+            "file" => file_name = Some(value.into_owned()),
             _ => {}
         }
     }
@@ -58,6 +74,59 @@ pub fn parse_invite_url(input: &str) -> Option<InviteLink> {
     Some(InviteLink {
         room_id,
         passcode,
+        invite,
         file_name,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn invite_links_round_trip_without_passcode() {
+        let url = build_invite_url("ABC123", Some("3f2a9c"), Some("Movie Night.mkv"));
+        assert!(!url.contains("code="));
+        let link = parse_invite_url(&url).unwrap();
+        assert_eq!(link.room_id, "ABC123");
+        assert_eq!(link.invite.as_deref(), Some("3f2a9c"));
+        assert_eq!(link.file_name.as_deref(), Some("Movie Night.mkv"));
+        assert!(link.passcode.is_none());
+    }
+
+    #[test]
+    fn percent_signs_in_file_names_survive() {
+        let url = build_invite_url("ABC123", None, Some("100%41 done.mkv"));
+        let link = parse_invite_url(&url).unwrap();
+        assert_eq!(link.file_name.as_deref(), Some("100%41 done.mkv"));
+    }
+
+    proptest! {
+        #[test]
+        fn built_links_parse_back_to_their_inputs(
+            room_id in any::<String>(),
+            invite in prop::option::of(".+"),
+            file_name in prop::option::of(".+"),
+        ) {
+            let url = build_invite_url(&room_id, invite.as_deref(), file_name.as_deref());
+            let link = parse_invite_url(&url).unwrap();
+            prop_assert_eq!(link.room_id, room_id);
+            prop_assert_eq!(link.invite, invite);
+            prop_assert_eq!(link.file_name, file_name);
+            prop_assert!(link.passcode.is_none());
+        }
+
+        #[test]
+        fn parsing_arbitrary_input_never_panics(input in any::<String>()) {
+            let _ = parse_invite_url(&input);
+        }
+    }
+
+    #[test]
+    fn legacy_links_still_carry_their_passcode() {
+        let link = parse_invite_url("https://hang.example/join?room=ABC123&code=hunter2").unwrap();
+        assert_eq!(link.passcode.as_deref(), Some("hunter2"));
+        assert!(link.invite.is_none());
+    }
+}
@@ -1,5 +1,4 @@
 use anyhow::Result;
-use serde_json;
 use tokio::{
     io::AsyncReadExt,
     io::AsyncWriteExt,
@@ -0,0 +1,128 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Entries kept at most; the least recently used are forgotten first
+const MAX_ENTRIES: usize = 500;
+
+/// Something a [`JsonStore`] can evict by age
+pub trait Timestamped {
+    /// Unix time in milliseconds the entry was last written
+    fn updated_at_ms(&self) -> u64;
+}
+
+/// Map of entries keyed by media hash, kept as one pretty-printed JSON file
+pub struct JsonStore<T> {
+    path: Option<PathBuf>,
+    /// What the file holds, for log messages
+    label: &'static str,
+    entries: HashMap<String, T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Timestamped> JsonStore<T> {
+    /// Reads `path`, starting empty if it is missing or unreadable; `None` keeps nothing on disk
+    pub fn open(path: Option<PathBuf>, label: &'static str) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            label,
+            entries,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.entries.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.entries
+            .iter()
+            .map(|(key, entry)| (key.as_str(), entry))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds or replaces an entry in memory, evicting the oldest past the limit
+    pub fn insert(&mut self, key: &str, entry: T) {
+        self.entries.insert(key.to_string(), entry);
+        while self.entries.len() > MAX_ENTRIES {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.updated_at_ms())
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&String, &mut T) -> bool) {
+        self.entries.retain(keep);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        match serde_json::to_vec_pretty(&self.entries) {
+            Ok(payload) => {
+                if let Err(e) = fs::write(path, payload) {
+                    tracing::warn!(error = %e, "Failed to save {}", self.label);
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to serialize {}", self.label),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry(u64);
+
+    impl Timestamped for Entry {
+        fn updated_at_ms(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn evicts_the_oldest_entries_past_the_limit() {
+        let path = std::env::temp_dir().join(format!("hang-store-{}.json", uuid::Uuid::new_v4()));
+        let mut store = JsonStore::open(Some(path.clone()), "test entries");
+        for age in 0..=MAX_ENTRIES as u64 {
+            store.insert(&format!("key-{age}"), Entry(1_000 + age));
+        }
+        store.save();
+
+        let reloaded: JsonStore<Entry> = JsonStore::open(Some(path.clone()), "test entries");
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.iter().count(), MAX_ENTRIES);
+        assert!(reloaded.get("key-0").is_none());
+        assert_eq!(reloaded.get("key-1").unwrap().0, 1_001);
+    }
+}
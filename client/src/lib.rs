@@ -0,0 +1,16 @@
+pub mod constants;
+pub mod invite;
+pub mod ipc;
+pub mod json_store;
+pub mod media;
+pub mod media_settings;
+pub mod player;
+pub mod protocol;
+pub mod room_code;
+pub mod subtitles;
+pub mod sync;
+pub mod ui;
+pub mod update;
+pub mod utils;
+pub mod watch_history;
+pub mod youtube;
@@ -1,26 +1,17 @@
 #![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
 
-mod constants;
-mod invite;
-mod ipc;
-mod player;
-mod protocol;
-mod sync;
-mod ui;
-mod update;
-mod utils;
-mod youtube;
-
 use anyhow::Result;
 use parking_lot::Mutex;
 use std::sync::Arc;
 
-use constants::{LOCAL_WS_URL, RENDER_WS_URL};
-use invite::InviteSignal;
-use player::VideoPlayer;
-use sync::SyncClient;
+use hang_client::constants::{LOCAL_WS_URL, RENDER_WS_URL};
+use hang_client::invite::InviteSignal;
+use hang_client::ipc;
+use hang_client::media::{Clock, MediaBackend, SimulatedBackend};
+use hang_client::player::VideoPlayer;
+use hang_client::sync::SyncClient;
+use hang_client::ui::HangApp;
 use tokio::{sync::mpsc, time::Duration};
-use ui::HangApp;
 use url::Url;
 
 #[tokio::main]
@@ -51,8 +42,14 @@ async fn main() -> Result<()> {
         let _ = invite_tx.send(InviteSignal { url });
     }
 
-    // Initialize video player
-    let player = Arc::new(VideoPlayer::new(None).map_err(|e| anyhow::anyhow!(e))?);
+    // Initialize video player; the simulated backend runs without libVLC
+    let simulated = std::env::var("HANG_MEDIA_BACKEND").is_ok_and(|backend| backend == "simulated");
+    let player: Arc<dyn MediaBackend> = if simulated {
+        tracing::info!("Using simulated media backend");
+        Arc::new(SimulatedBackend::new(Clock::system()))
+    } else {
+        Arc::new(VideoPlayer::new(None).map_err(|e| anyhow::anyhow!(e))?)
+    };
 
     // Initialize sync client
     let sync = Arc::new(SyncClient::new());
@@ -186,33 +183,50 @@ async fn run_connection_loop(
                     );
                     attempt = 0;
 
-                    loop {
-                        tokio::select! {
-                            _ = &mut disconnect_rx => {
-                                tracing::warn!("Sync connection closed; re-attempting connection");
-                                sync_client.mark_disconnected();
-                                update_connection_status(
-                                    &app_state,
-                                    "Lost sync connection. Attempting to reconnect...".to_string(),
-                                    Some(false),
+                    tokio::select! {
+                        _ = &mut disconnect_rx => {
+                            sync_client.mark_disconnected();
+                            if let Some(delay) = sync_client.take_planned_reconnect() {
+                                tracing::info!(
+                                    "Sync server is restarting; reconnecting in {} ms",
+                                    delay.as_millis()
                                 );
-                                notify_connection_loss(&app_state);
-                                continue 'outer;
-                            }
-                            recv = reconnect_rx.recv() => {
-                                if recv.is_none() {
-                                    tracing::info!("Reconnect channel closed; stopping connection loop");
-                                    return;
+                                notify_planned_reconnect(&app_state, delay);
+                                let sleep = tokio::time::sleep(delay);
+                                tokio::pin!(sleep);
+                                tokio::select! {
+                                    _ = sleep.as_mut() => {},
+                                    recv = reconnect_rx.recv() => {
+                                        if recv.is_none() {
+                                            tracing::info!("Reconnect channel closed; stopping connection loop");
+                                            return;
+                                        }
+                                    }
                                 }
-                                tracing::info!("Manual reconnect requested; restarting connection attempts");
-                                sync_client.mark_disconnected();
-                                update_connection_status(
-                                    &app_state,
-                                    "Reconnecting to sync server...".to_string(),
-                                    Some(false),
-                                );
                                 continue 'outer;
                             }
+                            tracing::warn!("Sync connection closed; re-attempting connection");
+                            update_connection_status(
+                                &app_state,
+                                "Lost sync connection. Attempting to reconnect...".to_string(),
+                                Some(false),
+                            );
+                            notify_connection_loss(&app_state);
+                            continue 'outer;
+                        }
+                        recv = reconnect_rx.recv() => {
+                            if recv.is_none() {
+                                tracing::info!("Reconnect channel closed; stopping connection loop");
+                                return;
+                            }
+                            tracing::info!("Manual reconnect requested; restarting connection attempts");
+                            sync_client.mark_disconnected();
+                            update_connection_status(
+                                &app_state,
+                                "Reconnecting to sync server...".to_string(),
+                                Some(false),
+                            );
+                            continue 'outer;
                         }
                     }
                 }
@@ -268,6 +282,13 @@ fn notify_connection_loss(app_state: &Arc<Mutex<Option<Arc<Mutex<HangApp>>>>>) {
     }
 }
 
+fn notify_planned_reconnect(app_state: &Arc<Mutex<Option<Arc<Mutex<HangApp>>>>>, delay: Duration) {
+    if let Some(app_arc) = app_state.lock().as_ref() {
+        let mut app = app_arc.lock();
+        app.handle_planned_reconnect(delay);
+    }
+}
+
 async fn warm_up_backend(
     app_state: &Arc<Mutex<Option<Arc<Mutex<HangApp>>>>>,
     label: &str,
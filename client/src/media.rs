@@ -0,0 +1,616 @@
+use parking_lot::Mutex;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Playback operations the UI and sync logic rely on.
+/// Positions are in seconds, volume is 0-100, and track id -1 disables a track.
+pub trait MediaBackend: Send + Sync {
+    /// Load a video file
+    fn load_file(&self, path: &Path) -> Result<(), String>;
+    /// Load a video from URL
+    fn load_url(&self, url: &str) -> Result<(), String>;
+    fn play(&self) -> Result<(), String>;
+    fn pause(&self) -> Result<(), String>;
+    fn stop(&self) -> Result<(), String>;
+    /// Seek to a specific timestamp (in seconds)
+    fn seek(&self, timestamp: f64) -> Result<(), String>;
+    fn set_speed(&self, speed: f64) -> Result<(), String>;
+    /// Current playback position (in seconds)
+    fn get_position(&self) -> Result<f64, String>;
+    /// Video duration (in seconds)
+    fn get_duration(&self) -> Result<f64, String>;
+    fn is_paused(&self) -> Result<bool, String>;
+    fn get_speed(&self) -> Result<f64, String>;
+    /// Set volume (0-100)
+    fn set_volume(&self, volume: f64) -> Result<(), String>;
+    fn get_volume(&self) -> Result<f64, String>;
+    fn get_audio_tracks(&self) -> Result<Vec<AudioTrack>, String>;
+    fn set_audio_track(&self, track_id: i64) -> Result<(), String>;
+    fn get_subtitle_tracks(&self) -> Result<Vec<SubtitleTrack>, String>;
+    /// Set current subtitle track (use -1 to disable)
+    fn set_subtitle_track(&self, track_id: i64) -> Result<(), String>;
+    /// Add a subtitle file (SRT, ASS, VTT) to the loaded video as an extra track and select it.
+    /// The track can take a moment to show up in `get_subtitle_tracks`, titled with the file name.
+    fn add_subtitle_file(&self, path: &Path) -> Result<(), String>;
+    /// Shift subtitles by `seconds`; positive values show them later. Resets when media loads.
+    fn set_subtitle_delay(&self, seconds: f64) -> Result<(), String>;
+    fn get_subtitle_delay(&self) -> Result<f64, String>;
+    /// Shift audio by `seconds`; positive values play it later. Resets when media loads.
+    fn set_audio_delay(&self, seconds: f64) -> Result<(), String>;
+    fn get_audio_delay(&self) -> Result<f64, String>;
+    fn frame_step_forward(&self) -> Result<(), String>;
+    fn frame_step_backward(&self) -> Result<(), String>;
+    /// Latest BGRA frame, if one arrived since the last call
+    fn latest_frame(&self) -> Option<VideoFrame>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AudioTrack {
+    pub id: i64,
+    pub title: String,
+    pub lang: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubtitleTrack {
+    pub id: i64,
+    pub title: String,
+    pub lang: String,
+    /// Loaded from a separate file rather than muxed into the video
+    pub external: bool,
+}
+
+/// Finds the track among `(id, title, lang)` entries that matches one picked in another copy of
+/// the video, where ids can differ. Compares the full title, then the title without its
+/// `Track N - ` prefix, then the language. Negative ids ("Disable") never match.
+pub fn find_matching_track(tracks: &[(i64, &str, &str)], title: &str, lang: &str) -> Option<i64> {
+    let candidates = || tracks.iter().filter(|(id, _, _)| *id >= 0);
+    let wanted_name = track_name(title);
+    let wanted_lang = track_language(title, lang);
+    candidates()
+        .find(|(_, t, _)| t.eq_ignore_ascii_case(title))
+        .or_else(|| candidates().find(|(_, t, _)| track_name(t).eq_ignore_ascii_case(wanted_name)))
+        .or_else(|| {
+            let wanted = wanted_lang.as_deref()?;
+            candidates().find(|(_, t, l)| track_language(t, l).as_deref() == Some(wanted))
+        })
+        .map(|(id, _, _)| *id)
+}
+
+/// Title without the `Track N - ` prefix libVLC puts in front of track names
+fn track_name(title: &str) -> &str {
+    title
+        .strip_prefix("Track ")
+        .and_then(|rest| rest.split_once(" - "))
+        .filter(|(number, _)| number.chars().all(|ch| ch.is_ascii_digit()))
+        .map_or(title, |(_, name)| name)
+        .trim()
+}
+
+/// Lowercased language code, or the `[Language]` part of a libVLC track name
+fn track_language(title: &str, lang: &str) -> Option<String> {
+    if !lang.is_empty() && lang != title {
+        return Some(lang.to_lowercase());
+    }
+    let (_, rest) = title.rsplit_once('[')?;
+    let (language, _) = rest.split_once(']')?;
+    Some(language.trim().to_lowercase()).filter(|language| !language.is_empty())
+}
+
+#[derive(Clone)]
+pub struct VideoFrame {
+    pub width: u32,
+    pub height: u32,
+    pub buffer: Vec<u8>,
+}
+
+/// Length reported for every simulated video unless configured otherwise
+const DEFAULT_SIMULATED_DURATION: f64 = 2.0 * 60.0 * 60.0;
+const SIMULATED_FRAME_RATE: f64 = 25.0;
+const SIMULATED_FRAME_WIDTH: u32 = 64;
+const SIMULATED_FRAME_HEIGHT: u32 = 36;
+const DEFAULT_AUDIO_TRACK: i64 = 1;
+/// Track id given to the first added subtitle file; later ones count up from here
+const FIRST_EXTERNAL_SUBTITLE_ID: i64 = 100;
+
+/// Time source for [`SimulatedBackend`]
+#[derive(Debug, Clone, Copy)]
+pub enum Clock {
+    /// Only moves when [`SimulatedBackend::advance`] is called, so tests are deterministic
+    Virtual,
+    /// Follows the wall clock, for running the client headless
+    System(Instant),
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self::System(Instant::now())
+    }
+}
+
+/// Player without libVLC: tracks position, rate, volume and tracks against a clock
+/// and renders a flat frame whose shade changes with every video frame.
+pub struct SimulatedBackend {
+    clock: Clock,
+    duration: f64,
+    state: Mutex<SimulatedState>,
+}
+
+struct SimulatedState {
+    /// Time added by `advance`, on top of the clock
+    advanced: Duration,
+    media: Option<String>,
+    playing: bool,
+    /// Position at `anchor`
+    position: f64,
+    anchor: Duration,
+    rate: f64,
+    volume: f64,
+    audio_track: i64,
+    subtitle_track: i64,
+    /// File names of subtitles added to the current media, in track id order
+    external_subtitles: Vec<String>,
+    subtitle_delay: f64,
+    audio_delay: f64,
+    /// Index of the last frame handed out
+    last_frame: Option<u64>,
+}
+
+impl SimulatedBackend {
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            clock,
+            duration: DEFAULT_SIMULATED_DURATION,
+            state: Mutex::new(SimulatedState {
+                advanced: Duration::ZERO,
+                media: None,
+                playing: false,
+                position: 0.0,
+                anchor: Duration::ZERO,
+                rate: 1.0,
+                volume: 100.0,
+                audio_track: DEFAULT_AUDIO_TRACK,
+                subtitle_track: -1,
+                external_subtitles: Vec::new(),
+                subtitle_delay: 0.0,
+                audio_delay: 0.0,
+                last_frame: None,
+            }),
+        }
+    }
+
+    fn now(&self, state: &SimulatedState) -> Duration {
+        let base = match self.clock {
+            Clock::Virtual => Duration::ZERO,
+            Clock::System(start) => start.elapsed(),
+        };
+        base + state.advanced
+    }
+
+    /// Folds play time since the last change into the position; stops at the end of the video
+    fn settle(&self, state: &mut SimulatedState, now: Duration) {
+        if state.playing {
+            let elapsed = now.saturating_sub(state.anchor).as_secs_f64();
+            state.position += elapsed * state.rate;
+            if state.position >= self.duration {
+                state.position = self.duration;
+                state.playing = false;
+            }
+        }
+        state.anchor = now;
+    }
+
+    /// Runs `apply` on up-to-date state, failing when nothing is loaded
+    fn with_media<T>(&self, apply: impl FnOnce(&mut SimulatedState) -> T) -> Result<T, String> {
+        let mut state = self.state.lock();
+        if state.media.is_none() {
+            return Err("No media loaded".to_string());
+        }
+        let now = self.now(&state);
+        self.settle(&mut state, now);
+        Ok(apply(&mut state))
+    }
+
+    fn load(&self, media: String) {
+        let mut state = self.state.lock();
+        let now = self.now(&state);
+        state.media = Some(media);
+        state.playing = false;
+        state.position = 0.0;
+        state.anchor = now;
+        state.audio_track = DEFAULT_AUDIO_TRACK;
+        state.subtitle_track = -1;
+        state.external_subtitles.clear();
+        state.subtitle_delay = 0.0;
+        state.audio_delay = 0.0;
+        state.last_frame = None;
+    }
+
+    fn audio_tracks() -> Vec<AudioTrack> {
+        vec![
+            AudioTrack {
+                id: -1,
+                title: "Disable".to_string(),
+                lang: String::new(),
+            },
+            AudioTrack {
+                id: 1,
+                title: "Track 1 - English".to_string(),
+                lang: "eng".to_string(),
+            },
+            AudioTrack {
+                id: 2,
+                title: "Track 2 - Commentary".to_string(),
+                lang: "eng".to_string(),
+            },
+        ]
+    }
+
+    fn subtitle_tracks(state: &SimulatedState) -> Vec<SubtitleTrack> {
+        let mut tracks = vec![
+            SubtitleTrack {
+                id: -1,
+                title: "Disable".to_string(),
+                lang: String::new(),
+                external: false,
+            },
+            SubtitleTrack {
+                id: 3,
+                title: "Track 1 - English".to_string(),
+                lang: "eng".to_string(),
+                external: false,
+            },
+        ];
+        tracks.extend(
+            state
+                .external_subtitles
+                .iter()
+                .zip(FIRST_EXTERNAL_SUBTITLE_ID..)
+                .map(|(name, id)| SubtitleTrack {
+                    id,
+                    title: name.clone(),
+                    lang: String::new(),
+                    external: true,
+                }),
+        );
+        tracks
+    }
+}
+
+/// Controls for tests and tools driving the simulated backend
+impl SimulatedBackend {
+    /// Length of every video loaded into this backend, in seconds
+    pub fn with_media_duration(mut self, seconds: f64) -> Self {
+        self.duration = seconds.max(0.0);
+        self
+    }
+
+    /// Moves the clock forward; with [`Clock::System`] this skips ahead of real time
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.state.lock();
+        let now = self.now(&state);
+        self.settle(&mut state, now);
+        state.advanced += by;
+    }
+
+    /// Path or URL of the loaded video
+    pub fn loaded_media(&self) -> Option<String> {
+        self.state.lock().media.clone()
+    }
+
+    pub fn selected_audio_track(&self) -> i64 {
+        self.state.lock().audio_track
+    }
+
+    pub fn selected_subtitle_track(&self) -> i64 {
+        self.state.lock().subtitle_track
+    }
+}
+
+impl MediaBackend for SimulatedBackend {
+    fn load_file(&self, path: &Path) -> Result<(), String> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| "Invalid path encoding".to_string())?;
+        self.load(path.to_string());
+        Ok(())
+    }
+
+    fn load_url(&self, url: &str) -> Result<(), String> {
+        self.load(url.to_string());
+        Ok(())
+    }
+
+    fn play(&self) -> Result<(), String> {
+        let duration = self.duration;
+        self.with_media(|state| {
+            // Like VLC, playing a finished video starts it over
+            if state.position >= duration {
+                state.position = 0.0;
+            }
+            state.playing = true;
+        })
+    }
+
+    fn pause(&self) -> Result<(), String> {
+        self.with_media(|state| state.playing = false)
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        self.with_media(|state| {
+            state.playing = false;
+            state.position = 0.0;
+        })
+    }
+
+    fn seek(&self, timestamp: f64) -> Result<(), String> {
+        let duration = self.duration;
+        self.with_media(|state| state.position = timestamp.clamp(0.0, duration))
+    }
+
+    fn set_speed(&self, speed: f64) -> Result<(), String> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(format!("Unsupported playback rate {}", speed));
+        }
+        let mut state = self.state.lock();
+        let now = self.now(&state);
+        self.settle(&mut state, now);
+        state.rate = speed;
+        Ok(())
+    }
+
+    fn get_position(&self) -> Result<f64, String> {
+        self.with_media(|state| state.position)
+            .map_err(|_| "Position unavailable".to_string())
+    }
+
+    fn get_duration(&self) -> Result<f64, String> {
+        let duration = self.duration;
+        self.with_media(|_| duration)
+            .map_err(|_| "Duration unavailable".to_string())
+    }
+
+    fn is_paused(&self) -> Result<bool, String> {
+        let mut state = self.state.lock();
+        let now = self.now(&state);
+        self.settle(&mut state, now);
+        Ok(!state.playing)
+    }
+
+    fn get_speed(&self) -> Result<f64, String> {
+        Ok(self.state.lock().rate)
+    }
+
+    fn set_volume(&self, volume: f64) -> Result<(), String> {
+        self.state.lock().volume = volume.clamp(0.0, 100.0).trunc();
+        Ok(())
+    }
+
+    fn get_volume(&self) -> Result<f64, String> {
+        Ok(self.state.lock().volume)
+    }
+
+    fn get_audio_tracks(&self) -> Result<Vec<AudioTrack>, String> {
+        self.with_media(|_| Self::audio_tracks())
+            .or_else(|_| Ok(Vec::new()))
+    }
+
+    fn set_audio_track(&self, track_id: i64) -> Result<(), String> {
+        if !Self::audio_tracks()
+            .iter()
+            .any(|track| track.id == track_id)
+        {
+            return Err(format!("Unknown audio track {}", track_id));
+        }
+        self.with_media(|state| state.audio_track = track_id)
+    }
+
+    fn get_subtitle_tracks(&self) -> Result<Vec<SubtitleTrack>, String> {
+        self.with_media(|state| Self::subtitle_tracks(state))
+            .or_else(|_| Ok(Vec::new()))
+    }
+
+    fn set_subtitle_track(&self, track_id: i64) -> Result<(), String> {
+        self.with_media(|state| {
+            if !Self::subtitle_tracks(state)
+                .iter()
+                .any(|track| track.id == track_id)
+            {
+                return Err(format!("Unknown subtitle track {}", track_id));
+            }
+            state.subtitle_track = track_id;
+            Ok(())
+        })?
+    }
+
+    fn add_subtitle_file(&self, path: &Path) -> Result<(), String> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| "Invalid subtitle path".to_string())?
+            .to_string();
+        self.with_media(|state| {
+            state.external_subtitles.push(name);
+            state.subtitle_track =
+                FIRST_EXTERNAL_SUBTITLE_ID + state.external_subtitles.len() as i64 - 1;
+        })
+    }
+
+    fn set_subtitle_delay(&self, seconds: f64) -> Result<(), String> {
+        self.with_media(|state| state.subtitle_delay = seconds)
+    }
+
+    fn get_subtitle_delay(&self) -> Result<f64, String> {
+        self.with_media(|state| state.subtitle_delay)
+    }
+
+    fn set_audio_delay(&self, seconds: f64) -> Result<(), String> {
+        self.with_media(|state| state.audio_delay = seconds)
+    }
+
+    fn get_audio_delay(&self) -> Result<f64, String> {
+        self.with_media(|state| state.audio_delay)
+    }
+
+    fn frame_step_forward(&self) -> Result<(), String> {
+        let duration = self.duration;
+        self.with_media(|state| {
+            state.playing = false;
+            state.position = (state.position + 1.0 / SIMULATED_FRAME_RATE).min(duration);
+        })
+    }
+
+    fn frame_step_backward(&self) -> Result<(), String> {
+        self.with_media(|state| {
+            state.position = (state.position - 1.0 / SIMULATED_FRAME_RATE).max(0.0);
+        })
+    }
+
+    fn latest_frame(&self) -> Option<VideoFrame> {
+        self.with_media(|state| {
+            let index = (state.position * SIMULATED_FRAME_RATE) as u64;
+            if state.last_frame == Some(index) {
+                return None;
+            }
+            state.last_frame = Some(index);
+            let shade = (index % 256) as u8;
+            let pixels = (SIMULATED_FRAME_WIDTH * SIMULATED_FRAME_HEIGHT) as usize;
+            Some(VideoFrame {
+                width: SIMULATED_FRAME_WIDTH,
+                height: SIMULATED_FRAME_HEIGHT,
+                buffer: [shade, shade, shade, 255].repeat(pixels),
+            })
+        })
+        .ok()
+        .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded(duration: f64) -> SimulatedBackend {
+        let backend = SimulatedBackend::new(Clock::Virtual).with_media_duration(duration);
+        backend.load_file(Path::new("movie.mkv")).unwrap();
+        backend
+    }
+
+    #[test]
+    fn position_follows_the_virtual_clock_and_rate() {
+        let backend = loaded(600.0);
+        backend.play().unwrap();
+        backend.advance(Duration::from_secs(10));
+        assert_eq!(backend.get_position().unwrap(), 10.0);
+
+        backend.set_speed(2.0).unwrap();
+        backend.advance(Duration::from_secs(5));
+        assert_eq!(backend.get_position().unwrap(), 20.0);
+
+        backend.pause().unwrap();
+        backend.advance(Duration::from_secs(30));
+        assert_eq!(backend.get_position().unwrap(), 20.0);
+        assert!(backend.is_paused().unwrap());
+    }
+
+    #[test]
+    fn playback_stops_at_the_end() {
+        let backend = loaded(60.0);
+        backend.seek(55.0).unwrap();
+        backend.play().unwrap();
+        backend.advance(Duration::from_secs(10));
+        assert_eq!(backend.get_position().unwrap(), 60.0);
+        assert!(backend.is_paused().unwrap());
+
+        backend.seek(500.0).unwrap();
+        assert_eq!(backend.get_position().unwrap(), 60.0);
+    }
+
+    #[test]
+    fn nothing_plays_before_media_is_loaded() {
+        let backend = SimulatedBackend::new(Clock::Virtual);
+        assert!(backend.play().is_err());
+        assert!(backend.get_position().is_err());
+        assert!(backend.get_audio_tracks().unwrap().is_empty());
+        assert!(backend.latest_frame().is_none());
+    }
+
+    #[test]
+    fn frames_are_only_handed_out_once() {
+        let backend = loaded(60.0);
+        assert!(backend.latest_frame().is_some());
+        assert!(backend.latest_frame().is_none());
+        backend.frame_step_forward().unwrap();
+        let frame = backend.latest_frame().unwrap();
+        assert_eq!(
+            frame.buffer.len(),
+            (frame.width * frame.height * 4) as usize
+        );
+    }
+
+    #[test]
+    fn tracks_can_be_switched() {
+        let backend = loaded(60.0);
+        backend.set_subtitle_track(3).unwrap();
+        backend.set_audio_track(2).unwrap();
+        assert_eq!(backend.selected_subtitle_track(), 3);
+        assert_eq!(backend.selected_audio_track(), 2);
+        assert!(backend.set_audio_track(42).is_err());
+
+        backend
+            .add_subtitle_file(Path::new("/videos/movie.en.srt"))
+            .unwrap();
+        let external = backend.get_subtitle_tracks().unwrap().pop().unwrap();
+        assert!(external.external);
+        assert_eq!(external.title, "movie.en.srt");
+        assert_eq!(backend.selected_subtitle_track(), external.id);
+
+        backend.set_subtitle_delay(-0.25).unwrap();
+        backend.set_audio_delay(0.1).unwrap();
+        assert_eq!(backend.get_subtitle_delay().unwrap(), -0.25);
+        assert_eq!(backend.get_audio_delay().unwrap(), 0.1);
+
+        backend.load_url("https://example.com/video.mp4").unwrap();
+        assert_eq!(backend.selected_subtitle_track(), -1);
+        assert_eq!(backend.get_subtitle_delay().unwrap(), 0.0);
+        assert_eq!(backend.get_subtitle_tracks().unwrap().len(), 2);
+        assert_eq!(
+            backend.loaded_media().as_deref(),
+            Some("https://example.com/video.mp4")
+        );
+    }
+
+    #[test]
+    fn tracks_match_by_title_then_name_then_language() {
+        let tracks = [
+            (-1, "Disable", ""),
+            (1, "Track 1 - [English]", "Track 1 - [English]"),
+            (2, "Track 2 - [Japanese]", "Track 2 - [Japanese]"),
+            (
+                3,
+                "Track 3 - Commentary - [English]",
+                "Track 3 - Commentary - [English]",
+            ),
+        ];
+        let find = |title, lang| find_matching_track(&tracks, title, lang);
+        assert_eq!(find("Track 3 - Commentary - [English]", ""), Some(3));
+        assert_eq!(find("Track 4 - Commentary - [English]", ""), Some(3));
+        assert_eq!(
+            find("Track 1 - [Japanese]", "Track 1 - [Japanese]"),
+            Some(2)
+        );
+        assert_eq!(find("Japanese", "jpn"), None);
+        assert_eq!(find("Disable", ""), None);
+        assert_eq!(find("Track 5 - [French]", ""), None);
+
+        let simulated = [
+            (1, "Track 1 - English", "eng"),
+            (2, "Track 2 - Commentary", "eng"),
+        ];
+        assert_eq!(
+            find_matching_track(&simulated, "Commentary", "eng"),
+            Some(2)
+        );
+        assert_eq!(find_matching_track(&simulated, "Main", "ENG"), Some(1));
+    }
+}
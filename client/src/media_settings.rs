@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::json_store::{JsonStore, Timestamped};
+use crate::protocol::Bookmark;
+use crate::sync::get_data_directory;
+use crate::utils::unix_millis;
+
+/// Playback tweaks and bookmarks remembered for one video, keyed like the watch history
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaSettings {
+    /// Positive values show subtitles later
+    #[serde(default)]
+    pub subtitle_delay_ms: i64,
+    /// Positive values play audio later
+    #[serde(default)]
+    pub audio_delay_ms: i64,
+    /// Ordered by position; bookmarks from other members have an `author`
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub updated_at_ms: u64,
+}
+
+impl Timestamped for MediaSettings {
+    fn updated_at_ms(&self) -> u64 {
+        self.updated_at_ms
+    }
+}
+
+impl MediaSettings {
+    fn is_default(&self) -> bool {
+        self.subtitle_delay_ms == 0 && self.audio_delay_ms == 0 && self.bookmarks.is_empty()
+    }
+}
+
+/// Adds `bookmark`, or replaces the one with its id, keeping the list ordered by position
+pub fn insert_bookmark(bookmarks: &mut Vec<Bookmark>, bookmark: Bookmark) {
+    bookmarks.retain(|existing| existing.id != bookmark.id);
+    let index = bookmarks.partition_point(|existing| existing.position <= bookmark.position);
+    bookmarks.insert(index, bookmark);
+}
+
+/// JSON file of [`MediaSettings`] in the data directory
+pub struct MediaSettingsStore {
+    entries: JsonStore<MediaSettings>,
+}
+
+impl MediaSettingsStore {
+    pub fn load() -> Self {
+        Self::open(get_data_directory().map(|dir| dir.join("media_settings.json")))
+    }
+
+    fn open(path: Option<PathBuf>) -> Self {
+        Self {
+            entries: JsonStore::open(path, "media settings"),
+        }
+    }
+
+    pub fn get(&self, file_hash: &str) -> MediaSettings {
+        self.entries.get(file_hash).cloned().unwrap_or_default()
+    }
+
+    /// Stores `settings` for the video and writes the file; defaults are not kept
+    pub fn set(&mut self, file_hash: &str, mut settings: MediaSettings) {
+        if settings.is_default() {
+            self.entries.remove(file_hash);
+        } else {
+            settings.updated_at_ms = unix_millis();
+            self.entries.insert(file_hash, settings);
+        }
+        self.entries.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn offsets_survive_a_reload_and_defaults_are_dropped() {
+        let path = std::env::temp_dir().join(format!("hang-media-{}.json", uuid::Uuid::new_v4()));
+        let mut store = MediaSettingsStore::open(Some(path.clone()));
+        store.set(
+            "abc",
+            MediaSettings {
+                subtitle_delay_ms: -250,
+                audio_delay_ms: 100,
+                ..Default::default()
+            },
+        );
+        store.set("def", MediaSettings::default());
+
+        let reloaded = MediaSettingsStore::open(Some(path.clone()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.get("abc").subtitle_delay_ms, -250);
+        assert_eq!(reloaded.get("abc").audio_delay_ms, 100);
+        assert!(reloaded.entries.get("def").is_none());
+        assert_eq!(reloaded.get("missing"), MediaSettings::default());
+    }
+
+    #[test]
+    fn bookmarks_stay_ordered_and_replace_by_id() {
+        let bookmark = |id: &str, position: f64, label: &str| Bookmark {
+            id: id.to_string(),
+            position,
+            label: label.to_string(),
+            author: String::new(),
+        };
+        let mut bookmarks = Vec::new();
+        insert_bookmark(&mut bookmarks, bookmark("a", 300.0, "Chase"));
+        insert_bookmark(&mut bookmarks, bookmark("b", 60.0, "Intro"));
+        insert_bookmark(&mut bookmarks, bookmark("c", 900.0, "Fight"));
+        insert_bookmark(&mut bookmarks, bookmark("a", 1200.0, "Ending"));
+        let labels: Vec<&str> = bookmarks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, ["Intro", "Fight", "Ending"]);
+
+        let path = std::env::temp_dir().join(format!("hang-media-{}.json", uuid::Uuid::new_v4()));
+        let mut store = MediaSettingsStore::open(Some(path.clone()));
+        store.set(
+            "abc",
+            MediaSettings {
+                bookmarks: bookmarks.clone(),
+                ..Default::default()
+            },
+        );
+        let reloaded = MediaSettingsStore::open(Some(path.clone()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.get("abc").bookmarks, bookmarks);
+    }
+}
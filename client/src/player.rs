@@ -2,6 +2,7 @@ use libloading::{Library, Symbol};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
     env,
     ffi::{c_char, c_float, c_int, c_uint, c_void, CStr, CString},
     mem,
@@ -10,11 +11,14 @@ use std::{
     sync::Arc,
 };
 
-/// Wrapper around libVLC for video playback
+use crate::media::{AudioTrack, MediaBackend, SubtitleTrack, VideoFrame};
+
+/// libVLC-backed media player, loaded at runtime from the VLC installation
 pub struct VideoPlayer {
     instance: *mut libvlc_instance_t,
     media_player: *mut libvlc_media_player_t,
     current_file: Mutex<Option<String>>,
+    external_subtitles: Mutex<ExternalSubtitles>,
     frame_state: Arc<VideoFrameState>,
     callbacks_handle: *mut VideoFrameState,
 }
@@ -23,6 +27,7 @@ unsafe impl Send for VideoPlayer {}
 unsafe impl Sync for VideoPlayer {}
 
 impl VideoPlayer {
+    #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
     pub fn new(window_id: Option<i64>) -> Result<Self, String> {
         ensure_lib_loaded()?;
         let instance = unsafe { libvlc_new_instance()? };
@@ -48,15 +53,16 @@ impl VideoPlayer {
             instance,
             media_player,
             current_file: Mutex::new(None),
+            external_subtitles: Mutex::new(ExternalSubtitles::default()),
             callbacks_handle,
             frame_state,
         })
     }
+}
 
-    /// Load a video file
-    pub fn load_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+impl MediaBackend for VideoPlayer {
+    fn load_file(&self, path: &Path) -> Result<(), String> {
         let path_str = path
-            .as_ref()
             .to_str()
             .ok_or_else(|| "Invalid path encoding".to_string())?;
         let c_path =
@@ -69,11 +75,11 @@ impl VideoPlayer {
         }
 
         *self.current_file.lock() = Some(path_str.to_string());
+        *self.external_subtitles.lock() = ExternalSubtitles::default();
         Ok(())
     }
-    
-    /// Load a video from URL
-    pub fn load_url(&self, url: &str) -> Result<(), String> {
+
+    fn load_url(&self, url: &str) -> Result<(), String> {
         let c_url = CString::new(url).map_err(|_| "URL contains embedded NUL".to_string())?;
 
         unsafe {
@@ -83,36 +89,31 @@ impl VideoPlayer {
         }
 
         *self.current_file.lock() = Some(url.to_string());
+        *self.external_subtitles.lock() = ExternalSubtitles::default();
         Ok(())
     }
 
-    /// Play the video
-    pub fn play(&self) -> Result<(), String> {
+    fn play(&self) -> Result<(), String> {
         unsafe { libvlc_media_player_play(self.media_player) }
     }
 
-    /// Pause the video
-    pub fn pause(&self) -> Result<(), String> {
+    fn pause(&self) -> Result<(), String> {
         unsafe { libvlc_media_player_set_pause(self.media_player, true) }
     }
 
-    /// Stop playback
-    pub fn stop(&self) -> Result<(), String> {
+    fn stop(&self) -> Result<(), String> {
         unsafe { libvlc_media_player_stop(self.media_player) }
     }
 
-    /// Seek to a specific timestamp (in seconds)
-    pub fn seek(&self, timestamp: f64) -> Result<(), String> {
+    fn seek(&self, timestamp: f64) -> Result<(), String> {
         unsafe { libvlc_media_player_set_time(self.media_player, (timestamp * 1000.0) as i64) }
     }
 
-    /// Set playback speed
-    pub fn set_speed(&self, speed: f64) -> Result<(), String> {
+    fn set_speed(&self, speed: f64) -> Result<(), String> {
         unsafe { libvlc_media_player_set_rate(self.media_player, speed as c_float) }
     }
 
-    /// Get current playback position (in seconds)
-    pub fn get_position(&self) -> Result<f64, String> {
+    fn get_position(&self) -> Result<f64, String> {
         unsafe {
             libvlc_media_player_get_time(self.media_player)
                 .map(|ms| ms as f64 / 1000.0)
@@ -120,8 +121,7 @@ impl VideoPlayer {
         }
     }
 
-    /// Get video duration (in seconds)
-    pub fn get_duration(&self) -> Result<f64, String> {
+    fn get_duration(&self) -> Result<f64, String> {
         unsafe {
             let len = libvlc_media_player_get_length(self.media_player);
             if len <= 0 {
@@ -132,72 +132,113 @@ impl VideoPlayer {
         }
     }
 
-    /// Check if video is paused
-    pub fn is_paused(&self) -> Result<bool, String> {
+    fn is_paused(&self) -> Result<bool, String> {
         unsafe { Ok(!libvlc_media_player_is_playing(self.media_player)) }
     }
 
-    /// Get current playback speed
-    pub fn get_speed(&self) -> Result<f64, String> {
+    fn get_speed(&self) -> Result<f64, String> {
         unsafe { Ok(libvlc_media_player_get_rate(self.media_player) as f64) }
     }
 
-    /// Set volume (0-100)
-    pub fn set_volume(&self, volume: f64) -> Result<(), String> {
+    fn set_volume(&self, volume: f64) -> Result<(), String> {
         let clamped = volume.clamp(0.0, 100.0) as c_int;
         unsafe { libvlc_audio_set_volume(self.media_player, clamped) }
     }
 
-    /// Get volume (0-100)
-    pub fn get_volume(&self) -> Result<f64, String> {
+    fn get_volume(&self) -> Result<f64, String> {
         unsafe { libvlc_audio_get_volume(self.media_player).map(|v| v as f64) }
     }
 
-    /// Get available audio tracks
-    pub fn get_audio_tracks(&self) -> Result<Vec<AudioTrack>, String> {
+    fn get_audio_tracks(&self) -> Result<Vec<AudioTrack>, String> {
         unsafe { enumerate_tracks(libvlc_audio_get_track_description, self.media_player) }
     }
 
-    /// Set current audio track
-    pub fn set_audio_track(&self, track_id: i64) -> Result<(), String> {
+    fn set_audio_track(&self, track_id: i64) -> Result<(), String> {
         unsafe { libvlc_audio_set_track(self.media_player, track_id as c_int) }
     }
 
-    /// Get available subtitle tracks
-    pub fn get_subtitle_tracks(&self) -> Result<Vec<SubtitleTrack>, String> {
-        unsafe { enumerate_tracks(libvlc_video_get_spu_description, self.media_player) }.map(
-            |tracks| {
-                tracks
-                    .into_iter()
-                    .map(|t| SubtitleTrack {
-                        id: t.id,
-                        title: t.title.clone(),
-                        lang: t.lang,
-                    })
-                    .collect()
-            },
-        )
-    }
-
-    /// Set current subtitle track (use -1 to disable)
-    pub fn set_subtitle_track(&self, track_id: i64) -> Result<(), String> {
+    fn get_subtitle_tracks(&self) -> Result<Vec<SubtitleTrack>, String> {
+        let tracks =
+            unsafe { enumerate_tracks(libvlc_video_get_spu_description, self.media_player) }?;
+        let mut external = self.external_subtitles.lock();
+        external.claim_new_tracks(tracks.iter().map(|t| t.id));
+        Ok(tracks
+            .into_iter()
+            .map(|t| match external.labels.get(&t.id) {
+                Some(name) => SubtitleTrack {
+                    id: t.id,
+                    title: name.clone(),
+                    lang: String::new(),
+                    external: true,
+                },
+                None => SubtitleTrack {
+                    id: t.id,
+                    title: t.title.clone(),
+                    lang: t.lang,
+                    external: false,
+                },
+            })
+            .collect())
+    }
+
+    fn set_subtitle_track(&self, track_id: i64) -> Result<(), String> {
         unsafe { libvlc_video_set_spu(self.media_player, track_id as c_int) }
     }
 
-    /// Frame step forward
-    pub fn frame_step_forward(&self) -> Result<(), String> {
+    fn add_subtitle_file(&self, path: &Path) -> Result<(), String> {
+        let uri = url::Url::from_file_path(path)
+            .map_err(|_| "Subtitle path must be absolute".to_string())?;
+        let c_uri =
+            CString::new(uri.as_str()).map_err(|_| "Path contains embedded NUL".to_string())?;
+        let existing =
+            unsafe { enumerate_tracks(libvlc_video_get_spu_description, self.media_player) }?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| uri.to_string());
+
+        unsafe {
+            libvlc_media_player_add_slave(
+                self.media_player,
+                LIBVLC_MEDIA_SLAVE_TYPE_SUBTITLE,
+                c_uri.as_ptr(),
+                true,
+            )?
+        };
+        let mut external = self.external_subtitles.lock();
+        external.known.extend(existing.iter().map(|t| t.id));
+        external.pending.push_back(name);
+        Ok(())
+    }
+
+    fn set_subtitle_delay(&self, seconds: f64) -> Result<(), String> {
+        unsafe { libvlc_video_set_spu_delay(self.media_player, (seconds * 1_000_000.0) as i64) }
+    }
+
+    fn get_subtitle_delay(&self) -> Result<f64, String> {
+        unsafe { libvlc_video_get_spu_delay(self.media_player).map(|us| us as f64 / 1_000_000.0) }
+    }
+
+    fn set_audio_delay(&self, seconds: f64) -> Result<(), String> {
+        unsafe { libvlc_audio_set_delay(self.media_player, (seconds * 1_000_000.0) as i64) }
+    }
+
+    fn get_audio_delay(&self) -> Result<f64, String> {
+        unsafe { libvlc_audio_get_delay(self.media_player).map(|us| us as f64 / 1_000_000.0) }
+    }
+
+    fn frame_step_forward(&self) -> Result<(), String> {
         unsafe { libvlc_media_player_next_frame(self.media_player) }
     }
 
-    /// Frame step backward (approximate using a short reverse seek)
-    pub fn frame_step_backward(&self) -> Result<(), String> {
+    // libVLC can't step backwards; approximate with a short reverse seek
+    fn frame_step_backward(&self) -> Result<(), String> {
         let current = unsafe { libvlc_media_player_get_time(self.media_player).unwrap_or(0) };
         let target = (current - 40).max(0);
         unsafe { libvlc_media_player_set_time(self.media_player, target) }
     }
 
-    /// Retrieve latest RGB frame if available
-    pub fn latest_frame(&self) -> Option<VideoFrame> {
+    fn latest_frame(&self) -> Option<VideoFrame> {
         self.frame_state.grab_frame()
     }
 }
@@ -216,25 +257,33 @@ impl Drop for VideoPlayer {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct AudioTrack {
-    pub id: i64,
-    pub title: String,
-    pub lang: String,
-}
-
-#[derive(Debug, Clone)]
-pub struct SubtitleTrack {
-    pub id: i64,
-    pub title: String,
-    pub lang: String,
-}
-
-#[derive(Clone)]
-pub struct VideoFrame {
-    pub width: u32,
-    pub height: u32,
-    pub buffer: Vec<u8>,
+/// Names for subtitle tracks libVLC created from added files. libVLC adds the track
+/// asynchronously and gives it a generic title, so the file name is attached to the first
+/// unknown track id that shows up after the file was added.
+#[derive(Default)]
+struct ExternalSubtitles {
+    /// Track ids seen before the pending files were added
+    known: HashSet<i64>,
+    /// File names still waiting for their track, oldest first
+    pending: VecDeque<String>,
+    labels: HashMap<i64, String>,
+}
+
+impl ExternalSubtitles {
+    fn claim_new_tracks(&mut self, ids: impl Iterator<Item = i64>) {
+        let mut ids: Vec<i64> = ids.collect();
+        ids.sort_unstable();
+        for id in ids {
+            if self.pending.is_empty() {
+                break;
+            }
+            if id >= 0 && !self.known.contains(&id) && !self.labels.contains_key(&id) {
+                if let Some(name) = self.pending.pop_front() {
+                    self.labels.insert(id, name);
+                }
+            }
+        }
+    }
 }
 
 struct VideoFrameState {
@@ -402,8 +451,8 @@ unsafe extern "C" fn video_format_setup(
         *opaque = state_ptr as *mut c_void;
     }
 
-    let w = unsafe { *width } as u32;
-    let h = unsafe { *height } as u32;
+    let w = unsafe { *width };
+    let h = unsafe { *height };
     if w == 0 || h == 0 {
         return 0;
     }
@@ -479,7 +528,7 @@ fn libvlc_library() -> Result<&'static Library, String> {
             let lib = unsafe { load_library()? };
             Ok(Box::leak(Box::new(lib)))
         })
-        .map(|lib| *lib)
+        .copied()
 }
 
 unsafe fn load_library() -> Result<Library, String> {
@@ -778,6 +827,61 @@ unsafe fn libvlc_video_set_spu(
     }
 }
 
+/// Delays are in microseconds; libVLC rejects them while no media is playing
+unsafe fn libvlc_video_set_spu_delay(
+    player: *mut libvlc_media_player_t,
+    delay_us: i64,
+) -> Result<(), String> {
+    let sym: Symbol<unsafe extern "C" fn(*mut libvlc_media_player_t, i64) -> c_int> =
+        get_symbol(b"libvlc_video_set_spu_delay\0")?;
+    if sym(player, delay_us) == 0 {
+        Ok(())
+    } else {
+        Err(format_error("Failed to set subtitle delay"))
+    }
+}
+
+unsafe fn libvlc_video_get_spu_delay(player: *mut libvlc_media_player_t) -> Result<i64, String> {
+    let sym: Symbol<unsafe extern "C" fn(*mut libvlc_media_player_t) -> i64> =
+        get_symbol(b"libvlc_video_get_spu_delay\0")?;
+    Ok(sym(player))
+}
+
+unsafe fn libvlc_audio_set_delay(
+    player: *mut libvlc_media_player_t,
+    delay_us: i64,
+) -> Result<(), String> {
+    let sym: Symbol<unsafe extern "C" fn(*mut libvlc_media_player_t, i64) -> c_int> =
+        get_symbol(b"libvlc_audio_set_delay\0")?;
+    if sym(player, delay_us) == 0 {
+        Ok(())
+    } else {
+        Err(format_error("Failed to set audio delay"))
+    }
+}
+
+unsafe fn libvlc_audio_get_delay(player: *mut libvlc_media_player_t) -> Result<i64, String> {
+    let sym: Symbol<unsafe extern "C" fn(*mut libvlc_media_player_t) -> i64> =
+        get_symbol(b"libvlc_audio_get_delay\0")?;
+    Ok(sym(player))
+}
+
+unsafe fn libvlc_media_player_add_slave(
+    player: *mut libvlc_media_player_t,
+    kind: c_int,
+    uri: *const c_char,
+    select: bool,
+) -> Result<(), String> {
+    let sym: Symbol<
+        unsafe extern "C" fn(*mut libvlc_media_player_t, c_int, *const c_char, bool) -> c_int,
+    > = get_symbol(b"libvlc_media_player_add_slave\0")?;
+    if sym(player, kind, uri, select) == 0 {
+        Ok(())
+    } else {
+        Err(format_error("Failed to add subtitle file"))
+    }
+}
+
 unsafe fn libvlc_track_description_list_release(list: *mut libvlc_track_description_t) {
     if let Ok(sym) = get_symbol::<unsafe extern "C" fn(*mut libvlc_track_description_t)>(
         b"libvlc_track_description_list_release\0",
@@ -868,6 +972,9 @@ fn format_error(action: &str) -> String {
     action.to_string()
 }
 
+/// `libvlc_media_slave_type_subtitle`
+const LIBVLC_MEDIA_SLAVE_TYPE_SUBTITLE: c_int = 0;
+
 #[repr(C)]
 struct libvlc_instance_t {
     _private: [u8; 0],
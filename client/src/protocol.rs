@@ -11,21 +11,76 @@ pub enum Message {
         passcode: Option<String>,
         display_name: Option<String>,
         capacity: Option<usize>,
+        /// Present when the room should be advertised in the public directory
+        #[serde(default)]
+        listing: Option<RoomListing>,
+        /// Vanity code such as `friday-anime`; a generated code is used if it is taken
+        #[serde(default)]
+        requested_code: Option<String>,
+        /// Reserve the room for a watch party starting later
+        #[serde(default)]
+        schedule: Option<RoomSchedule>,
+        /// Seats for spectators, on top of `capacity`
+        #[serde(default)]
+        spectator_capacity: Option<usize>,
+        /// Members follow the host's audio and subtitle track choices
+        #[serde(default)]
+        sync_tracks: bool,
     },
     JoinRoom {
         room_id: String,
         file_hash: String,
         passcode: Option<String>,
         display_name: Option<String>,
+        /// Watch without playback control, using a spectator seat
+        #[serde(default)]
+        spectator: bool,
+        /// Invite token from the host, accepted instead of the passcode
+        #[serde(default)]
+        invite: Option<String>,
     },
     ResumeSession {
         token: String,
         display_name: Option<String>,
     },
     LeaveRoom,
+    ListPublicRooms,
     SyncCommand(SyncCommand),
+    /// Host mints an invite token so links don't have to carry the passcode
+    CreateInvite {
+        /// Joins the token admits before it stops working; unlimited if absent
+        #[serde(default)]
+        max_uses: Option<u32>,
+        #[serde(default)]
+        expires_in_secs: Option<u64>,
+    },
+    RevokeInvite {
+        token: String,
+    },
+    /// Host's current tracks, for rooms created with `sync_tracks`
+    SelectTracks {
+        selection: TrackSelection,
+    },
+    /// Offer a text subtitle file to everyone else in the room; the server relays it without
+    /// keeping a copy
+    ShareSubtitle {
+        file_name: String,
+        content: String,
+    },
+    /// Mark a moment for the whole room; the server keeps the room's bookmarks for members
+    /// who join later. Sharing a bookmark id again replaces it.
+    ShareBookmark {
+        bookmark: Bookmark,
+    },
 
     // Server -> Client
+    /// Sent once after connecting so clients can validate input against this server
+    ServerHello {
+        room_code_format: RoomCodeFormat,
+        /// Largest subtitle file `ShareSubtitle` accepts, in bytes; 0 when sharing is disabled
+        #[serde(default)]
+        max_subtitle_bytes: usize,
+    },
     RoomCreated {
         room_id: String,
         client_id: Uuid,
@@ -34,6 +89,14 @@ pub enum Message {
         resume_token: String,
         capacity: usize,
         display_name: String,
+        #[serde(default)]
+        schedule: Option<RoomSchedule>,
+        #[serde(default)]
+        sync_tracks: bool,
+        /// Read-only credential for the room's event feed; unlike `resume_token` it never
+        /// grants a seat, so it is safe to put in a URL
+        #[serde(default)]
+        feed_token: Option<String>,
     },
     RoomJoined {
         room_id: String,
@@ -44,6 +107,31 @@ pub enum Message {
         resume_token: String,
         capacity: usize,
         display_name: String,
+        #[serde(default)]
+        schedule: Option<RoomSchedule>,
+        #[serde(default)]
+        spectator: bool,
+        #[serde(default)]
+        sync_tracks: bool,
+        /// The host's latest track choice when `sync_tracks` is on
+        #[serde(default)]
+        track_selection: Option<TrackSelection>,
+        /// Bookmarks members have shared with the room so far
+        #[serde(default)]
+        bookmarks: Vec<Bookmark>,
+        /// Read-only credential for the room's event feed
+        #[serde(default)]
+        feed_token: Option<String>,
+    },
+    InviteCreated {
+        room_id: String,
+        token: String,
+        max_uses: Option<u32>,
+        /// Unix time in milliseconds after which the token is rejected
+        expires_at_ms: Option<u64>,
+    },
+    InviteRevoked {
+        token: String,
     },
     RoomLeft,
     RoomNotFound,
@@ -57,16 +145,88 @@ pub enum Message {
         from_client: Uuid,
         command: SyncCommand,
     },
+    /// The host switched tracks in a room with `sync_tracks`
+    TracksSelected {
+        selection: TrackSelection,
+    },
+    /// Subtitle file another member shared with the room
+    SubtitleShared {
+        from_client: Uuid,
+        display_name: String,
+        file_name: String,
+        content: String,
+    },
+    /// Bookmark another member shared with the room
+    BookmarkShared {
+        from_client: Uuid,
+        bookmark: Bookmark,
+    },
     RoomMemberUpdate {
         room_id: String,
         members: Vec<MemberSummary>,
         capacity: usize,
     },
+    /// A scheduled watch party reached its start time; play from the beginning
+    ScheduledStart {
+        room_id: String,
+    },
+    PublicRoomList {
+        rooms: Vec<PublicRoomSummary>,
+    },
+    /// The server is going away; clients should reconnect after the given delay
+    ServerShuttingDown {
+        reconnect_after_ms: u64,
+    },
+    /// An operator closed the room; every member has been removed
+    RoomClosed {
+        room_id: String,
+        reason: Option<String>,
+    },
+    /// An operator removed this client from its room
+    Kicked {
+        reason: Option<String>,
+    },
+    /// Announcement from the server operator
+    ServerNotice {
+        message: String,
+    },
     Error {
         message: String,
     },
 }
 
+/// Audio and subtitle tracks picked by the host. Track ids differ between copies of a video,
+/// so tracks are named by their label instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackSelection {
+    /// `None` leaves each member's audio track alone
+    #[serde(default)]
+    pub audio: Option<TrackLabel>,
+    /// `None` turns subtitles off
+    #[serde(default)]
+    pub subtitle: Option<TrackLabel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackLabel {
+    pub title: String,
+    #[serde(default)]
+    pub lang: String,
+}
+
+/// Labelled moment in the room's video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// Chosen by the member who created it, so copies can be matched up
+    pub id: String,
+    /// Seconds from the start of the video
+    pub position: f64,
+    pub label: String,
+    /// Display name of the member who shared it; filled in by the server
+    #[serde(default)]
+    pub author: String,
+}
+
 /// Synchronization commands for video playback
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action")]
@@ -83,4 +243,60 @@ pub struct MemberSummary {
     pub client_id: Uuid,
     pub display_name: String,
     pub is_host: bool,
+    #[serde(default)]
+    pub is_spectator: bool,
+}
+
+/// Title and description shown for rooms in the public directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomListing {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Directory entry for a public room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicRoomSummary {
+    pub room_id: String,
+    pub title: String,
+    pub description: String,
+    pub host_name: String,
+    /// Members holding a regular seat; spectators are not included
+    pub member_count: usize,
+    pub capacity: usize,
+    pub passcode_protected: bool,
+    pub is_playing: bool,
+    /// Start time of a scheduled watch party, in Unix milliseconds
+    #[serde(default)]
+    pub starts_at_ms: Option<u64>,
+}
+
+/// Shape of the room codes issued and accepted by the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomCodeFormat {
+    /// Characters used in generated codes
+    pub charset: String,
+    /// Number of characters in a generated code, excluding separators
+    pub length: usize,
+    /// Generated codes are split into hyphen-separated groups of this size
+    pub group_size: usize,
+    /// Whether hosts may request a vanity code
+    pub allow_vanity: bool,
+    pub vanity_min_len: usize,
+    pub vanity_max_len: usize,
+}
+
+/// Planned start of a watch party reserved ahead of time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSchedule {
+    /// Unix time in milliseconds
+    pub starts_at_ms: u64,
+    pub title: String,
+    /// File name or URL the party will watch
+    #[serde(default)]
+    pub media: Option<String>,
+    /// Set by the server once playback has been triggered
+    #[serde(default)]
+    pub started: bool,
 }
@@ -0,0 +1,172 @@
+use crate::protocol::RoomCodeFormat;
+
+/// Matches servers that predate `ServerHello`: six digits as `123-456`, no vanity codes
+impl Default for RoomCodeFormat {
+    fn default() -> Self {
+        Self {
+            charset: "0123456789".to_string(),
+            length: 6,
+            group_size: 3,
+            allow_vanity: false,
+            vanity_min_len: 3,
+            vanity_max_len: 32,
+        }
+    }
+}
+
+impl RoomCodeFormat {
+    /// Cleans up text as the user types it into a room code field
+    pub fn sanitize_input(&self, input: &str) -> String {
+        let lowered = input.to_lowercase();
+        let has_foreign = lowered
+            .chars()
+            .any(|ch| ch != '-' && !ch.is_whitespace() && !self.charset.contains(ch));
+        let has_letter = lowered.chars().any(|ch| ch.is_alphabetic());
+
+        if self.allow_vanity && (has_foreign || has_letter) {
+            return self.sanitize_vanity(&lowered);
+        }
+
+        let compact: String = lowered
+            .chars()
+            .filter(|ch| self.charset.contains(*ch))
+            .take(self.length)
+            .collect();
+        self.group(&compact)
+    }
+
+    /// Normalizes a code before sending it, mirroring the server's canonical form
+    pub fn canonicalize(&self, input: &str) -> String {
+        let lowered = input.trim().to_lowercase();
+        let compact: String = lowered
+            .chars()
+            .filter(|ch| *ch != '-' && !ch.is_whitespace())
+            .collect();
+        if self.is_generated_shape(&compact) {
+            self.group(&compact)
+        } else {
+            lowered
+        }
+    }
+
+    /// Whether the code could name a room on this server
+    pub fn is_valid(&self, code: &str) -> bool {
+        let canonical = self.canonicalize(code);
+        let compact: String = canonical.chars().filter(|ch| *ch != '-').collect();
+        self.is_generated_shape(&compact) || self.is_valid_vanity(&canonical)
+    }
+
+    pub fn is_valid_vanity(&self, code: &str) -> bool {
+        let code = code.trim();
+        let len = code.chars().count();
+        self.allow_vanity
+            && (self.vanity_min_len..=self.vanity_max_len).contains(&len)
+            && code
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+            && !code.starts_with('-')
+            && !code.ends_with('-')
+            && !code.contains("--")
+            && code.chars().any(|ch| ch.is_ascii_lowercase())
+    }
+
+    /// Sample code for hints and error messages, e.g. `123-456` or `abcd-efgh`
+    pub fn example(&self) -> String {
+        let charset: Vec<char> = self.charset.chars().collect();
+        let start = charset
+            .iter()
+            .position(|ch| ch.is_alphabetic())
+            .unwrap_or(1.min(charset.len().saturating_sub(1)));
+        let compact: String = charset
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(self.length)
+            .collect();
+        self.group(&compact)
+    }
+
+    fn is_generated_shape(&self, compact: &str) -> bool {
+        compact.chars().count() == self.length && compact.chars().all(|ch| self.charset.contains(ch))
+    }
+
+    fn sanitize_vanity(&self, lowered: &str) -> String {
+        let mut cleaned = String::with_capacity(lowered.len());
+        for ch in lowered.chars() {
+            let ch = if ch.is_whitespace() || ch == '_' { '-' } else { ch };
+            let allowed = ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-';
+            if !allowed || (ch == '-' && (cleaned.is_empty() || cleaned.ends_with('-'))) {
+                continue;
+            }
+            if cleaned.chars().count() >= self.vanity_max_len {
+                break;
+            }
+            cleaned.push(ch);
+        }
+        cleaned
+    }
+
+    fn group(&self, compact: &str) -> String {
+        let chars: Vec<char> = compact.chars().collect();
+        chars
+            .chunks(self.group_size.max(1))
+            .map(|chunk| chunk.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alphanumeric() -> RoomCodeFormat {
+        RoomCodeFormat {
+            charset: "23456789abcdefghjkmnpqrstuvwxyz".to_string(),
+            length: 8,
+            group_size: 4,
+            allow_vanity: true,
+            ..RoomCodeFormat::default()
+        }
+    }
+
+    #[test]
+    fn test_legacy_format() {
+        let format = RoomCodeFormat::default();
+        assert_eq!(format.sanitize_input("12"), "12");
+        assert_eq!(format.sanitize_input("1234"), "123-4");
+        assert_eq!(format.sanitize_input("12a3-4567 89"), "123-456");
+        assert!(format.is_valid("123-456"));
+        assert!(format.is_valid(" 123456 "));
+        assert!(!format.is_valid("123-45"));
+        assert!(!format.is_valid("friday-anime"));
+        assert_eq!(format.example(), "123-456");
+    }
+
+    #[test]
+    fn test_alphanumeric_codes() {
+        let format = alphanumeric();
+        assert_eq!(format.canonicalize("K7DQ M3XA"), "k7dq-m3xa");
+        assert_eq!(format.canonicalize("k7dqm3xa"), "k7dq-m3xa");
+        assert!(format.is_valid("K7DQM3XA"));
+        assert_eq!(format.example(), "abcd-efgh");
+    }
+
+    #[test]
+    fn test_vanity_codes() {
+        let format = alphanumeric();
+        assert_eq!(format.sanitize_input("Friday Anime!"), "friday-anime");
+        assert_eq!(format.sanitize_input("--movie__night"), "movie-night");
+        assert!(format.is_valid("friday-anime"));
+        assert!(!format.is_valid("-friday"));
+        assert!(!format.is_valid("fr"));
+        assert!(!format.is_valid_vanity("123-456"));
+
+        let numeric = RoomCodeFormat {
+            allow_vanity: true,
+            ..RoomCodeFormat::default()
+        };
+        assert_eq!(numeric.sanitize_input("1234567"), "123-456");
+        assert_eq!(numeric.sanitize_input("Friday"), "friday");
+    }
+}
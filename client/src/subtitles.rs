@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// External subtitle formats libVLC can render
+pub const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt", "sub"];
+/// Text formats the server relays to other room members
+pub const SHAREABLE_SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt"];
+
+pub fn is_subtitle_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            SUBTITLE_EXTENSIONS
+                .iter()
+                .any(|allowed| ext.eq_ignore_ascii_case(allowed))
+        })
+}
+
+/// Subtitle files next to `video` that share its name, e.g. `Movie.srt` or `Movie.en.srt`
+/// for `Movie.mkv`. An exact name match comes first, the rest follow alphabetically.
+pub fn find_sidecar_subtitles(video: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem)) = (video.parent(), video.file_stem().and_then(|s| s.to_str()))
+    else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    }) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_subtitle_file(path))
+        .filter(|path| language_hint(stem, path).is_some())
+        .collect();
+    found.sort_by_key(|path| {
+        let hint = language_hint(stem, path).unwrap_or_default();
+        (
+            !hint.is_empty(),
+            path.file_name().map(|name| name.to_os_string()),
+        )
+    });
+    found
+}
+
+/// What sits between the video's name and the extension: `Some("en")` for `Movie.en.srt`,
+/// `Some("")` for `Movie.srt`, and `None` when the file belongs to a different video
+pub fn language_hint(video_stem: &str, subtitle: &Path) -> Option<String> {
+    let stem = subtitle.file_stem()?.to_str()?;
+    if stem.eq_ignore_ascii_case(video_stem) {
+        return Some(String::new());
+    }
+    let prefix = stem.get(..video_stem.len())?;
+    let rest = stem[video_stem.len()..].strip_prefix('.')?;
+    (prefix.eq_ignore_ascii_case(video_stem) && !rest.is_empty()).then(|| rest.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_exact_and_tagged_names_only() {
+        assert_eq!(
+            language_hint("Movie", Path::new("Movie.srt")).as_deref(),
+            Some("")
+        );
+        assert_eq!(
+            language_hint("Movie", Path::new("movie.EN.srt")).as_deref(),
+            Some("EN")
+        );
+        assert_eq!(
+            language_hint("Movie", Path::new("Movie.en.forced.ass")).as_deref(),
+            Some("en.forced")
+        );
+        assert_eq!(language_hint("Movie", Path::new("Movie 2.srt")), None);
+        assert_eq!(language_hint("Movie", Path::new("Mov.srt")), None);
+        assert_eq!(language_hint("Movie", Path::new("Movie..srt")), None);
+    }
+
+    #[test]
+    fn finds_sidecars_next_to_the_video() {
+        let dir = std::env::temp_dir().join(format!("hang-subs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "Movie.mkv",
+            "Movie.srt",
+            "Movie.de.vtt",
+            "Movie.nfo",
+            "Other.srt",
+            "Movie.ASS",
+        ] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let found: Vec<String> = find_sidecar_subtitles(&dir.join("Movie.mkv"))
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, ["Movie.ASS", "Movie.srt", "Movie.de.vtt"]);
+    }
+}
@@ -16,7 +16,7 @@ use tokio::{
 use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
 use uuid::Uuid;
 
-use crate::protocol::{Message, SyncCommand};
+use crate::protocol::{Bookmark, Message, RoomListing, RoomSchedule, SyncCommand, TrackSelection};
 
 pub struct SyncClient {
     inner: Arc<SyncClientState>,
@@ -29,6 +29,8 @@ struct SyncClientState {
     is_host: Mutex<bool>,
     session_store: SessionStore,
     stats: Mutex<SyncStats>,
+    /// Set when the server announced a planned shutdown before closing the socket
+    planned_reconnect: Mutex<Option<Duration>>,
 }
 
 #[derive(Default, Clone)]
@@ -47,6 +49,19 @@ struct SyncStats {
     endpoint_label: Option<String>,
 }
 
+/// Optional settings for a room created by this client
+#[derive(Debug, Clone, Default)]
+pub struct NewRoomOptions {
+    pub passcode: Option<String>,
+    pub display_name: Option<String>,
+    pub capacity: Option<usize>,
+    pub listing: Option<RoomListing>,
+    pub requested_code: Option<String>,
+    pub schedule: Option<RoomSchedule>,
+    pub spectator_capacity: Option<usize>,
+    pub sync_tracks: bool,
+}
+
 pub struct SyncStatsSnapshot {
     pub bytes_out: u64,
     pub bytes_in: u64,
@@ -83,6 +98,12 @@ pub fn get_data_directory() -> Option<PathBuf> {
     ProjectDirs::from("com", "hang", "Hang").map(|dirs| dirs.data_dir().to_path_buf())
 }
 
+impl Default for SyncClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SyncClient {
     pub fn new() -> Self {
         Self {
@@ -93,6 +114,7 @@ impl SyncClient {
                 is_host: Mutex::new(false),
                 session_store: SessionStore::new(),
                 stats: Mutex::new(SyncStats::default()),
+                planned_reconnect: Mutex::new(None),
             }),
         }
     }
@@ -109,6 +131,7 @@ impl SyncClient {
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
         let (tx, mut rx) = mpsc::unbounded_channel::<WsMessage>();
         *self.inner.tx.lock() = Some(tx.clone());
+        *self.inner.planned_reconnect.lock() = None;
 
         let (disconnect_tx, disconnect_rx) = oneshot::channel();
         let disconnect_signal = Arc::new(Mutex::new(Some(disconnect_tx)));
@@ -137,6 +160,10 @@ impl SyncClient {
                     Ok(WsMessage::Text(text)) => {
                         recv_inner.record_incoming(text.len() as u64);
                         if let Ok(parsed) = serde_json::from_str::<Message>(&text) {
+                            if let Message::ServerShuttingDown { reconnect_after_ms } = &parsed {
+                                *recv_inner.planned_reconnect.lock() =
+                                    Some(Duration::from_millis(*reconnect_after_ms));
+                            }
                             handler(parsed);
                         }
                     }
@@ -168,6 +195,12 @@ impl SyncClient {
         Ok(disconnect_rx)
     }
 
+    /// Close the socket without leaving the room, so the session can be resumed later
+    pub fn disconnect(&self) {
+        let _ = self.inner.enqueue_ws(WsMessage::Close(None));
+        self.inner.clear_transport();
+    }
+
     pub fn mark_connected(&self, label: &str) {
         self.inner.mark_connected(label);
     }
@@ -180,38 +213,79 @@ impl SyncClient {
         self.inner.snapshot()
     }
 
+    /// Returns the reconnect delay if the last disconnect was announced by the server
+    pub fn take_planned_reconnect(&self) -> Option<Duration> {
+        self.inner.planned_reconnect.lock().take()
+    }
+
     /// Create a new room
-    pub fn create_room(
-        &self,
-        file_hash: String,
-        passcode: Option<String>,
-        display_name: Option<String>,
-        capacity: Option<usize>,
-    ) -> Result<()> {
+    pub fn create_room(&self, file_hash: String, options: NewRoomOptions) -> Result<()> {
         self.send_message(Message::CreateRoom {
             file_hash,
-            passcode,
-            display_name,
-            capacity,
+            passcode: options.passcode,
+            display_name: options.display_name,
+            capacity: options.capacity,
+            listing: options.listing,
+            requested_code: options.requested_code,
+            schedule: options.schedule,
+            spectator_capacity: options.spectator_capacity,
+            sync_tracks: options.sync_tracks,
         })
     }
 
+    /// Ask the server for the public room directory
+    pub fn list_public_rooms(&self) -> Result<()> {
+        self.send_message(Message::ListPublicRooms)
+    }
+
     /// Join an existing room
     pub fn join_room(
         &self,
         room_id: String,
         file_hash: String,
         passcode: Option<String>,
+        invite: Option<String>,
         display_name: Option<String>,
+        spectator: bool,
     ) -> Result<()> {
         self.send_message(Message::JoinRoom {
             room_id,
             file_hash,
             passcode,
             display_name,
+            spectator,
+            invite,
         })
     }
 
+    /// Ask the server for an invite token for the room this client hosts
+    pub fn create_invite(&self, max_uses: Option<u32>, expires_in_secs: Option<u64>) -> Result<()> {
+        self.send_message(Message::CreateInvite {
+            max_uses,
+            expires_in_secs,
+        })
+    }
+
+    /// Invalidate an invite token handed out earlier
+    pub fn revoke_invite(&self, token: String) -> Result<()> {
+        self.send_message(Message::RevokeInvite { token })
+    }
+
+    /// Tell the room which tracks the host is using
+    pub fn select_tracks(&self, selection: TrackSelection) -> Result<()> {
+        self.send_message(Message::SelectTracks { selection })
+    }
+
+    /// Offer a subtitle file to the other members of the room
+    pub fn share_subtitle(&self, file_name: String, content: String) -> Result<()> {
+        self.send_message(Message::ShareSubtitle { file_name, content })
+    }
+
+    /// Add a bookmark to the room, or update one shared before
+    pub fn share_bookmark(&self, bookmark: Bookmark) -> Result<()> {
+        self.send_message(Message::ShareBookmark { bookmark })
+    }
+
     /// Leave current room
     pub fn leave_room(&self) -> Result<()> {
         self.send_message(Message::LeaveRoom)
@@ -1,7 +1,7 @@
 use eframe::egui;
 use parking_lot::Mutex;
 use std::{
-    collections::VecDeque,
+    collections::HashSet,
     env,
     path::{Path, PathBuf},
     sync::Arc,
@@ -10,12 +10,21 @@ use tokio::sync::mpsc::{error::TryRecvError, UnboundedReceiver, UnboundedSender}
 
 use crate::{
     constants::VERSION,
-    invite::{self, InviteLink, InviteSignal},
-    player::{VideoFrame, VideoPlayer},
-    protocol::{MemberSummary, Message, SyncCommand},
-    sync::{get_data_directory, is_portable_mode, PersistedSession, SyncClient, SyncStatsSnapshot},
+    invite::{self, InviteLink, InviteSignal, IssuedInvite},
+    media::{self, AudioTrack, MediaBackend, SubtitleTrack, VideoFrame},
+    media_settings::{self, MediaSettings, MediaSettingsStore},
+    protocol::{
+        Bookmark, MemberSummary, Message, PublicRoomSummary, RoomCodeFormat, RoomListing, RoomSchedule,
+        SyncCommand, TrackLabel, TrackSelection,
+    },
+    sync::{
+        get_data_directory, is_portable_mode, NewRoomOptions, PersistedSession, SyncClient,
+        SyncStatsSnapshot,
+    },
+    subtitles::{self, SHAREABLE_SUBTITLE_EXTENSIONS, SUBTITLE_EXTENSIONS},
     update::{self, UpdateInfo},
-    utils::{compute_file_hash, format_time},
+    utils::{compute_file_hash, compute_media_fingerprint, format_countdown, format_time, unix_millis},
+    watch_history::{WatchEntry, WatchHistory},
     youtube,
 };
 use uuid::Uuid;
@@ -26,13 +35,32 @@ const KEYBOARD_VOLUME_STEP: f64 = 5.0;
 const ROOM_CAPACITY_MIN: u32 = 2;
 const ROOM_CAPACITY_MAX: u32 = 32;
 const DEFAULT_ROOM_CAPACITY: u32 = 12;
+const SPECTATOR_CAPACITY_MAX: u32 = 500;
+const DEFAULT_SPECTATOR_CAPACITY: u32 = 100;
 const MAX_RECENT_FILES: usize = 10;
+/// How often the playback position is written to the watch history
+const WATCH_HISTORY_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// How long the "resume from" prompt waits before playback just continues from the start
+const RESUME_OFFER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
 const CURSOR_HIDE_DELAY_SECS: f64 = 3.0;
+/// How often, and how many times, to look for tracks libVLC hasn't listed yet, such as those of
+/// newly added subtitle files or of a video that only just started playing
+const TRACK_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+const TRACK_POLL_ATTEMPTS: u8 = 20;
+/// Shared subtitle files waiting for an answer; older offers are dropped beyond this
+const MAX_SUBTITLE_OFFERS: usize = 5;
+const DELAY_STEP_MS: i64 = 50;
+const DELAY_LIMIT_MS: i64 = 10_000;
+/// Matches the server's limit so shared labels aren't cut short
+const BOOKMARK_LABEL_MAX_CHARS: usize = 80;
+/// How close, in points, the pointer has to be to a bookmark tick on the seek bar
+const BOOKMARK_TICK_HIT_WIDTH: f32 = 4.0;
 
 /// Aspect ratio modes for video display
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[allow(dead_code)]
 pub enum AspectRatioMode {
+    #[default]
     Original,   // Keep original video aspect ratio
     Fit,        // Fit to window (same as Original)
     Fill,       // Fill entire area (may crop)
@@ -65,11 +93,34 @@ impl AspectRatioMode {
     
 }
 
+/// Tabs shown in the room dialog while not in a room
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum RoomDialogTab {
+    #[default]
+    Private,
+    Lobby,
+}
 
-impl Default for AspectRatioMode {
-    fn default() -> Self {
-        AspectRatioMode::Original
-    }
+/// Subtitle files handed to the player whose tracks haven't shown up yet
+struct PendingSubtitles {
+    names: Vec<String>,
+    /// File name of the track to switch to once it appears
+    select: Option<String>,
+    next_check: std::time::Instant,
+    checks_left: u8,
+}
+
+/// Track list refreshes scheduled while a room follows the host's tracks
+struct TrackPoll {
+    next_check: std::time::Instant,
+    checks_left: u8,
+}
+
+/// Subtitle file another room member shared, waiting to be loaded or dismissed
+struct SubtitleOffer {
+    from: String,
+    file_name: String,
+    content: String,
 }
 
 /// Toast notification
@@ -107,6 +158,11 @@ impl Toast {
         Self::new(message, egui::Color32::from_rgb(255, 100, 100))
     }
     
+    fn with_duration(mut self, duration_secs: f32) -> Self {
+        self.duration_secs = duration_secs;
+        self
+    }
+    
     fn is_expired(&self) -> bool {
         self.created_at.elapsed().as_secs_f32() > self.duration_secs
     }
@@ -124,7 +180,7 @@ impl Toast {
 
 pub struct HangApp {
     // Video player
-    player: Arc<VideoPlayer>,
+    player: Arc<dyn MediaBackend>,
 
     // Sync client
     sync: Arc<SyncClient>,
@@ -132,6 +188,9 @@ pub struct HangApp {
     // UI state
     video_file: Option<PathBuf>,
     video_hash: Option<String>,
+    /// Key for watch history and per-video settings: a content fingerprint for local files,
+    /// since `video_hash` only covers the file name
+    media_key: Option<String>,
     room_id_input: String,
     create_passcode_input: String,
     join_passcode_input: String,
@@ -160,13 +219,63 @@ pub struct HangApp {
     room_has_passcode: bool,
     room_capacity_input: u32,
     room_capacity_limit: Option<usize>,
+    spectator_capacity_input: u32,
+    join_as_spectator: bool,
+    sync_tracks_input: bool,
+    /// Members of this room follow the host's audio and subtitle tracks
+    room_sync_tracks: bool,
+    /// Host: the (audio, subtitle) ids last sent to the room
+    shared_tracks: Option<(i64, i64)>,
+    /// Member: the host's latest choice, and whether it still has to be applied
+    room_track_selection: Option<TrackSelection>,
+    track_selection_pending: bool,
+    track_poll: Option<TrackPoll>,
+    /// Joined without playback control; local controls don't sync
+    is_spectator: bool,
+    room_dialog_tab: RoomDialogTab,
+    room_code_format: RoomCodeFormat,
+    vanity_code_input: String,
+    pending_vanity_code: Option<String>,
+    list_room_publicly: bool,
+    public_title_input: String,
+    public_description_input: String,
+    public_rooms: Vec<PublicRoomSummary>,
+    public_rooms_loading: bool,
+    schedule_enabled: bool,
+    schedule_title_input: String,
+    schedule_media_input: String,
+    schedule_delay_hours: u32,
+    schedule_delay_minutes: u32,
+    /// Watch party this room is waiting for, until the server starts it
+    room_schedule: Option<RoomSchedule>,
 
     // Settings panel
     show_settings: bool,
-    audio_tracks: Vec<crate::player::AudioTrack>,
-    subtitle_tracks: Vec<crate::player::SubtitleTrack>,
+    audio_tracks: Vec<AudioTrack>,
+    subtitle_tracks: Vec<SubtitleTrack>,
     selected_audio: i64,
     selected_subtitle: i64,
+    pending_subtitles: Option<PendingSubtitles>,
+    /// External subtitle files added to the current video
+    subtitle_files: Vec<PathBuf>,
+    subtitle_offers: Vec<SubtitleOffer>,
+    /// Limit announced by the server in `ServerHello`; 0 when it can't relay subtitles
+    max_subtitle_bytes: usize,
+    /// Per-video delays, remembered across sessions by file hash
+    media_settings: MediaSettingsStore,
+    subtitle_delay_ms: i64,
+    audio_delay_ms: i64,
+    /// libVLC only accepts delays once playback starts, so they are retried each frame
+    delays_pending: bool,
+    /// Bookmarks of the current video, ordered by position
+    bookmarks: Vec<Bookmark>,
+    show_bookmarks: bool,
+    bookmark_label_input: String,
+    share_new_bookmarks: bool,
+    /// Own bookmarks already sent to the current room
+    shared_bookmark_ids: HashSet<String>,
+    /// Focus the label field the next time the bookmarks window is drawn
+    focus_bookmark_label: bool,
 
     // Sync control
     sync_enabled: bool,
@@ -175,10 +284,18 @@ pub struct HangApp {
     invite_rx: Option<UnboundedReceiver<InviteSignal>>,
     pending_invite: Option<InviteLink>,
     invite_modal_open: bool,
+    /// Invite tokens minted for the current room, newest last
+    issued_invites: Vec<IssuedInvite>,
+    invite_single_use: bool,
+    /// Lifetime of new invite links; 0 keeps them valid until revoked
+    invite_expiry_hours: u32,
+    /// Text to put on the clipboard on the next frame
+    pending_clipboard: Option<String>,
     sync_reconnect_tx: Option<UnboundedSender<()>>,
     saved_session: Option<PersistedSession>,
     auto_resume_attempted: bool,
     resume_in_progress: bool,
+    resume_is_automatic: bool,
     show_about: bool,
     show_network_overlay: bool,
 
@@ -205,8 +322,13 @@ pub struct HangApp {
     // Aspect ratio
     aspect_ratio_mode: AspectRatioMode,
     
-    // Recent files
-    recent_files: VecDeque<PathBuf>,
+    // Watch history
+    watch_history: WatchHistory,
+    last_history_save: std::time::Instant,
+    /// Title of the loaded media when it isn't just the file name, e.g. a YouTube video's
+    media_title: Option<String>,
+    /// Position from the history to offer resuming at, and when the offer appeared
+    resume_offer: Option<(f64, std::time::Instant)>,
     
     // Toast notifications
     toasts: Vec<Toast>,
@@ -223,7 +345,7 @@ pub struct HangApp {
 impl HangApp {
     pub fn new(
         _cc: &eframe::CreationContext,
-        player: Arc<VideoPlayer>,
+        player: Arc<dyn MediaBackend>,
         sync: Arc<SyncClient>,
         invite_rx: UnboundedReceiver<InviteSignal>,
         sync_reconnect_tx: UnboundedSender<()>,
@@ -234,6 +356,7 @@ impl HangApp {
             sync,
             video_file: None,
             video_hash: None,
+            media_key: None,
             room_id_input: String::new(),
             create_passcode_input: String::new(),
             join_passcode_input: String::new(),
@@ -258,21 +381,64 @@ impl HangApp {
             room_has_passcode: false,
             room_capacity_input: DEFAULT_ROOM_CAPACITY,
             room_capacity_limit: None,
+            spectator_capacity_input: DEFAULT_SPECTATOR_CAPACITY,
+            join_as_spectator: false,
+            sync_tracks_input: false,
+            room_sync_tracks: false,
+            shared_tracks: None,
+            room_track_selection: None,
+            track_selection_pending: false,
+            track_poll: None,
+            is_spectator: false,
+            room_dialog_tab: RoomDialogTab::default(),
+            room_code_format: RoomCodeFormat::default(),
+            vanity_code_input: String::new(),
+            pending_vanity_code: None,
+            list_room_publicly: false,
+            public_title_input: String::new(),
+            public_description_input: String::new(),
+            public_rooms: Vec::new(),
+            public_rooms_loading: false,
+            schedule_enabled: false,
+            schedule_title_input: String::new(),
+            schedule_media_input: String::new(),
+            schedule_delay_hours: 1,
+            schedule_delay_minutes: 0,
+            room_schedule: None,
             show_settings: false,
             audio_tracks: Vec::new(),
             subtitle_tracks: Vec::new(),
             selected_audio: -1,
             selected_subtitle: -1,
+            pending_subtitles: None,
+            subtitle_files: Vec::new(),
+            subtitle_offers: Vec::new(),
+            max_subtitle_bytes: 0,
+            media_settings: MediaSettingsStore::load(),
+            subtitle_delay_ms: 0,
+            audio_delay_ms: 0,
+            delays_pending: false,
+            bookmarks: Vec::new(),
+            show_bookmarks: false,
+            bookmark_label_input: String::new(),
+            share_new_bookmarks: true,
+            shared_bookmark_ids: HashSet::new(),
+            focus_bookmark_label: false,
             sync_enabled: true,
             sync_connected: false,
             last_sync_time: Arc::new(Mutex::new(std::time::Instant::now())),
             invite_rx: Some(invite_rx),
             pending_invite: None,
             invite_modal_open: false,
+            issued_invites: Vec::new(),
+            invite_single_use: false,
+            invite_expiry_hours: 24,
+            pending_clipboard: None,
             sync_reconnect_tx: Some(sync_reconnect_tx),
             saved_session: cached_session,
             auto_resume_attempted: false,
             resume_in_progress: false,
+            resume_is_automatic: false,
             show_about: false,
             show_network_overlay: false,
             update_info: None,
@@ -290,7 +456,10 @@ impl HangApp {
             video_texture: None,
             last_frame_size: None,
             aspect_ratio_mode: AspectRatioMode::default(),
-            recent_files: Self::load_recent_files(),
+            watch_history: WatchHistory::load(),
+            last_history_save: std::time::Instant::now(),
+            media_title: None,
+            resume_offer: None,
             toasts: Vec::new(),
             last_mouse_move: std::time::Instant::now(),
             last_mouse_pos: None,
@@ -309,6 +478,7 @@ impl HangApp {
     /// Clean up resources before app exit for smooth shutdown
     pub fn cleanup(&mut self) {
         tracing::info!("Cleaning up before exit...");
+        self.record_watch_progress(true);
         
         // Stop any playing video
         if self.is_playing {
@@ -323,8 +493,9 @@ impl HangApp {
         // Clear video texture
         self.video_texture = None;
         
-        // Leave room if connected
-        if self.in_room {
+        // Leave room if connected. Rooms waiting for a scheduled start are kept so the
+        // session can be resumed on the next launch.
+        if self.in_room && self.room_schedule.is_none() {
             let _ = self.sync.leave_room();
         }
         
@@ -334,54 +505,119 @@ impl HangApp {
         tracing::info!("Cleanup complete");
     }
     
-    // ===== Recent Files =====
-    
-    fn get_recent_files_path() -> Option<PathBuf> {
-        get_data_directory().map(|d| d.join("recent_files.txt"))
+    // ===== Watch History =====
+
+    /// Where the loaded media can be reopened from: its path, URL, or YouTube page
+    fn media_source(&self) -> Option<String> {
+        if self.is_youtube_video() {
+            return self.current_youtube_url.clone();
+        }
+        self.video_file
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned())
     }
-    
-    fn load_recent_files() -> VecDeque<PathBuf> {
-        if let Some(path) = Self::get_recent_files_path() {
-            if let Ok(contents) = std::fs::read_to_string(&path) {
-                return contents
-                    .lines()
-                    .filter_map(|line| {
-                        let p = PathBuf::from(line);
-                        if p.exists() { Some(p) } else { None }
-                    })
-                    .take(MAX_RECENT_FILES)
-                    .collect();
-            }
+
+    /// Writes the current position to the watch history every few seconds, or right away
+    /// when `force` is set
+    fn record_watch_progress(&mut self, force: bool) {
+        if !force && self.last_history_save.elapsed() < WATCH_HISTORY_SAVE_INTERVAL {
+            return;
+        }
+        self.last_history_save = std::time::Instant::now();
+        // Don't overwrite the saved position before the user has answered the resume prompt
+        if self.resume_offer.is_some() || self.duration <= 0.0 {
+            return;
         }
-        VecDeque::new()
+        let (Some(hash), Some(source)) = (self.media_key.clone(), self.media_source()) else {
+            return;
+        };
+        let title = self.media_title.clone().unwrap_or_else(|| {
+            self.video_file
+                .as_ref()
+                .filter(|_| !self.is_url_video())
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| source.clone())
+        });
+        self.watch_history.record(
+            &hash,
+            WatchEntry {
+                source,
+                title,
+                position: self.current_position,
+                duration: self.duration,
+                last_watched_ms: 0,
+                room_id: self.current_room_id.clone(),
+            },
+        );
+        self.watch_history.save();
     }
-    
-    fn save_recent_files(&self) {
-        if let Some(path) = Self::get_recent_files_path() {
-            if let Some(parent) = path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            let contents: String = self.recent_files
-                .iter()
-                .filter_map(|p| p.to_str())
-                .collect::<Vec<_>>()
-                .join("\n");
-            let _ = std::fs::write(path, contents);
+
+    fn open_from_history(&mut self, source: &str) {
+        if youtube::is_youtube_url(source) {
+            self.load_youtube_video(source);
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            self.url_input = source.to_string();
+            self.load_video_from_url();
+        } else if let Err(e) = self.load_video_from_path(Path::new(source)) {
+            self.toast_error(format!("Failed to open: {}", e));
         }
     }
-    
-    fn add_to_recent_files(&mut self, path: &Path) {
-        // Remove if already exists
-        self.recent_files.retain(|p| p != path);
-        // Add to front
-        self.recent_files.push_front(path.to_path_buf());
-        // Trim to max
-        while self.recent_files.len() > MAX_RECENT_FILES {
-            self.recent_files.pop_back();
+
+    /// Offers to continue where the media was left off, unless the room's host decides
+    fn offer_resume(&mut self) {
+        self.resume_offer = None;
+        if self.in_room && !self.is_host {
+            return;
+        }
+        let position = self
+            .media_key
+            .as_deref()
+            .and_then(|hash| self.watch_history.get(hash))
+            .and_then(WatchEntry::resume_position);
+        if let Some(position) = position {
+            self.resume_offer = Some((position, std::time::Instant::now()));
         }
-        self.save_recent_files();
     }
-    
+
+    fn render_resume_offer(&mut self, ctx: &egui::Context) {
+        let Some((position, shown_at)) = self.resume_offer else {
+            return;
+        };
+        if shown_at.elapsed() >= RESUME_OFFER_TIMEOUT {
+            self.resume_offer = None;
+            return;
+        }
+
+        let mut resume = false;
+        let mut dismiss = false;
+        egui::Window::new("Resume")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -96.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(format!("▶ Resume from {}", format_time(position)))
+                        .clicked()
+                    {
+                        resume = true;
+                    }
+                    if ui.button("Start over").clicked() {
+                        dismiss = true;
+                    }
+                });
+            });
+        ctx.request_repaint_after(std::time::Duration::from_millis(500));
+
+        if resume {
+            self.resume_offer = None;
+            self.seek(position);
+        } else if dismiss {
+            self.resume_offer = None;
+        }
+    }
+
     // ===== Toast Notifications =====
     
     fn show_toast(&mut self, toast: Toast) {
@@ -496,9 +732,13 @@ impl HangApp {
     fn load_video_from_path(&mut self, path: &Path) -> Result<(), String> {
         self.player.load_file(path)?;
         let hash = compute_file_hash(path).map_err(|e| e.to_string())?;
+        let media_key = compute_media_fingerprint(path).unwrap_or_else(|_| hash.clone());
 
+        self.record_watch_progress(true);
         self.video_file = Some(path.to_path_buf());
         self.video_hash = Some(hash);
+        self.media_key = Some(media_key);
+        self.on_media_loaded();
         self.video_texture = None;
         self.last_frame_size = None;
         self.status_message = format!(
@@ -509,9 +749,6 @@ impl HangApp {
         );
         self.error_message = None;
         
-        // Add to recent files
-        self.add_to_recent_files(path);
-        
         // Show toast notification
         let filename = path.file_name()
             .and_then(|n| n.to_str())
@@ -529,9 +766,408 @@ impl HangApp {
             self.is_playing = true;
         }
 
+        let sidecars = subtitles::find_sidecar_subtitles(path);
+        if !sidecars.is_empty() {
+            self.add_subtitle_files(&sidecars);
+        }
+
         Ok(())
     }
 
+    /// Adds subtitle files to the loaded video and switches to the first one once libVLC
+    /// exposes its track
+    fn add_subtitle_files(&mut self, paths: &[PathBuf]) {
+        let mut added = Vec::new();
+        for path in paths {
+            match self.player.add_subtitle_file(path) {
+                Ok(()) => {
+                    added.push(
+                        path.file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                    );
+                    self.subtitle_files.push(path.clone());
+                }
+                Err(e) => self.toast_error(format!("Failed to add subtitles: {}", e)),
+            }
+        }
+        let Some(first) = added.first().cloned() else {
+            return;
+        };
+        if added.len() == 1 {
+            self.toast_success(format!("Subtitles: {}", first));
+        } else {
+            self.toast_success(format!("Added {} subtitle files", added.len()));
+        }
+        self.pending_subtitles = Some(PendingSubtitles {
+            names: added,
+            select: Some(first),
+            next_check: std::time::Instant::now(),
+            checks_left: TRACK_POLL_ATTEMPTS,
+        });
+    }
+
+    fn poll_pending_subtitles(&mut self) {
+        let Some(pending) = self.pending_subtitles.as_mut() else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        if now < pending.next_check {
+            return;
+        }
+        pending.next_check = now + TRACK_POLL_INTERVAL;
+        pending.checks_left = pending.checks_left.saturating_sub(1);
+
+        if let Err(e) = self.refresh_media_tracks() {
+            self.error_message = Some(e);
+        }
+        let Some(pending) = self.pending_subtitles.as_mut() else {
+            return;
+        };
+        let arrived: Vec<&SubtitleTrack> = self
+            .subtitle_tracks
+            .iter()
+            .filter(|track| track.external && pending.names.contains(&track.title))
+            .collect();
+        pending.names.retain(|name| !arrived.iter().any(|track| &track.title == name));
+        let select = pending.select.as_ref().and_then(|name| {
+            arrived
+                .iter()
+                .find(|track| &track.title == name)
+                .map(|track| track.id)
+        });
+        if select.is_some() {
+            pending.select = None;
+        }
+        if pending.names.is_empty() || pending.checks_left == 0 {
+            self.pending_subtitles = None;
+        }
+        if let Some(track_id) = select {
+            self.selected_subtitle = track_id;
+            if let Err(e) = self.player.set_subtitle_track(track_id) {
+                self.error_message = Some(format!("Failed to switch subtitle track: {}", e));
+            }
+        }
+    }
+
+    fn start_track_poll(&mut self) {
+        self.track_poll = Some(TrackPoll {
+            next_check: std::time::Instant::now(),
+            checks_left: TRACK_POLL_ATTEMPTS,
+        });
+    }
+
+    fn tracks_listed(&self) -> bool {
+        self.audio_tracks.iter().any(|track| track.id >= 0)
+            || self.subtitle_tracks.iter().any(|track| track.id >= 0)
+    }
+
+    /// Keeps a room with track sync on the host's tracks: the host sends every change, and
+    /// members switch to the matching tracks once their player lists them
+    fn poll_synced_tracks(&mut self) {
+        if !self.room_sync_tracks || self.video_file.is_none() {
+            return;
+        }
+        if let Some(poll) = self.track_poll.as_mut() {
+            let now = std::time::Instant::now();
+            if now < poll.next_check {
+                return;
+            }
+            poll.next_check = now + TRACK_POLL_INTERVAL;
+            poll.checks_left = poll.checks_left.saturating_sub(1);
+            let exhausted = poll.checks_left == 0;
+            if let Err(e) = self.refresh_media_tracks() {
+                self.error_message = Some(e);
+            }
+            if !exhausted && !self.tracks_listed() {
+                return;
+            }
+            self.track_poll = None;
+        }
+
+        if self.is_host {
+            self.share_track_selection();
+        } else if self.track_selection_pending {
+            self.track_selection_pending = false;
+            if let Some(selection) = self.room_track_selection.clone() {
+                self.apply_track_selection(&selection);
+            }
+        }
+    }
+
+    fn share_track_selection(&mut self) {
+        let current = (self.selected_audio, self.selected_subtitle);
+        if self.shared_tracks == Some(current) || !self.tracks_listed() {
+            return;
+        }
+        let label = |title: &str, lang: &str| TrackLabel {
+            title: title.to_string(),
+            lang: lang.to_string(),
+        };
+        let selection = TrackSelection {
+            audio: self
+                .audio_tracks
+                .iter()
+                .find(|track| track.id >= 0 && track.id == self.selected_audio)
+                .map(|track| label(&track.title, &track.lang)),
+            subtitle: self
+                .subtitle_tracks
+                .iter()
+                .find(|track| track.id >= 0 && track.id == self.selected_subtitle)
+                .map(|track| label(&track.title, &track.lang)),
+        };
+        match self.sync.select_tracks(selection) {
+            Ok(()) => self.shared_tracks = Some(current),
+            Err(e) => self.error_message = Some(format!("Failed to sync tracks: {}", e)),
+        }
+    }
+
+    fn apply_track_selection(&mut self, selection: &TrackSelection) {
+        let mut missing = Vec::new();
+        if let Some(wanted) = &selection.audio {
+            let tracks: Vec<(i64, &str, &str)> = self
+                .audio_tracks
+                .iter()
+                .map(|track| (track.id, track.title.as_str(), track.lang.as_str()))
+                .collect();
+            match media::find_matching_track(&tracks, &wanted.title, &wanted.lang) {
+                Some(id) if id != self.selected_audio => {
+                    self.selected_audio = id;
+                    if let Err(e) = self.player.set_audio_track(id) {
+                        self.error_message = Some(format!("Failed to switch audio track: {}", e));
+                    }
+                }
+                Some(_) => {}
+                None => missing.push(format!("audio track \"{}\"", wanted.title)),
+            }
+        }
+        let subtitle = match &selection.subtitle {
+            None => Some(-1),
+            Some(wanted) => {
+                let tracks: Vec<(i64, &str, &str)> = self
+                    .subtitle_tracks
+                    .iter()
+                    .map(|track| (track.id, track.title.as_str(), track.lang.as_str()))
+                    .collect();
+                let found = media::find_matching_track(&tracks, &wanted.title, &wanted.lang);
+                if found.is_none() {
+                    missing.push(format!("subtitles \"{}\"", wanted.title));
+                }
+                found
+            }
+        };
+        if let Some(id) = subtitle.filter(|id| *id != self.selected_subtitle) {
+            self.selected_subtitle = id;
+            if let Err(e) = self.player.set_subtitle_track(id) {
+                self.error_message = Some(format!("Failed to switch subtitle track: {}", e));
+            }
+        }
+        if !missing.is_empty() {
+            self.toast_warning(format!(
+                "Host is using {}, which this video doesn't have",
+                missing.join(" and ")
+            ));
+        }
+    }
+
+    /// Starts per-video state over, restores the offsets and bookmarks remembered for the new
+    /// video, and offers to resume it
+    fn on_media_loaded(&mut self) {
+        self.subtitle_files.clear();
+        self.media_title = None;
+        self.current_position = 0.0;
+        self.duration = 0.0;
+        self.offer_resume();
+        if self.room_sync_tracks {
+            self.shared_tracks = None;
+            self.track_selection_pending = self.room_track_selection.is_some();
+            self.start_track_poll();
+        }
+        let settings = self
+            .media_key
+            .as_deref()
+            .map(|hash| self.media_settings.get(hash))
+            .unwrap_or_default();
+        self.subtitle_delay_ms = settings.subtitle_delay_ms;
+        self.audio_delay_ms = settings.audio_delay_ms;
+        self.delays_pending = true;
+        self.bookmarks = settings.bookmarks;
+    }
+
+    fn apply_pending_delays(&mut self) {
+        if !self.delays_pending {
+            return;
+        }
+        let subtitle = self
+            .player
+            .set_subtitle_delay(self.subtitle_delay_ms as f64 / 1000.0);
+        let audio = self.player.set_audio_delay(self.audio_delay_ms as f64 / 1000.0);
+        self.delays_pending = subtitle.is_err() || audio.is_err();
+    }
+
+    fn set_subtitle_delay(&mut self, delay_ms: i64) {
+        self.subtitle_delay_ms = delay_ms.clamp(-DELAY_LIMIT_MS, DELAY_LIMIT_MS);
+        self.delays_pending = true;
+        self.apply_pending_delays();
+    }
+
+    fn set_audio_delay(&mut self, delay_ms: i64) {
+        self.audio_delay_ms = delay_ms.clamp(-DELAY_LIMIT_MS, DELAY_LIMIT_MS);
+        self.delays_pending = true;
+        self.apply_pending_delays();
+    }
+
+    fn save_media_settings(&mut self) {
+        let Some(hash) = self.media_key.clone() else {
+            return;
+        };
+        self.media_settings.set(
+            &hash,
+            MediaSettings {
+                subtitle_delay_ms: self.subtitle_delay_ms,
+                audio_delay_ms: self.audio_delay_ms,
+                bookmarks: self.bookmarks.clone(),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn open_bookmarks(&mut self) {
+        self.show_bookmarks = true;
+        self.focus_bookmark_label = true;
+    }
+
+    /// Bookmarks the current position, sharing it with the room if that's switched on
+    fn add_bookmark(&mut self) {
+        if self.video_hash.is_none() {
+            return;
+        }
+        let label = match self.bookmark_label_input.trim() {
+            "" => format!("Bookmark at {}", format_time(self.current_position)),
+            label => label.to_string(),
+        };
+        let bookmark = Bookmark {
+            id: Uuid::new_v4().simple().to_string(),
+            position: self.current_position,
+            label,
+            author: String::new(),
+        };
+        let id = bookmark.id.clone();
+        self.bookmark_label_input.clear();
+        self.toast_success(format!(
+            "🔖 {} ({})",
+            bookmark.label,
+            format_time(bookmark.position)
+        ));
+        media_settings::insert_bookmark(&mut self.bookmarks, bookmark);
+        self.save_media_settings();
+        if self.in_room && self.share_new_bookmarks {
+            self.share_bookmark(&id);
+        }
+    }
+
+    fn share_bookmark(&mut self, id: &str) {
+        let Some(bookmark) = self.bookmarks.iter().find(|b| b.id == id).cloned() else {
+            return;
+        };
+        match self.sync.share_bookmark(bookmark) {
+            Ok(()) => {
+                self.shared_bookmark_ids.insert(id.to_string());
+            }
+            Err(e) => self.toast_error(format!("Failed to share bookmark: {}", e)),
+        }
+    }
+
+    /// Forgets a bookmark on this device; members it was shared with keep their copy
+    fn remove_bookmark(&mut self, id: &str) {
+        self.bookmarks.retain(|b| b.id != id);
+        self.save_media_settings();
+    }
+
+    /// Keeps bookmarks shared in the room alongside the video's own ones
+    fn merge_room_bookmarks(&mut self, bookmarks: Vec<Bookmark>) {
+        if bookmarks.is_empty() {
+            return;
+        }
+        for bookmark in bookmarks {
+            // Our own bookmarks come back signed when we rejoin; keep them as ours
+            let own = self
+                .bookmarks
+                .iter()
+                .any(|b| b.id == bookmark.id && b.author.is_empty());
+            if own {
+                self.shared_bookmark_ids.insert(bookmark.id);
+            } else {
+                media_settings::insert_bookmark(&mut self.bookmarks, bookmark);
+            }
+        }
+        self.save_media_settings();
+    }
+
+    fn select_subtitle_files(&mut self) {
+        let mut dialog = rfd::FileDialog::new().add_filter("Subtitles", SUBTITLE_EXTENSIONS);
+        if let Some(dir) = self.video_file.as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        if let Some(paths) = dialog.pick_files() {
+            self.add_subtitle_files(&paths);
+        }
+    }
+
+    /// Sends a subtitle file to the rest of the room and loads it here too if it isn't already
+    fn share_subtitle_file(&mut self) {
+        let mut dialog =
+            rfd::FileDialog::new().add_filter("Subtitles", SHAREABLE_SUBTITLE_EXTENSIONS);
+        if let Some(dir) = self.video_file.as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.pick_file() else {
+            return;
+        };
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.toast_error(format!("Failed to read subtitles: {}", e));
+                return;
+            }
+        };
+        if bytes.len() > self.max_subtitle_bytes {
+            self.toast_error(format!(
+                "Subtitle file is too large to share (limit {} KB)",
+                self.max_subtitle_bytes.div_ceil(1024)
+            ));
+            return;
+        }
+        // Legacy encodings are rare enough that replacing their odd characters is acceptable
+        let content = String::from_utf8(bytes)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if let Err(e) = self.sync.share_subtitle(file_name.clone(), content) {
+            self.toast_error(format!("Failed to share subtitles: {}", e));
+            return;
+        }
+        self.toast_success(format!("Shared {} with the room", file_name));
+        if !self.subtitle_files.contains(&path) {
+            self.add_subtitle_files(&[path]);
+        }
+    }
+
+    /// Writes a shared subtitle file to a temporary folder and loads it into the player
+    fn accept_subtitle_offer(&mut self, offer: SubtitleOffer) {
+        let dir = env::temp_dir()
+            .join("hang-subtitles")
+            .join(Uuid::new_v4().to_string());
+        let path = dir.join(&offer.file_name);
+        if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, offer.content)) {
+            self.toast_error(format!("Failed to save shared subtitles: {}", e));
+            return;
+        }
+        self.add_subtitle_files(&[path]);
+    }
+
     fn is_supported_video(path: &Path) -> bool {
         path.extension()
             .and_then(|ext| ext.to_str())
@@ -743,6 +1379,7 @@ impl HangApp {
             Ok(_) => {
                 self.status_message = format!("Attempting to resume room {}...", session.room_id);
                 self.resume_in_progress = true;
+                self.resume_is_automatic = automatic;
                 if automatic {
                     self.auto_resume_attempted = true;
                 }
@@ -792,6 +1429,20 @@ impl HangApp {
     }
 
     pub fn handle_connection_loss(&mut self) {
+        self.reset_connection_state();
+        self.status_message = "Sync connection lost. Reconnecting...".to_string();
+    }
+
+    /// The server announced a restart; wait quietly and let auto-resume pick the room back up
+    pub fn handle_planned_reconnect(&mut self, delay: std::time::Duration) {
+        self.reset_connection_state();
+        self.status_message = format!(
+            "Sync server is restarting. Reconnecting in {:.0}s...",
+            delay.as_secs_f32().ceil()
+        );
+    }
+
+    fn reset_connection_state(&mut self) {
         self.sync_connected = false;
         self.in_room = false;
         self.current_room_id = None;
@@ -799,15 +1450,13 @@ impl HangApp {
         self.participant_count = 0;
         self.member_roster.clear();
         self.room_capacity_limit = None;
-        self.status_message = "Sync connection lost. Reconnecting...".to_string();
         self.resume_in_progress = false;
     }
 
     fn process_invite_signal(&mut self, signal: InviteSignal) {
         match invite::parse_invite_url(&signal.url) {
             Some(link) => {
-                self.room_id_input = link.room_id.clone();
-                self.sanitize_room_code_input();
+                self.room_id_input = self.room_code_format.canonicalize(&link.room_id);
                 if let Some(passcode) = &link.passcode {
                     self.join_passcode_input = passcode.clone();
                 } else {
@@ -859,8 +1508,11 @@ impl HangApp {
                 // Use URL for hash computation
                 let hash = crate::utils::compute_string_hash(&url);
                 
+                self.record_watch_progress(true);
                 self.video_file = Some(PathBuf::from(&url));
+                self.media_key = Some(hash.clone());
                 self.video_hash = Some(hash);
+                self.on_media_loaded();
                 self.video_texture = None;
                 self.last_frame_size = None;
                 self.status_message = format!("Loading URL: {}", &url[..url.len().min(50)]);
@@ -928,8 +1580,16 @@ impl HangApp {
                         // Load the direct stream URL
                         match self.player.load_url(&video_info.stream_url) {
                             Ok(()) => {
+                                self.record_watch_progress(true);
                                 self.video_file = Some(PathBuf::from(format!("youtube://{}", video_id)));
+                                self.media_key = Some(hash.clone());
                                 self.video_hash = Some(hash);
+                                self.on_media_loaded();
+                                self.media_title = Some(video_info.title.clone());
+                                if pending_seek.is_some() {
+                                    // Same video at another quality; playback picks up where it was
+                                    self.resume_offer = None;
+                                }
                                 self.video_texture = None;
                                 self.last_frame_size = None;
                                 self.current_youtube_url = Some(url);  // Save URL for quality changes
@@ -1009,10 +1669,43 @@ impl HangApp {
             self.pending_room_passcode = passcode.clone();
             let display_name = self.sanitized_display_name();
             let capacity = Some(self.room_capacity_input as usize);
-            if let Err(e) = self
-                .sync
-                .create_room(hash.clone(), passcode, display_name, capacity)
-            {
+            let requested_code = Some(self.room_code_format.canonicalize(&self.vanity_code_input))
+                .filter(|code| self.room_code_format.allow_vanity && !code.is_empty());
+            self.pending_vanity_code = requested_code.clone();
+            let listing = self.list_room_publicly.then(|| RoomListing {
+                title: self.public_title_input.trim().to_string(),
+                description: self.public_description_input.trim().to_string(),
+            });
+            let schedule = if self.schedule_enabled {
+                let title = self.schedule_title_input.trim();
+                if title.is_empty() {
+                    self.error_message = Some("Give the watch party a title".into());
+                    return;
+                }
+                let delay_ms = (u64::from(self.schedule_delay_hours) * 60
+                    + u64::from(self.schedule_delay_minutes))
+                    * 60_000;
+                let media = self.schedule_media_input.trim();
+                Some(RoomSchedule {
+                    starts_at_ms: unix_millis() + delay_ms,
+                    title: title.to_string(),
+                    media: (!media.is_empty()).then(|| media.to_string()),
+                    started: false,
+                })
+            } else {
+                None
+            };
+            let options = NewRoomOptions {
+                passcode,
+                display_name,
+                capacity,
+                listing,
+                requested_code,
+                schedule,
+                spectator_capacity: Some(self.spectator_capacity_input as usize),
+                sync_tracks: self.sync_tracks_input,
+            };
+            if let Err(e) = self.sync.create_room(hash.clone(), options) {
                 self.error_message = Some(format!("Failed to create room: {}", e));
             } else {
                 self.status_message = "Creating room...".to_string();
@@ -1028,14 +1721,17 @@ impl HangApp {
             );
             return;
         }
-        let code = self.room_id_input.trim().to_string();
+        let code = self.room_code_format.canonicalize(&self.room_id_input);
         if self.video_hash.is_none() {
             self.error_message = Some("Load the same video before joining a room".into());
             return;
         }
 
-        if !Self::is_valid_room_code(&code) {
-            self.error_message = Some("Room code must look like 123-456".into());
+        if !self.is_valid_room_code(&code) {
+            self.error_message = Some(format!(
+                "Room code must look like {}",
+                self.room_code_format.example()
+            ));
             return;
         }
 
@@ -1043,10 +1739,19 @@ impl HangApp {
             let passcode = Self::normalize_passcode(&self.join_passcode_input);
             self.pending_room_passcode = passcode.clone();
             let display_name = self.sanitized_display_name();
-            if let Err(e) =
-                self.sync
-                    .join_room(code.clone(), hash.clone(), passcode.clone(), display_name)
-            {
+            let invite = self
+                .pending_invite
+                .as_ref()
+                .filter(|link| self.room_code_format.canonicalize(&link.room_id) == code)
+                .and_then(|link| link.invite.clone());
+            if let Err(e) = self.sync.join_room(
+                code.clone(),
+                hash.clone(),
+                passcode.clone(),
+                invite,
+                display_name,
+                self.join_as_spectator,
+            ) {
                 self.error_message = Some(format!("Failed to join room: {}", e));
             } else {
                 self.status_message = format!("Joining room {}...", code);
@@ -1054,6 +1759,41 @@ impl HangApp {
         }
     }
 
+    /// Asks the server for an invite token; the link is copied once it arrives
+    fn create_invite(&mut self) {
+        let max_uses = self.invite_single_use.then_some(1);
+        let expires_in_secs =
+            (self.invite_expiry_hours > 0).then(|| u64::from(self.invite_expiry_hours) * 3600);
+        if let Err(e) = self.sync.create_invite(max_uses, expires_in_secs) {
+            self.error_message = Some(format!("Failed to create invite: {}", e));
+        } else {
+            self.status_message = "Creating invite link...".to_string();
+        }
+    }
+
+    fn describe_invite(issued: &IssuedInvite) -> String {
+        let uses = match issued.max_uses {
+            Some(1) => "Single use".to_string(),
+            Some(uses) => format!("{} uses", uses),
+            None => "Unlimited".to_string(),
+        };
+        let expiry = match issued.expires_at_ms {
+            Some(expires_at_ms) => {
+                let minutes = expires_at_ms.saturating_sub(unix_millis()) / 60_000;
+                if minutes == 0 {
+                    "expired".to_string()
+                } else if minutes < 120 {
+                    format!("expires in {} min", minutes)
+                } else {
+                    format!("expires in {} h", minutes / 60)
+                }
+            }
+            None => "no expiry".to_string(),
+        };
+        let short = &issued.token[issued.token.len().saturating_sub(6)..];
+        format!("Invite …{}: {}, {}", short, uses, expiry)
+    }
+
     fn leave_room(&mut self) {
         if let Err(e) = self.sync.leave_room() {
             self.error_message = Some(format!("Failed to leave room: {}", e));
@@ -1061,12 +1801,18 @@ impl HangApp {
         self.in_room = false;
         self.current_room_id = None;
         self.is_host = false;
+        self.is_spectator = false;
         self.participant_count = 0;
         self.member_roster.clear();
         self.room_capacity_limit = None;
         self.status_message = "Left room".to_string();
     }
 
+    /// Spectators can still pause or seek locally, but the room doesn't follow them
+    fn sends_sync_commands(&self) -> bool {
+        self.sync_enabled && self.in_room && !self.is_spectator
+    }
+
     fn toggle_play(&mut self) {
         let result = if self.is_playing {
             self.player.pause()
@@ -1076,7 +1822,7 @@ impl HangApp {
 
         if let Err(e) = result {
             self.error_message = Some(format!("Playback error: {}", e));
-        } else if self.sync_enabled && self.in_room {
+        } else if self.sends_sync_commands() {
             let _ = self.sync.send_sync_command(if self.is_playing {
                 SyncCommand::Pause {
                     timestamp: self.current_position,
@@ -1099,7 +1845,7 @@ impl HangApp {
         if let Err(e) = self.player.seek(position) {
             self.error_message = Some(format!("Seek error: {}", e));
             self.is_buffering = false;
-        } else if self.sync_enabled && self.in_room {
+        } else if self.sends_sync_commands() {
             let _ = self.sync.send_sync_command(SyncCommand::Seek {
                 timestamp: position,
             });
@@ -1130,7 +1876,7 @@ impl HangApp {
     fn set_speed(&mut self, speed: f64) {
         if let Err(e) = self.player.set_speed(speed) {
             self.error_message = Some(format!("Speed error: {}", e));
-        } else if self.sync_enabled && self.in_room {
+        } else if self.sends_sync_commands() {
             let _ = self
                 .sync
                 .send_sync_command(SyncCommand::Speed { rate: speed });
@@ -1147,17 +1893,41 @@ impl HangApp {
                 resume_token,
                 capacity,
                 display_name,
+                schedule,
+                sync_tracks,
+                feed_token: _,
             } => {
                 self.sync.set_room_joined(room_id.clone(), client_id, true);
+                self.shared_bookmark_ids.clear();
+                self.room_sync_tracks = sync_tracks;
+                self.shared_tracks = None;
+                if sync_tracks {
+                    self.start_track_poll();
+                }
+                self.room_schedule = schedule.filter(|schedule| !schedule.started);
+                if self.room_schedule.is_some() {
+                    self.schedule_enabled = false;
+                }
                 self.in_room = true;
                 self.current_room_id = Some(room_id.clone());
                 self.is_host = true;
                 self.participant_count = 1;
                 self.status_message = format!("Room created: {}", room_id);
                 self.toast_success(format!("Room created: {}", room_id));
+                if let Some(requested) = self.pending_vanity_code.take() {
+                    if requested != room_id {
+                        self.toast_warning(format!(
+                            "\"{}\" is unavailable, using {} instead",
+                            requested, room_id
+                        ));
+                    } else {
+                        self.vanity_code_input.clear();
+                    }
+                }
                 self.room_id_input = room_id.clone();
                 self.invite_modal_open = false;
                 self.pending_invite = None;
+                self.issued_invites.clear();
                 self.room_has_passcode = passcode_enabled;
                 self.room_capacity_limit = Some(capacity);
                 self.room_capacity_input = capacity as u32;
@@ -1171,10 +1941,12 @@ impl HangApp {
                     self.create_passcode_input.clear();
                 }
                 self.display_name_input = display_name.clone();
+                self.is_spectator = false;
                 self.member_roster = vec![MemberSummary {
                     client_id,
                     display_name,
                     is_host: true,
+                    is_spectator: false,
                 }];
                 self.remember_session(room_id.clone(), resume_token, file_hash, true);
             }
@@ -1187,21 +1959,42 @@ impl HangApp {
                 resume_token,
                 capacity,
                 display_name,
+                schedule,
+                spectator,
+                sync_tracks,
+                track_selection,
+                bookmarks,
+                feed_token: _,
             } => {
                 self.sync
                     .set_room_joined(room_id.clone(), client_id, is_host);
+                self.shared_bookmark_ids.clear();
+                self.merge_room_bookmarks(bookmarks);
+                self.room_sync_tracks = sync_tracks;
+                self.shared_tracks = None;
+                self.track_selection_pending = track_selection.is_some();
+                self.room_track_selection = track_selection;
+                if sync_tracks {
+                    self.start_track_poll();
+                }
+                self.room_schedule = schedule.filter(|schedule| !schedule.started);
                 self.in_room = true;
                 self.current_room_id = Some(room_id.clone());
                 self.is_host = is_host;
+                self.is_spectator = spectator;
                 self.participant_count = 1;
-                self.status_message = format!(
-                    "Joined room: {} ({})",
-                    room_id,
-                    if is_host { "Host" } else { "Guest" }
-                );
+                let role = if is_host {
+                    "Host"
+                } else if spectator {
+                    "Spectator"
+                } else {
+                    "Guest"
+                };
+                self.status_message = format!("Joined room: {} ({})", room_id, role);
                 self.toast_success(format!("Joined room: {}", room_id));
                 self.invite_modal_open = false;
                 self.pending_invite = None;
+                self.issued_invites.clear();
                 self.room_has_passcode = passcode_enabled;
                 self.room_capacity_limit = Some(capacity);
                 self.room_capacity_input = capacity as u32;
@@ -1219,63 +2012,186 @@ impl HangApp {
                     client_id,
                     display_name,
                     is_host,
+                    is_spectator: spectator,
                 }];
                 self.remember_session(room_id, resume_token, file_hash, is_host);
             }
             Message::RoomLeft => {
-                self.sync.clear_room();
-                self.in_room = false;
-                self.current_room_id = None;
-                self.is_host = false;
-                self.participant_count = 0;
-                self.status_message = "Left room".to_string();
-                self.room_has_passcode = false;
-                self.active_room_passcode = None;
-                self.pending_room_passcode = None;
-                self.pending_invite = None;
-                self.invite_modal_open = false;
-                self.member_roster.clear();
-                self.room_capacity_limit = None;
-                self.clear_saved_session();
-            }
-            Message::RoomNotFound => {
-                self.resume_in_progress = false;
-                self.error_message = Some("Room not found".to_string());
+                self.reset_room_state("Left room");
             }
-            Message::RoomFull { capacity } => {
-                self.resume_in_progress = false;
-                self.error_message = Some(format!("Room is full ({} seats)", capacity));
+            Message::RoomClosed { room_id, reason } => {
+                self.reset_room_state("Room closed");
+                self.toast_warning(match reason {
+                    Some(reason) => format!("Room {} was closed: {}", room_id, reason),
+                    None => format!("Room {} was closed by the server", room_id),
+                });
             }
-            Message::FileHashMismatch { expected } => {
-                self.resume_in_progress = false;
-                self.error_message =
-                    Some(format!("File mismatch! Expected hash: {}", &expected[..16]));
+            Message::Kicked { reason } => {
+                self.reset_room_state("Removed from room");
+                self.toast_warning(match reason {
+                    Some(reason) => format!("You were removed from the room: {}", reason),
+                    None => "You were removed from the room".to_string(),
+                });
             }
-            Message::SyncBroadcast { command, .. } => {
-                if self.sync_enabled {
-                    self.handle_sync_command(command);
+            Message::ScheduledStart { room_id }
+                if self.current_room_id.as_deref() == Some(room_id.as_str()) =>
+            {
+                let title = self
+                    .room_schedule
+                    .take()
+                    .map(|schedule| schedule.title)
+                    .unwrap_or_else(|| "Watch party".to_string());
+                let _ = self.player.seek(0.0);
+                let _ = self.player.play();
+                self.toast_success(format!("{} is starting!", title));
+            }
+            Message::InviteCreated {
+                room_id,
+                token,
+                max_uses,
+                expires_at_ms,
+            } if self.current_room_id.as_deref() == Some(room_id.as_str()) => {
+                let file_name = self
+                    .video_file
+                    .as_ref()
+                    .and_then(|path| path.file_name())
+                    .and_then(|name| name.to_str());
+                let url = invite::build_invite_url(&room_id, Some(&token), file_name);
+                self.pending_clipboard = Some(url.clone());
+                self.issued_invites.push(IssuedInvite {
+                    token,
+                    url,
+                    max_uses,
+                    expires_at_ms,
+                });
+                self.status_message = "Invite link copied".to_string();
+                self.toast_success("Invite link copied");
+            }
+            Message::InviteRevoked { token } => {
+                self.issued_invites.retain(|invite| invite.token != token);
+                self.toast_info("Invite link revoked");
+            }
+            Message::ServerHello {
+                room_code_format,
+                max_subtitle_bytes,
+            } => {
+                self.room_code_format = room_code_format;
+                self.max_subtitle_bytes = max_subtitle_bytes;
+            }
+            Message::TracksSelected { selection } if self.room_sync_tracks && !self.is_host => {
+                self.room_track_selection = Some(selection);
+                self.track_selection_pending = true;
+            }
+            Message::SubtitleShared {
+                display_name,
+                file_name,
+                content,
+                ..
+            } if self.in_room => {
+                // The server strips directories too, but don't rely on it for paths we write to
+                let Some(file_name) = Path::new(&file_name)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .filter(|name| subtitles::is_subtitle_file(Path::new(name)))
+                else {
+                    return;
+                };
+                self.toast_info(format!("{} shared subtitles: {}", display_name, file_name));
+                self.subtitle_offers.push(SubtitleOffer {
+                    from: display_name,
+                    file_name,
+                    content,
+                });
+                if self.subtitle_offers.len() > MAX_SUBTITLE_OFFERS {
+                    self.subtitle_offers.remove(0);
                 }
             }
+            Message::BookmarkShared { bookmark, .. } if self.in_room => {
+                self.toast_info(format!(
+                    "🔖 {} bookmarked \"{}\" at {}",
+                    bookmark.author,
+                    bookmark.label,
+                    format_time(bookmark.position)
+                ));
+                self.merge_room_bookmarks(vec![bookmark]);
+            }
+            Message::PublicRoomList { rooms } => {
+                self.public_rooms = rooms;
+                self.public_rooms_loading = false;
+            }
+            Message::ServerNotice { message } => {
+                self.show_toast(Toast::info(format!("Server: {}", message)).with_duration(8.0));
+            }
+            Message::RoomNotFound => {
+                self.resume_in_progress = false;
+                self.error_message = Some("Room not found".to_string());
+            }
+            Message::RoomFull { capacity } => {
+                self.resume_in_progress = false;
+                self.error_message = Some(format!("Room is full ({} seats)", capacity));
+            }
+            Message::FileHashMismatch { expected } => {
+                self.resume_in_progress = false;
+                self.error_message =
+                    Some(format!("File mismatch! Expected hash: {}", &expected[..16]));
+            }
+            Message::SyncBroadcast { command, .. } if self.sync_enabled => {
+                self.handle_sync_command(command);
+            }
             Message::Error { message } => {
                 if message.contains("Session token") {
                     self.clear_saved_session();
+                    if self.resume_is_automatic {
+                        // Expected after a server restart: the previous room no longer exists
+                        self.toast_warning("Previous room is no longer available");
+                        return;
+                    }
                 }
                 self.resume_in_progress = false;
                 self.error_message = Some(message);
             }
+            Message::ServerShuttingDown { .. } => {
+                self.toast_info("Sync server is restarting. Reconnecting shortly...");
+            }
             Message::RoomMemberUpdate {
                 room_id,
                 members,
                 capacity,
-            } => {
-                if self.current_room_id.as_deref() == Some(room_id.as_str()) {
-                    self.apply_member_roster(members, capacity);
-                }
+            } if self.current_room_id.as_deref() == Some(room_id.as_str()) => {
+                self.apply_member_roster(members, capacity);
             }
             _ => {}
         }
     }
 
+    /// Forget everything about the current room after leaving or being removed
+    fn reset_room_state(&mut self, status: &str) {
+        self.sync.clear_room();
+        self.in_room = false;
+        self.current_room_id = None;
+        self.is_host = false;
+        self.is_spectator = false;
+        self.participant_count = 0;
+        self.status_message = status.to_string();
+        self.room_has_passcode = false;
+        self.active_room_passcode = None;
+        self.pending_room_passcode = None;
+        self.pending_invite = None;
+        self.invite_modal_open = false;
+        self.issued_invites.clear();
+        self.subtitle_offers.clear();
+        self.shared_bookmark_ids.clear();
+        self.room_sync_tracks = false;
+        self.shared_tracks = None;
+        self.room_track_selection = None;
+        self.track_selection_pending = false;
+        self.track_poll = None;
+        self.member_roster.clear();
+        self.room_capacity_limit = None;
+        self.room_schedule = None;
+        self.clear_saved_session();
+    }
+
     fn handle_sync_command(&mut self, command: SyncCommand) {
         // Debounce rapid sync commands
         let now = std::time::Instant::now();
@@ -1403,18 +2319,27 @@ impl HangApp {
         
         self.is_dragging_file = false;
 
-        for file in dropped_files {
-            if let Some(path) = file.path {
-                if !Self::is_supported_video(&path) {
-                    self.toast_error("Unsupported file type");
-                    continue;
-                }
-                if let Err(e) = self.load_video_from_path(&path) {
-                    self.toast_error(format!("Failed to open: {}", e));
-                }
-                break;
+        let mut video = None;
+        let mut subtitle_files = Vec::new();
+        for path in dropped_files.into_iter().filter_map(|file| file.path) {
+            if subtitles::is_subtitle_file(&path) {
+                subtitle_files.push(path);
+            } else if !Self::is_supported_video(&path) {
+                self.toast_error("Unsupported file type");
+            } else if video.is_none() {
+                video = Some(path);
             }
         }
+
+        // Load the video first so dropped subtitles attach to it
+        if let Some(path) = video {
+            if let Err(e) = self.load_video_from_path(&path) {
+                self.toast_error(format!("Failed to open: {}", e));
+            }
+        }
+        if !subtitle_files.is_empty() {
+            self.add_subtitle_files(&subtitle_files);
+        }
     }
 
     fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
@@ -1432,6 +2357,16 @@ impl HangApp {
                 input.key_pressed(egui::Key::F),
             )
         });
+        let (subtitle_earlier, subtitle_later, audio_earlier, audio_later, bookmark) =
+            ctx.input(|input| {
+                (
+                    input.key_pressed(egui::Key::G),
+                    input.key_pressed(egui::Key::H),
+                    input.key_pressed(egui::Key::J),
+                    input.key_pressed(egui::Key::K),
+                    input.key_pressed(egui::Key::B),
+                )
+            });
 
         if space {
             self.toggle_play();
@@ -1466,6 +2401,24 @@ impl HangApp {
             self.is_fullscreen = !self.is_fullscreen;
             ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(self.is_fullscreen));
         }
+
+        if self.video_hash.is_some() {
+            if subtitle_earlier || subtitle_later {
+                let step = if subtitle_later { DELAY_STEP_MS } else { -DELAY_STEP_MS };
+                self.set_subtitle_delay(self.subtitle_delay_ms + step);
+                self.save_media_settings();
+                self.toast_info(format!("Subtitle delay: {:+} ms", self.subtitle_delay_ms));
+            }
+            if audio_earlier || audio_later {
+                let step = if audio_later { DELAY_STEP_MS } else { -DELAY_STEP_MS };
+                self.set_audio_delay(self.audio_delay_ms + step);
+                self.save_media_settings();
+                self.toast_info(format!("Audio delay: {:+} ms", self.audio_delay_ms));
+            }
+            if bookmark {
+                self.open_bookmarks();
+            }
+        }
     }
 
     fn render_room_dialog(&mut self, ctx: &egui::Context) {
@@ -1477,6 +2430,10 @@ impl HangApp {
         let mut create_room_requested = false;
         let mut join_room_requested = false;
         let mut leave_room_requested = false;
+        let mut refresh_lobby_requested = false;
+        let mut lobby_join_requested: Option<PublicRoomSummary> = None;
+        let mut create_invite_requested = false;
+        let mut revoke_invite_requested: Option<String> = None;
 
         egui::Window::new("Room Controls")
             .open(&mut dialog_open)
@@ -1498,20 +2455,33 @@ impl HangApp {
                     });
                     if self.is_host {
                         ui.horizontal(|ui| {
+                            // Links carry a revocable token rather than the passcode
                             if ui.button("Copy invite link").clicked() {
-                                let file_name = self
-                                    .video_file
-                                    .as_ref()
-                                    .and_then(|path| path.file_name())
-                                    .and_then(|name| name.to_str());
-                                let link = invite::build_invite_url(
-                                    &code,
-                                    self.active_room_passcode.as_deref(),
-                                    file_name,
-                                );
-                                ui.output_mut(|o| o.copied_text = link);
-                                self.status_message = "Invite link copied".to_string();
+                                create_invite_requested = true;
                             }
+                            ui.checkbox(&mut self.invite_single_use, "Single use");
+                            ui.label("Expires after");
+                            ui.add(
+                                egui::DragValue::new(&mut self.invite_expiry_hours)
+                                    .range(0..=720)
+                                    .suffix(" h"),
+                            );
+                        });
+                        if self.invite_expiry_hours == 0 {
+                            ui.small("Links stay valid until revoked");
+                        }
+                        for issued in &self.issued_invites {
+                            ui.horizontal(|ui| {
+                                ui.small(Self::describe_invite(issued));
+                                if ui.small_button("Copy").clicked() {
+                                    ui.output_mut(|o| o.copied_text = issued.url.clone());
+                                }
+                                if ui.small_button("Revoke").clicked() {
+                                    revoke_invite_requested = Some(issued.token.clone());
+                                }
+                            });
+                        }
+                        ui.horizontal(|ui| {
                             if let Some(passcode) = &self.active_room_passcode {
                                 ui.monospace(format!("Passcode: {}", passcode));
                             } else if self.room_has_passcode {
@@ -1533,73 +2503,196 @@ impl HangApp {
                     ui.checkbox(&mut self.sync_enabled, "Enable sync");
                     self.draw_participant_indicator(ui);
                 } else {
-                    ui.label("Create a room to get a sharable 6-digit code.");
-                    let can_create = self.video_hash.is_some() && self.sync_connected;
-                    if ui
-                        .add_enabled(can_create, egui::Button::new("Create Room"))
-                        .clicked()
-                    {
-                        create_room_requested = true;
-                    }
-                    if !self.sync_connected {
-                        ui.colored_label(
-                            egui::Color32::LIGHT_YELLOW,
-                            "Waiting for sync server (cold starts on Render can take up to a minute)...",
-                        );
-                    }
-                    ui.label("Optional passcode:");
-                    ui.add(
-                        egui::TextEdit::singleline(&mut self.create_passcode_input)
-                            .password(true)
-                            .hint_text("Leave blank for none"),
-                    );
-                    ui.add(
-                        egui::Slider::new(
-                            &mut self.room_capacity_input,
-                            ROOM_CAPACITY_MIN..=ROOM_CAPACITY_MAX,
-                        )
-                        .text("Seats"),
-                    );
-
-                    ui.separator();
-                    ui.label("Join an existing room:");
-                    let response = ui.text_edit_singleline(&mut self.room_id_input);
-                    if response.changed() {
-                        self.sanitize_room_code_input();
-                    }
-                    ui.label("Passcode (if needed):");
-                    ui.add(
-                        egui::TextEdit::singleline(&mut self.join_passcode_input)
-                            .password(true)
-                            .hint_text("Provided by the host"),
-                    );
                     ui.horizontal(|ui| {
-                        let can_join = self.video_hash.is_some() && self.sync_connected;
-                        if ui.add_enabled(can_join, egui::Button::new("Join")).clicked() {
-                            join_room_requested = true;
-                        }
-                        ui.label("Format: 123-456");
-                    });
-
-                    if let Some(session) = self.saved_session.as_ref() {
-                        ui.add_space(8.0);
-                        ui.separator();
-                        let preview_len = session.file_hash.len().min(8);
-                        let hash_preview = &session.file_hash[..preview_len];
-                        ui.colored_label(
-                            egui::Color32::LIGHT_GREEN,
-                            format!(
-                                "Last room {} (hash {}...)",
-                                session.room_id,
-                                hash_preview
-                            ),
+                        ui.selectable_value(
+                            &mut self.room_dialog_tab,
+                            RoomDialogTab::Private,
+                            "Create / Join",
                         );
-                        let resume_enabled = self.sync_connected && !self.resume_in_progress;
                         if ui
-                            .add_enabled(resume_enabled, egui::Button::new("Resume last session"))
+                            .selectable_value(
+                                &mut self.room_dialog_tab,
+                                RoomDialogTab::Lobby,
+                                "Public lobby",
+                            )
                             .clicked()
                         {
-                            self.attempt_resume(false);
+                            refresh_lobby_requested = true;
+                        }
+                    });
+                    ui.separator();
+                    match self.room_dialog_tab {
+                        RoomDialogTab::Private => {
+                            ui.label(format!(
+                                "Create a room to get a sharable code like {}.",
+                                self.room_code_format.example()
+                            ));
+                            let can_create = self.video_hash.is_some() && self.sync_connected;
+                            if ui
+                                .add_enabled(can_create, egui::Button::new("Create Room"))
+                                .clicked()
+                            {
+                                create_room_requested = true;
+                            }
+                            if !self.sync_connected {
+                                ui.colored_label(
+                                    egui::Color32::LIGHT_YELLOW,
+                                    "Waiting for sync server (cold starts on Render can take up to a minute)...",
+                                );
+                            }
+                            ui.label("Optional passcode:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.create_passcode_input)
+                                    .password(true)
+                                    .hint_text("Leave blank for none"),
+                            );
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut self.room_capacity_input,
+                                    ROOM_CAPACITY_MIN..=ROOM_CAPACITY_MAX,
+                                )
+                                .text("Seats"),
+                            );
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut self.spectator_capacity_input,
+                                    0..=SPECTATOR_CAPACITY_MAX,
+                                )
+                                .text("Spectator seats"),
+                            );
+                            ui.checkbox(
+                                &mut self.sync_tracks_input,
+                                "Sync audio and subtitle tracks",
+                            )
+                            .on_hover_text("Members switch to the tracks you pick, matched by name");
+                            if self.room_code_format.allow_vanity {
+                                ui.label("Custom room code (optional):");
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut self.vanity_code_input)
+                                        .hint_text("e.g. friday-anime"),
+                                );
+                                if response.changed() {
+                                    self.vanity_code_input =
+                                        self.room_code_format.sanitize_input(&self.vanity_code_input);
+                                }
+                                let requested = self.vanity_code_input.trim();
+                                if !requested.is_empty()
+                                    && !self.room_code_format.is_valid_vanity(requested)
+                                {
+                                    ui.colored_label(
+                                        egui::Color32::LIGHT_YELLOW,
+                                        format!(
+                                            "Use {}-{} letters, digits, or hyphens, with at least one letter",
+                                            self.room_code_format.vanity_min_len,
+                                            self.room_code_format.vanity_max_len
+                                        ),
+                                    );
+                                }
+                            }
+                            ui.checkbox(&mut self.list_room_publicly, "List in public lobby");
+                            if self.list_room_publicly {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.public_title_input)
+                                        .hint_text("Room title"),
+                                );
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut self.public_description_input)
+                                        .desired_rows(2)
+                                        .hint_text("What are you watching? (optional)"),
+                                );
+                                if !self.create_passcode_input.trim().is_empty() {
+                                    ui.weak("Listed with a lock; visitors still need the passcode.");
+                                }
+                            }
+                            ui.checkbox(&mut self.schedule_enabled, "Schedule for later");
+                            if self.schedule_enabled {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.schedule_title_input)
+                                        .hint_text("Watch party title"),
+                                );
+                                if self.schedule_media_input.is_empty() {
+                                    if let Some(name) = self
+                                        .video_file
+                                        .as_ref()
+                                        .and_then(|path| path.file_name())
+                                        .and_then(|name| name.to_str())
+                                    {
+                                        self.schedule_media_input = name.to_string();
+                                    }
+                                }
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.schedule_media_input)
+                                        .hint_text("What you'll watch (file name or URL)"),
+                                );
+                                ui.horizontal(|ui| {
+                                    ui.label("Starts in");
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.schedule_delay_hours)
+                                            .range(0..=336)
+                                            .suffix(" h"),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.schedule_delay_minutes)
+                                            .range(0..=59)
+                                            .suffix(" min"),
+                                    );
+                                });
+                                ui.weak("The code stays reserved until then, even if you close Hang.");
+                            }
+
+                            ui.separator();
+                            ui.label("Join an existing room:");
+                            let response = ui.text_edit_singleline(&mut self.room_id_input);
+                            if response.changed() {
+                                self.sanitize_room_code_input();
+                            }
+                            ui.label("Passcode (if needed):");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.join_passcode_input)
+                                    .password(true)
+                                    .hint_text("Provided by the host"),
+                            );
+                            ui.checkbox(&mut self.join_as_spectator, "Join as spectator")
+                                .on_hover_text("Watch along without controlling playback or taking a seat");
+                            ui.horizontal(|ui| {
+                                let can_join = self.video_hash.is_some() && self.sync_connected;
+                                if ui.add_enabled(can_join, egui::Button::new("Join")).clicked() {
+                                    join_room_requested = true;
+                                }
+                                let hint = if self.room_code_format.allow_vanity {
+                                    format!("Format: {} or a custom name", self.room_code_format.example())
+                                } else {
+                                    format!("Format: {}", self.room_code_format.example())
+                                };
+                                ui.label(hint);
+                            });
+
+                            if let Some(session) = self.saved_session.as_ref() {
+                                ui.add_space(8.0);
+                                ui.separator();
+                                let preview_len = session.file_hash.len().min(8);
+                                let hash_preview = &session.file_hash[..preview_len];
+                                ui.colored_label(
+                                    egui::Color32::LIGHT_GREEN,
+                                    format!(
+                                        "Last room {} (hash {}...)",
+                                        session.room_id,
+                                        hash_preview
+                                    ),
+                                );
+                                let resume_enabled = self.sync_connected && !self.resume_in_progress;
+                                if ui
+                                    .add_enabled(resume_enabled, egui::Button::new("Resume last session"))
+                                    .clicked()
+                                {
+                                    self.attempt_resume(false);
+                                }
+                            }
+                        }
+                        RoomDialogTab::Lobby => {
+                            let (selected, refresh) = self.render_public_lobby(ui);
+                            lobby_join_requested = selected;
+                            refresh_lobby_requested |= refresh;
                         }
                     }
                 }
@@ -1628,10 +2721,128 @@ impl HangApp {
         if join_room_requested {
             self.join_room();
         }
+        if create_invite_requested {
+            self.create_invite();
+        }
+        if let Some(token) = revoke_invite_requested {
+            if let Err(e) = self.sync.revoke_invite(token) {
+                self.error_message = Some(format!("Failed to revoke invite: {}", e));
+            }
+        }
+        if refresh_lobby_requested {
+            self.refresh_public_rooms();
+        }
+        if let Some(room) = lobby_join_requested {
+            self.join_public_room(room);
+        }
 
         self.room_dialog_open = dialog_open;
     }
 
+    /// Draws the public room directory; returns the room the user picked and whether to refresh
+    fn render_public_lobby(&self, ui: &mut egui::Ui) -> (Option<PublicRoomSummary>, bool) {
+        let mut selected = None;
+        let mut refresh = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Rooms listed by their hosts:");
+            if ui
+                .add_enabled(self.sync_connected, egui::Button::new("Refresh"))
+                .clicked()
+            {
+                refresh = true;
+            }
+            if self.public_rooms_loading {
+                ui.spinner();
+            }
+        });
+
+        if self.public_rooms.is_empty() {
+            ui.weak(if self.public_rooms_loading {
+                "Loading rooms..."
+            } else {
+                "No public rooms right now."
+            });
+            return (selected, refresh);
+        }
+
+        let can_join = self.video_hash.is_some() && self.sync_connected;
+        egui::ScrollArea::vertical()
+            .max_height(260.0)
+            .show(ui, |ui| {
+                for room in &self.public_rooms {
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.strong(&room.title);
+                            if room.passcode_protected {
+                                ui.colored_label(egui::Color32::LIGHT_YELLOW, "🔒 Passcode");
+                            }
+                            if room.is_playing {
+                                ui.colored_label(egui::Color32::LIGHT_GREEN, "▶ Playing");
+                            } else if let Some(starts_at_ms) = room.starts_at_ms {
+                                let remaining = starts_at_ms.saturating_sub(unix_millis());
+                                if remaining > 0 {
+                                    ui.colored_label(
+                                        egui::Color32::LIGHT_BLUE,
+                                        format!("📅 Starts in {}", format_countdown(remaining)),
+                                    );
+                                }
+                            }
+                        });
+                        if !room.description.is_empty() {
+                            ui.label(&room.description);
+                        }
+                        ui.horizontal(|ui| {
+                            ui.weak(format!(
+                                "{} · hosted by {} · {}/{} seats",
+                                room.room_id, room.host_name, room.member_count, room.capacity
+                            ));
+                            let full = room.member_count >= room.capacity;
+                            let label = if full { "Full" } else { "Join" };
+                            if ui
+                                .add_enabled(can_join && !full, egui::Button::new(label))
+                                .clicked()
+                            {
+                                selected = Some(room.clone());
+                            }
+                        });
+                    });
+                }
+            });
+
+        if self.video_hash.is_none() {
+            ui.colored_label(
+                egui::Color32::LIGHT_YELLOW,
+                "Load the host's video before joining a room",
+            );
+        }
+
+        (selected, refresh)
+    }
+
+    fn refresh_public_rooms(&mut self) {
+        if !self.sync_connected {
+            return;
+        }
+        match self.sync.list_public_rooms() {
+            Ok(()) => self.public_rooms_loading = true,
+            Err(e) => self.error_message = Some(format!("Failed to load public rooms: {}", e)),
+        }
+    }
+
+    fn join_public_room(&mut self, room: PublicRoomSummary) {
+        self.room_id_input = room.room_id.clone();
+        if room.passcode_protected {
+            // Hand over to the regular join form so the passcode can be entered
+            self.room_dialog_tab = RoomDialogTab::Private;
+            self.join_passcode_input.clear();
+            self.toast_info(format!("\"{}\" needs a passcode from the host", room.title));
+            return;
+        }
+        self.join_passcode_input.clear();
+        self.join_room();
+    }
+
     fn render_invite_modal(&mut self, ctx: &egui::Context) {
         if !self.invite_modal_open {
             return;
@@ -1660,7 +2871,9 @@ impl HangApp {
                     ui.label("Host did not specify a file name");
                 }
 
-                if let Some(passcode) = &invite.passcode {
+                if invite.invite.is_some() {
+                    ui.label("This invite lets you in without a passcode");
+                } else if let Some(passcode) = &invite.passcode {
                     ui.label(format!("Passcode: {}", passcode));
                 } else {
                     ui.label("No passcode included");
@@ -1716,6 +2929,52 @@ impl HangApp {
         }
     }
 
+    /// Waiting screen shown to members of a scheduled room before it starts
+    fn render_watch_party_lobby(&self, ctx: &egui::Context) {
+        let Some(schedule) = &self.room_schedule else {
+            return;
+        };
+        let remaining_ms = schedule.starts_at_ms.saturating_sub(unix_millis());
+
+        egui::Area::new(egui::Id::new("hang-watch-party-lobby"))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(egui::Color32::from_rgba_unmultiplied(10, 10, 10, 230))
+                    .inner_margin(egui::Margin::same(24.0))
+                    .rounding(16.0)
+                    .show(ui, |ui| {
+                        ui.vertical_centered(|ui| {
+                            ui.weak("Watch party");
+                            ui.heading(&schedule.title);
+                            if let Some(media) = &schedule.media {
+                                ui.label(format!("Watching: {}", media));
+                            }
+                            ui.add_space(12.0);
+                            let countdown = if remaining_ms == 0 {
+                                "Starting...".to_string()
+                            } else {
+                                format_countdown(remaining_ms)
+                            };
+                            ui.label(
+                                egui::RichText::new(countdown)
+                                    .size(40.0)
+                                    .strong()
+                                    .color(egui::Color32::from_rgb(255, 138, 0)),
+                            );
+                            ui.add_space(8.0);
+                            ui.label(format!(
+                                "{} waiting · playback starts for everyone automatically",
+                                self.participant_count.max(1)
+                            ));
+                        });
+                    });
+            });
+
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+
     fn render_network_overlay(&mut self, ctx: &egui::Context) {
         if !self.show_network_overlay {
             return;
@@ -1766,49 +3025,45 @@ impl HangApp {
     }
 
     fn sanitize_room_code_input(&mut self) {
-        let digits: String = self
-            .room_id_input
-            .chars()
-            .filter(|c| c.is_ascii_digit())
-            .collect();
-        if digits.len() <= 3 {
-            self.room_id_input = digits;
-        } else {
-            let (left, right) = digits.split_at(3);
-            let right = &right[..right.len().min(3)];
-            self.room_id_input = format!("{left}-{right}");
-        }
+        self.room_id_input = self.room_code_format.sanitize_input(&self.room_id_input);
     }
 
-    fn is_valid_room_code(code: &str) -> bool {
-        let trimmed = code.trim();
-        if trimmed.len() != 7 || trimmed.as_bytes()[3] != b'-' {
-            return false;
-        }
-        trimmed
-            .chars()
-            .enumerate()
-            .all(|(idx, ch)| idx == 3 || ch.is_ascii_digit())
+    fn is_valid_room_code(&self, code: &str) -> bool {
+        self.room_code_format.is_valid(code)
     }
 
     fn draw_participant_indicator(&self, ui: &mut egui::Ui) {
         if !self.in_room {
             return;
         }
+        let spectators = self
+            .member_roster
+            .iter()
+            .filter(|member| member.is_spectator)
+            .count();
+        let seated = self.participant_count.saturating_sub(spectators).max(1);
         let capacity_text = self
             .room_capacity_limit
-            .map(|limit| format!("{} / {}", self.participant_count.max(1), limit))
-            .unwrap_or_else(|| format!("{} online", self.participant_count.max(1)));
+            .map(|limit| format!("{} / {}", seated, limit))
+            .unwrap_or_else(|| format!("{} online", seated));
         ui.vertical(|ui| {
             ui.label(format!("Participants ({capacity_text})"));
+            if spectators > 0 {
+                ui.weak(format!("{} spectating", spectators));
+            }
             for member in &self.member_roster {
                 let label = if member.is_host {
                     format!("★ {}", member.display_name)
+                } else if member.is_spectator {
+                    format!("◦ {} (spectator)", member.display_name)
                 } else {
                     format!("• {}", member.display_name)
                 };
                 ui.label(label);
             }
+            if self.is_spectator {
+                ui.weak("You're spectating: your playback controls only affect you.");
+            }
             if self.member_roster.is_empty() {
                 ui.label("Waiting for roster update...");
             }
@@ -1816,7 +3071,10 @@ impl HangApp {
     }
 
     fn render_track_selectors(&mut self, ui: &mut egui::Ui) {
-        if self.audio_tracks.is_empty() && self.subtitle_tracks.is_empty() {
+        if self.video_file.is_none()
+            && self.audio_tracks.is_empty()
+            && self.subtitle_tracks.is_empty()
+        {
             return;
         }
         ui.horizontal(|ui| {
@@ -1881,9 +3139,175 @@ impl HangApp {
                         }
                     });
             }
+
+            if self.room_sync_tracks {
+                if self.is_host {
+                    ui.small("🔗 Shared with the room")
+                        .on_hover_text("Members switch to the tracks you pick");
+                } else {
+                    ui.small("🔗 Following the host")
+                        .on_hover_text("Tracks switch when the host picks different ones");
+                }
+            }
+
+            if ui
+                .button("➕ Subtitles…")
+                .on_hover_text("Add an SRT, ASS or VTT file, or drop one on the window")
+                .clicked()
+            {
+                self.select_subtitle_files();
+            }
+
+            if self.in_room
+                && ui
+                    .add_enabled(self.max_subtitle_bytes > 0, egui::Button::new("📤 Share…"))
+                    .on_hover_text("Send a subtitle file to everyone in the room")
+                    .on_disabled_hover_text("This server doesn't relay subtitle files")
+                    .clicked()
+            {
+                self.share_subtitle_file();
+            }
         });
     }
 
+    /// Subtitle files shared by other members, each loaded only if accepted
+    fn render_bookmarks(&mut self, ctx: &egui::Context) {
+        if !self.show_bookmarks {
+            return;
+        }
+
+        let mut open = true;
+        let mut add = false;
+        let mut jump_to = None;
+        let mut share = None;
+        let mut remove = None;
+        egui::Window::new("🔖 Bookmarks")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(320.0)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 48.0))
+            .show(ctx, |ui| {
+                if self.video_hash.is_none() {
+                    ui.label("Open a video to bookmark moments in it.");
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    let field = ui.add(
+                        egui::TextEdit::singleline(&mut self.bookmark_label_input)
+                            .hint_text("Label, e.g. best fight scene")
+                            .char_limit(BOOKMARK_LABEL_MAX_CHARS),
+                    );
+                    if std::mem::take(&mut self.focus_bookmark_label) {
+                        field.request_focus();
+                    }
+                    if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        add = true;
+                    }
+                    if ui
+                        .button(format!("Add at {}", format_time(self.current_position)))
+                        .clicked()
+                    {
+                        add = true;
+                    }
+                });
+                if self.in_room {
+                    ui.checkbox(&mut self.share_new_bookmarks, "Share new bookmarks with the room");
+                }
+                ui.separator();
+
+                if self.bookmarks.is_empty() {
+                    ui.weak("No bookmarks yet. They show up as ticks on the seek bar.");
+                }
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for bookmark in &self.bookmarks {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button(format_time(bookmark.position))
+                                .on_hover_text("Jump here")
+                                .clicked()
+                            {
+                                jump_to = Some(bookmark.position);
+                            }
+                            ui.label(&bookmark.label);
+                            if !bookmark.author.is_empty() {
+                                ui.weak(format!("by {}", bookmark.author));
+                            }
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui
+                                    .small_button("🗑")
+                                    .on_hover_text("Remove from this device")
+                                    .clicked()
+                                {
+                                    remove = Some(bookmark.id.clone());
+                                }
+                                let shareable = self.in_room
+                                    && bookmark.author.is_empty()
+                                    && !self.shared_bookmark_ids.contains(&bookmark.id);
+                                if shareable
+                                    && ui
+                                        .small_button("📤")
+                                        .on_hover_text("Share with the room")
+                                        .clicked()
+                                {
+                                    share = Some(bookmark.id.clone());
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+        self.show_bookmarks = open;
+
+        if add {
+            self.add_bookmark();
+        }
+        if let Some(position) = jump_to {
+            self.seek(position);
+        }
+        if let Some(id) = share {
+            self.share_bookmark(&id);
+        }
+        if let Some(id) = remove {
+            self.remove_bookmark(&id);
+        }
+    }
+
+    fn render_subtitle_offers(&mut self, ctx: &egui::Context) {
+        if self.subtitle_offers.is_empty() {
+            return;
+        }
+
+        let mut accepted = None;
+        let mut dismissed = None;
+        egui::Window::new("Shared Subtitles")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -96.0))
+            .show(ctx, |ui| {
+                for (index, offer) in self.subtitle_offers.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} from {}", offer.file_name, offer.from));
+                        if ui
+                            .add_enabled(self.video_file.is_some(), egui::Button::new("Load"))
+                            .clicked()
+                        {
+                            accepted = Some(index);
+                        }
+                        if ui.button("Dismiss").clicked() {
+                            dismissed = Some(index);
+                        }
+                    });
+                }
+            });
+
+        if let Some(index) = accepted {
+            let offer = self.subtitle_offers.remove(index);
+            self.accept_subtitle_offer(offer);
+        } else if let Some(index) = dismissed {
+            self.subtitle_offers.remove(index);
+        }
+    }
+
     fn describe_track(title: &str, lang: &str) -> String {
         if lang.is_empty() {
             title.to_string()
@@ -1972,13 +3396,13 @@ impl HangApp {
             let color = egui::Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
             
             let p1 = center + egui::vec2(
-                (start_angle.cos() * radius as f64) as f32,
-                (start_angle.sin() * radius as f64) as f32,
+                (start_angle.cos() * radius) as f32,
+                (start_angle.sin() * radius) as f32,
             );
             let end_angle = start_angle + std::f64::consts::TAU / segments as f64 * 0.7;
             let p2 = center + egui::vec2(
-                (end_angle.cos() * radius as f64) as f32,
-                (end_angle.sin() * radius as f64) as f32,
+                (end_angle.cos() * radius) as f32,
+                (end_angle.sin() * radius) as f32,
             );
             
             ui.painter().line_segment([p1, p2], egui::Stroke::new(2.5, color));
@@ -2000,11 +3424,18 @@ impl eframe::App for HangApp {
         self.update_playback_state();
         self.update_video_texture(ctx);
         self.handle_file_drop(ctx);
+        self.poll_pending_subtitles();
+        self.poll_synced_tracks();
+        self.record_watch_progress(false);
+        self.apply_pending_delays();
         self.poll_invite_channel();
         self.poll_youtube_loader();
         self.handle_keyboard_shortcuts(ctx);
         self.update_toasts();
         self.update_cursor_visibility(ctx);
+        if let Some(text) = self.pending_clipboard.take() {
+            ctx.output_mut(|o| o.copied_text = text);
+        }
         
         // Request repaint while YouTube is loading
         if self.youtube_loader.is_some() {
@@ -2047,28 +3478,58 @@ impl eframe::App for HangApp {
                         
                         // Recent Files submenu
                         ui.menu_button("📜 Recent Files", |ui| {
-                            if self.recent_files.is_empty() {
+                            let recent: Vec<(String, WatchEntry)> = self
+                                .watch_history
+                                .recent(MAX_RECENT_FILES)
+                                .into_iter()
+                                .map(|(hash, entry)| (hash.to_string(), entry.clone()))
+                                .collect();
+                            if recent.is_empty() {
                                 ui.label("No recent files");
                             } else {
-                                let mut file_to_open: Option<PathBuf> = None;
-                                for path in self.recent_files.iter() {
-                                    let name = path.file_name()
-                                        .and_then(|n| n.to_str())
-                                        .unwrap_or("Unknown");
-                                    if ui.button(name).clicked() {
-                                        file_to_open = Some(path.clone());
+                                let mut source_to_open: Option<String> = None;
+                                let mut hash_to_forget: Option<String> = None;
+                                for (hash, entry) in &recent {
+                                    let mut details = if entry.duration > 0.0 {
+                                        format!(
+                                            "{} / {}",
+                                            format_time(entry.position),
+                                            format_time(entry.duration)
+                                        )
+                                    } else {
+                                        entry.source.clone()
+                                    };
+                                    if let Some(room_id) = &entry.room_id {
+                                        details.push_str(&format!(" · room {}", room_id));
+                                    }
+                                    let response = ui.button(&entry.title).on_hover_text(&details);
+                                    if response.clicked() {
+                                        source_to_open = Some(entry.source.clone());
                                         ui.close_menu();
                                     }
-                                }
-                                if let Some(path) = file_to_open {
-                                    if let Err(e) = self.load_video_from_path(&path) {
-                                        self.toast_error(format!("Failed to open: {}", e));
+                                    response.context_menu(|ui| {
+                                        if ui.button("Remove from history").clicked() {
+                                            hash_to_forget = Some(hash.clone());
+                                            ui.close_menu();
+                                        }
+                                    });
+                                    if entry.duration > 0.0 {
+                                        ui.add(
+                                            egui::ProgressBar::new(entry.progress())
+                                                .desired_width(ui.available_width().max(160.0))
+                                                .desired_height(4.0),
+                                        );
                                     }
                                 }
+                                if let Some(hash) = hash_to_forget {
+                                    self.watch_history.remove(&hash);
+                                }
+                                if let Some(source) = source_to_open {
+                                    self.open_from_history(&source);
+                                }
                                 ui.separator();
                                 if ui.button("🗑 Clear History").clicked() {
-                                    self.recent_files.clear();
-                                    self.save_recent_files();
+                                    self.watch_history.clear();
                                     ui.close_menu();
                                 }
                             }
@@ -2112,6 +3573,13 @@ impl eframe::App for HangApp {
                             self.seek((self.current_position + 10.0).min(self.duration));
                             ui.close_menu();
                         }
+                        if ui
+                            .add_enabled(self.video_hash.is_some(), egui::Button::new("🔖 Bookmarks…"))
+                            .clicked()
+                        {
+                            self.open_bookmarks();
+                            ui.close_menu();
+                        }
                         ui.separator();
                         
                         // Speed submenu
@@ -2191,6 +3659,10 @@ impl eframe::App for HangApp {
                         
                         // Subtitle tracks
                         ui.label("Subtitles:");
+                        if ui.button("📄 Load Subtitle File...").clicked() {
+                            ui.close_menu();
+                            self.select_subtitle_files();
+                        }
                         if ui.selectable_label(self.selected_subtitle == -1, "None").clicked() {
                             self.selected_subtitle = -1;
                             let _ = self.player.set_subtitle_track(-1);
@@ -2281,11 +3753,9 @@ impl eframe::App for HangApp {
                         };
                         ui.label(egui::RichText::new(status_text).color(status_color));
                         
-                        if !self.sync_connected {
-                            if ui.button("🔄 Retry Connection").clicked() {
-                                self.request_manual_reconnect();
-                                ui.close_menu();
-                            }
+                        if !self.sync_connected && ui.button("🔄 Retry Connection").clicked() {
+                            self.request_manual_reconnect();
+                            ui.close_menu();
                         }
                         
                         ui.separator();
@@ -2295,6 +3765,9 @@ impl eframe::App for HangApp {
                         ui.label("  ↑/↓ - Volume ±5%");
                         ui.label("  F - Fullscreen");
                         ui.label("  M - Mute");
+                        ui.label("  G/H - Subtitle delay ∓50ms");
+                        ui.label("  J/K - Audio delay ∓50ms");
+                        ui.label("  B - Bookmarks");
                         ui.label("  Esc - Exit fullscreen");
                     });
 
@@ -2325,7 +3798,11 @@ impl eframe::App for HangApp {
 
         self.render_room_dialog(ctx);
         self.render_invite_modal(ctx);
+        self.render_subtitle_offers(ctx);
+        self.render_resume_offer(ctx);
+        self.render_bookmarks(ctx);
         self.render_network_overlay(ctx);
+        self.render_watch_party_lobby(ctx);
 
         // Bottom control panel
         if show_chrome {
@@ -2345,6 +3822,18 @@ impl eframe::App for HangApp {
                     egui::vec2(available_width, timeline_height + 16.0),
                     egui::Sense::click_and_drag(),
                 );
+                let bookmark_x = |position: f64| {
+                    rect.min.x + rect.width() * (position / duration).clamp(0.0, 1.0) as f32
+                };
+                let hovered_bookmark = response
+                    .hover_pos()
+                    .filter(|_| self.duration > 0.0)
+                    .and_then(|pointer| {
+                        self.bookmarks.iter().find(|bookmark| {
+                            (bookmark_x(bookmark.position) - pointer.x).abs() <= BOOKMARK_TICK_HIT_WIDTH
+                        })
+                    })
+                    .cloned();
                 
                 if ui.is_rect_visible(rect) {
                     let painter = ui.painter();
@@ -2396,6 +3885,23 @@ impl eframe::App for HangApp {
                         painter.rect_filled(progress_rect, 4.0, progress_color);
                     }
                     
+                    // Bookmark ticks: gold for ours, green for ones shared by others
+                    if self.duration > 0.0 {
+                        for bookmark in &self.bookmarks {
+                            let x = bookmark_x(bookmark.position);
+                            let hovered = hovered_bookmark.as_ref().is_some_and(|b| b.id == bookmark.id);
+                            let color = if bookmark.author.is_empty() {
+                                egui::Color32::from_rgb(255, 200, 60)
+                            } else {
+                                egui::Color32::from_rgb(120, 220, 140)
+                            };
+                            painter.line_segment(
+                                [egui::pos2(x, rect.min.y + 2.0), egui::pos2(x, rect.max.y - 2.0)],
+                                egui::Stroke::new(if hovered { 3.0 } else { 2.0 }, color),
+                            );
+                        }
+                    }
+                    
                     // Playhead (draggable handle)
                     let handle_x = track_rect.min.x + progress_width;
                     let handle_radius = if response.hovered() || response.dragged() { 8.0 } else { 6.0 };
@@ -2442,6 +3948,24 @@ impl eframe::App for HangApp {
                     }
                 }
                 
+                // Clicking a bookmark tick jumps to the exact moment
+                if let Some(bookmark) = &hovered_bookmark {
+                    if response.clicked() {
+                        position = bookmark.position;
+                    }
+                    let text = if bookmark.author.is_empty() {
+                        format!("🔖 {} · {}", bookmark.label, format_time(bookmark.position))
+                    } else {
+                        format!(
+                            "🔖 {} · {} (by {})",
+                            bookmark.label,
+                            format_time(bookmark.position),
+                            bookmark.author
+                        )
+                    };
+                    response.clone().on_hover_text_at_pointer(text);
+                }
+                
                 if response.drag_stopped() || response.clicked() {
                     self.seek(position);
                 }
@@ -2484,6 +4008,13 @@ impl eframe::App for HangApp {
                     if ui.button("⏭").clicked() {
                         let _ = self.player.frame_step_forward();
                     }
+                    if ui
+                        .add_enabled(self.video_hash.is_some(), egui::Button::new("🔖"))
+                        .on_hover_text("Bookmarks (B)")
+                        .clicked()
+                    {
+                        self.open_bookmarks();
+                    }
 
                     ui.separator();
 
@@ -2529,7 +4060,7 @@ impl eframe::App for HangApp {
                 }
 
                 ui.add_space(4.0);
-                ui.small("Keys: Space toggles playback · ←/→ seek 5s · ↑/↓ volume · F fullscreen");
+                ui.small("Keys: Space toggles playback · ←/→ seek 5s · ↑/↓ volume · F fullscreen · B bookmarks");
             });
         }
 
@@ -2618,6 +4149,52 @@ impl eframe::App for HangApp {
                             let _ = self.player.set_subtitle_track(track.id);
                         }
                     }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.heading("Sync Offsets");
+                    ui.small("Positive values delay; saved for this video");
+                    let mut subtitle_delay = self.subtitle_delay_ms;
+                    let mut audio_delay = self.audio_delay_ms;
+                    // Written once a drag ends rather than on every frame of it
+                    let mut save_delays = false;
+                    ui.add_enabled_ui(self.video_hash.is_some(), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Subtitles:");
+                            let slider = ui.add(
+                                egui::Slider::new(&mut subtitle_delay, -DELAY_LIMIT_MS..=DELAY_LIMIT_MS)
+                                    .step_by(DELAY_STEP_MS as f64)
+                                    .suffix(" ms"),
+                            );
+                            save_delays |= slider.drag_stopped() || (slider.changed() && !slider.dragged());
+                            if ui.small_button("Reset").clicked() {
+                                subtitle_delay = 0;
+                                save_delays = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Audio:");
+                            let slider = ui.add(
+                                egui::Slider::new(&mut audio_delay, -DELAY_LIMIT_MS..=DELAY_LIMIT_MS)
+                                    .step_by(DELAY_STEP_MS as f64)
+                                    .suffix(" ms"),
+                            );
+                            save_delays |= slider.drag_stopped() || (slider.changed() && !slider.dragged());
+                            if ui.small_button("Reset").clicked() {
+                                audio_delay = 0;
+                                save_delays = true;
+                            }
+                        });
+                    });
+                    if subtitle_delay != self.subtitle_delay_ms {
+                        self.set_subtitle_delay(subtitle_delay);
+                    }
+                    if audio_delay != self.audio_delay_ms {
+                        self.set_audio_delay(audio_delay);
+                    }
+                    if save_delays {
+                        self.save_media_settings();
+                    }
                 });
             self.show_settings = settings_open;
             
@@ -2863,7 +4440,7 @@ impl eframe::App for HangApp {
                     ui.painter().text(
                         rect.center(),
                         egui::Align2::CENTER_CENTER,
-                        "📁 Drop video file here",
+                        "📁 Drop video or subtitle file here",
                         egui::FontId::proportional(24.0),
                         egui::Color32::WHITE,
                     );
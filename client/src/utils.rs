@@ -1,5 +1,6 @@
 use sha2::{Digest, Sha256};
-use std::io::{self, ErrorKind};
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
 use std::path::Path;
 
 /// Compute SHA256 hash based only on the file name
@@ -15,6 +16,32 @@ pub fn compute_file_hash<P: AsRef<Path>>(path: P) -> io::Result<String> {
     Ok(format!("{:x}", hasher.finalize()))
 }
 
+/// Bytes read from each end of a file for its fingerprint
+const FINGERPRINT_CHUNK: u64 = 64 * 1024;
+
+/// Identifies a local file by its content rather than its name: SHA256 over the size and the
+/// first and last 64 KiB, so renamed copies match and different files with one name don't.
+/// Used for watch history and per-video settings; rooms keep matching on [`compute_file_hash`].
+pub fn compute_media_fingerprint<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+    let mut chunk = Vec::with_capacity(FINGERPRINT_CHUNK as usize);
+    file.by_ref().take(FINGERPRINT_CHUNK).read_to_end(&mut chunk)?;
+    hasher.update(&chunk);
+    if size > FINGERPRINT_CHUNK {
+        chunk.clear();
+        // Small files: only what the first chunk didn't cover
+        let tail_start = (size - FINGERPRINT_CHUNK).max(FINGERPRINT_CHUNK);
+        file.seek(SeekFrom::Start(tail_start))?;
+        file.take(FINGERPRINT_CHUNK).read_to_end(&mut chunk)?;
+        hasher.update(&chunk);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 /// Compute SHA256 hash of a string (for URLs)
 pub fn compute_string_hash(input: &str) -> String {
     let mut hasher = Sha256::new();
@@ -35,3 +62,49 @@ pub fn format_time(seconds: f64) -> String {
         format!("{:02}:{:02}", minutes, secs)
     }
 }
+
+/// Current Unix time in milliseconds
+pub fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Format a countdown such as `2d 03:15:09` or `14:05`
+pub fn format_countdown(remaining_ms: u64) -> String {
+    let total_secs = remaining_ms.div_ceil(1000);
+    let days = total_secs / 86_400;
+    let clock = format_time((total_secs % 86_400) as f64);
+    if days > 0 {
+        format!("{}d {}", days, clock)
+    } else {
+        clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprints_follow_content_not_names() {
+        let dir = std::env::temp_dir().join(format!("hang-fingerprint-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Large enough that the head and tail chunks are both read
+        let mut movie = vec![7u8; 3 * FINGERPRINT_CHUNK as usize];
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.join(name);
+            std::fs::write(&path, data).unwrap();
+            compute_media_fingerprint(path).unwrap()
+        };
+
+        let original = write("movie.mkv", &movie);
+        assert_eq!(write("renamed.mkv", &movie), original);
+        *movie.last_mut().unwrap() = 8;
+        assert_ne!(write("edited.mkv", &movie), original);
+        assert_ne!(write("short.mkv", b"movie"), original);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::json_store::{JsonStore, Timestamped};
+use crate::sync::get_data_directory;
+use crate::utils::{compute_media_fingerprint, unix_millis};
+
+/// Positions closer than this to the start aren't worth offering to resume
+const MIN_RESUME_SECS: f64 = 30.0;
+/// Media counts as finished once less than this much, or 5% of it, is left
+const FINISHED_MARGIN_SECS: f64 = 60.0;
+
+/// Where playback of one video stopped, keyed by the file's content fingerprint, or the URL
+/// hash for streams
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchEntry {
+    /// Local path, URL, or YouTube URL to reopen the media from
+    pub source: String,
+    pub title: String,
+    #[serde(default)]
+    pub position: f64,
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub last_watched_ms: u64,
+    /// Room the media was last watched in
+    #[serde(default)]
+    pub room_id: Option<String>,
+}
+
+impl WatchEntry {
+    /// Share of the media watched, from 0 to 1; 0 while the duration is unknown
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.position / self.duration).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Position to offer when the media is reopened, unless it was barely started or finished
+    pub fn resume_position(&self) -> Option<f64> {
+        let finished_at = self.duration - FINISHED_MARGIN_SECS.max(self.duration * 0.05);
+        (self.position >= MIN_RESUME_SECS && (self.duration <= 0.0 || self.position < finished_at))
+            .then_some(self.position)
+    }
+}
+
+impl Timestamped for WatchEntry {
+    fn updated_at_ms(&self) -> u64 {
+        self.last_watched_ms
+    }
+}
+
+/// JSON file of [`WatchEntry`]s in the data directory
+pub struct WatchHistory {
+    entries: JsonStore<WatchEntry>,
+}
+
+impl WatchHistory {
+    /// Reads the history, importing the plain recent files list older versions kept
+    pub fn load() -> Self {
+        let dir = get_data_directory();
+        let mut history = Self::open(dir.as_ref().map(|dir| dir.join("watch_history.json")));
+        if let Some(legacy) = dir.map(|dir| dir.join("recent_files.txt")) {
+            if history.entries.is_empty() && legacy.exists() {
+                history.import_recent_files(&legacy);
+                history.save();
+            }
+        }
+        history
+    }
+
+    fn open(path: Option<PathBuf>) -> Self {
+        Self {
+            entries: JsonStore::open(path, "watch history"),
+        }
+    }
+
+    fn import_recent_files(&mut self, legacy: &Path) {
+        let Ok(contents) = fs::read_to_string(legacy) else {
+            return;
+        };
+        let now = unix_millis();
+        // Newest first in the file; keep that order through the timestamps
+        for (age, line) in contents.lines().enumerate() {
+            let path = Path::new(line);
+            // Files that have gone missing since can't be reopened anyway
+            let Ok(fingerprint) = compute_media_fingerprint(path) else {
+                continue;
+            };
+            let title = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if self.entries.get(&fingerprint).is_some() {
+                continue;
+            }
+            let entry = WatchEntry {
+                source: line.to_string(),
+                title,
+                position: 0.0,
+                duration: 0.0,
+                last_watched_ms: now.saturating_sub(1 + age as u64),
+                room_id: None,
+            };
+            self.entries.insert(&fingerprint, entry);
+        }
+    }
+
+    pub fn get(&self, media_hash: &str) -> Option<&WatchEntry> {
+        self.entries.get(media_hash)
+    }
+
+    /// Most recently watched first
+    pub fn recent(&self, limit: usize) -> Vec<(&str, &WatchEntry)> {
+        let mut recent: Vec<_> = self.entries.iter().collect();
+        recent.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_watched_ms));
+        recent.truncate(limit);
+        recent
+    }
+
+    /// Updates the entry in memory; call [`WatchHistory::save`] to write it out.
+    /// Entries for the same source under another key, e.g. the file-name hashes older
+    /// versions used or a file since re-encoded, are replaced.
+    pub fn record(&mut self, media_hash: &str, mut entry: WatchEntry) {
+        entry.last_watched_ms = unix_millis();
+        self.entries
+            .retain(|hash, existing| hash == media_hash || existing.source != entry.source);
+        self.entries.insert(media_hash, entry);
+    }
+
+    pub fn remove(&mut self, media_hash: &str) {
+        self.entries.remove(media_hash);
+        self.save();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    pub fn save(&self) {
+        self.entries.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(position: f64, duration: f64) -> WatchEntry {
+        WatchEntry {
+            source: "/videos/movie.mkv".to_string(),
+            title: "movie.mkv".to_string(),
+            position,
+            duration,
+            last_watched_ms: 0,
+            room_id: None,
+        }
+    }
+
+    #[test]
+    fn resume_skips_the_first_moments_and_the_credits() {
+        assert_eq!(entry(2530.0, 5400.0).resume_position(), Some(2530.0));
+        assert_eq!(entry(10.0, 5400.0).resume_position(), None);
+        assert_eq!(entry(5200.0, 5400.0).resume_position(), None);
+        assert_eq!(entry(90.0, 0.0).resume_position(), Some(90.0));
+        assert_eq!(entry(2700.0, 5400.0).progress(), 0.5);
+    }
+
+    #[test]
+    fn history_survives_a_reload_newest_first() {
+        let dir = std::env::temp_dir().join(format!("hang-history-{}", uuid::Uuid::new_v4()));
+        let legacy = dir.join("recent_files.txt");
+        fs::create_dir_all(&dir).unwrap();
+        let (newer, older) = (dir.join("newer.mkv"), dir.join("older.mkv"));
+        fs::write(&newer, "newer").unwrap();
+        fs::write(&older, "older").unwrap();
+        let listed = format!(
+            "{}\n{}\n/videos/deleted.mkv",
+            newer.display(),
+            older.display()
+        );
+        fs::write(&legacy, listed).unwrap();
+
+        let mut history = WatchHistory::open(Some(dir.join("watch_history.json")));
+        history.import_recent_files(&legacy);
+        let titles: Vec<&str> = history
+            .recent(10)
+            .iter()
+            .map(|(_, e)| e.title.as_str())
+            .collect();
+        assert_eq!(titles, ["newer.mkv", "older.mkv"]);
+
+        history.record("abc", entry(2530.0, 5400.0));
+        history.save();
+        let reloaded = WatchHistory::open(Some(dir.join("watch_history.json")));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reloaded.recent(1)[0].0, "abc");
+        assert_eq!(reloaded.get("abc").unwrap().position, 2530.0);
+        assert_eq!(reloaded.recent(10).len(), 3);
+    }
+
+    #[test]
+    fn recording_under_a_new_key_replaces_the_old_entry_for_that_source() {
+        let mut history = WatchHistory::open(None);
+        history.record("name-hash", entry(600.0, 5400.0));
+        history.record("fingerprint", entry(1200.0, 5400.0));
+        history.record(
+            "other",
+            WatchEntry {
+                source: "/videos/other.mkv".to_string(),
+                ..entry(60.0, 5400.0)
+            },
+        );
+
+        assert!(history.get("name-hash").is_none());
+        assert_eq!(history.get("fingerprint").unwrap().position, 1200.0);
+        assert_eq!(history.recent(10).len(), 2);
+    }
+}
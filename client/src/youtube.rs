@@ -11,8 +11,9 @@ use std::os::windows::process::CommandExt;
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 /// Video quality options
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum VideoQuality {
+    #[default]
     Best,
     Quality4K,
     Quality1440p,
@@ -50,7 +51,7 @@ impl VideoQuality {
         ]
     }
 
-    fn to_format_string(&self) -> &'static str {
+    fn to_format_string(self) -> &'static str {
         // Use formats that have both video AND audio in a single stream
         // b = best format with both video and audio
         // The fallback formats ensure we get combined streams that VLC can play
@@ -67,12 +68,6 @@ impl VideoQuality {
     }
 }
 
-impl Default for VideoQuality {
-    fn default() -> Self {
-        VideoQuality::Best
-    }
-}
-
 /// Information about a YouTube video
 #[derive(Debug, Clone)]
 pub struct YouTubeVideo {
@@ -111,8 +106,8 @@ pub fn extract_video_id(url: &str) -> Option<String> {
     if url.contains("youtube.com/watch") {
         if let Some(query) = url.split('?').nth(1) {
             for param in query.split('&') {
-                if param.starts_with("v=") {
-                    return Some(param[2..].to_string());
+                if let Some(id) = param.strip_prefix("v=") {
+                    return Some(id.to_string());
                 }
             }
         }
@@ -122,7 +117,7 @@ pub fn extract_video_id(url: &str) -> Option<String> {
     if url.contains("/shorts/") || url.contains("/live/") {
         return url
             .split(&['/'][..])
-            .last()
+            .next_back()
             .map(|s| s.split(&['?', '&'][..]).next().unwrap_or(s).to_string());
     }
 
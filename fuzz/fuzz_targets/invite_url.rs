@@ -0,0 +1,16 @@
+#![no_main]
+
+use hang_client::invite::{build_invite_url, parse_invite_url};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let Some(link) = parse_invite_url(data) else {
+        return;
+    };
+    // Whatever a link decodes to must survive being rebuilt by the host's client
+    let rebuilt = build_invite_url(&link.room_id, link.invite.as_deref(), link.file_name.as_deref());
+    let reparsed = parse_invite_url(&rebuilt).expect("rebuilt link should parse");
+    assert_eq!(reparsed.room_id, link.room_id);
+    assert_eq!(reparsed.invite, link.invite.filter(|token| !token.is_empty()));
+    assert_eq!(reparsed.file_name, link.file_name.filter(|name| !name.is_empty()));
+});
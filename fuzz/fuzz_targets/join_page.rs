@@ -0,0 +1,24 @@
+#![no_main]
+
+use hang_server::fuzzing::join_page;
+use libfuzzer_sys::fuzz_target;
+
+fn markup_counts(html: &str) -> [usize; 4] {
+    ['<', '>', '"', '\''].map(|ch| html.matches(ch).count())
+}
+
+/// Inert stand-in for a value that keeps whether the page treats it as present
+fn inert(value: &Option<String>) -> Option<String> {
+    value
+        .as_ref()
+        .filter(|value| !value.trim().is_empty())
+        .map(|_| "x".to_string())
+}
+
+fuzz_target!(|params: (Option<String>, Option<String>, Option<String>)| {
+    let (room, invite, file) = params;
+    let baseline = join_page(inert(&room), inert(&invite), inert(&file));
+    let page = join_page(room, invite, file);
+    // Link parameters may only ever add text, never markup
+    assert_eq!(markup_counts(&page), markup_counts(&baseline));
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use std::cell::RefCell;
+
+use hang_server::fuzzing::MessageHarness;
+use libfuzzer_sys::fuzz_target;
+
+thread_local! {
+    // One server per process; rooms and sessions left behind by earlier inputs widen the coverage
+    static HARNESS: RefCell<MessageHarness> = RefCell::new(MessageHarness::new());
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        HARNESS.with(|harness| harness.borrow_mut().handle(text));
+    }
+});
@@ -0,0 +1,367 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::audit::AuditEvent;
+use crate::protocol::{MemberSummary, Message, PlaybackState, RoomListing, RoomSchedule};
+use crate::state::now_millis;
+use crate::webhooks::{CloseReason, WebhookEvent};
+use crate::{broadcast_room_state, notify_member_left, record_audit, AppState};
+
+const DEFAULT_AUDIT_LIMIT: usize = 200;
+const MAX_AUDIT_LIMIT: usize = 5_000;
+
+/// Operator endpoints mounted under `/admin`, guarded by `HANG_ADMIN_TOKEN`
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/rooms", get(list_rooms))
+        .route("/rooms/:room_id", get(room_details).delete(close_room))
+        .route("/rooms/:room_id/audit", get(room_audit))
+        .route("/clients/:client_id/kick", post(kick_client))
+        .route("/notice", post(send_notice))
+}
+
+/// Extractor that rejects requests without the configured bearer token
+struct AdminAuth;
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = AdminError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(expected) = state.config.admin_token.as_deref() else {
+            return Err(AdminError::new(
+                StatusCode::FORBIDDEN,
+                "Admin API disabled; set HANG_ADMIN_TOKEN to enable it",
+            ));
+        };
+        let provided = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        match provided {
+            Some(token) if tokens_match(token, expected) => Ok(AdminAuth),
+            _ => Err(AdminError::new(
+                StatusCode::UNAUTHORIZED,
+                "Invalid admin token",
+            )),
+        }
+    }
+}
+
+/// Compares fixed-length digests in constant time, so neither the contents nor the length
+/// of the expected token leak through timing
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    let provided = Sha256::digest(provided.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+    provided.ct_eq(&expected).into()
+}
+
+struct AdminError {
+    status: StatusCode,
+    message: String,
+}
+
+impl AdminError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(what: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("{what} not found"))
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+type AdminResult<T> = Result<Json<T>, AdminError>;
+
+/// Room id as the server stores it, so every accepted spelling of a code finds the room
+fn canonical_room_id(state: &AppState, raw: &str) -> String {
+    state.server_state.room_code_format().canonicalize(raw)
+}
+
+#[derive(Serialize)]
+struct RoomOverview {
+    room_id: String,
+    host_id: Uuid,
+    member_count: usize,
+    capacity: usize,
+    passcode_protected: bool,
+    file_hash: String,
+    /// Public directory entry, if the host listed the room
+    listing: Option<RoomListing>,
+    /// Watch party reservation, if the room was scheduled
+    schedule: Option<RoomSchedule>,
+    playback: PlaybackState,
+    /// Playback position extrapolated to the time of the request
+    estimated_position: f64,
+}
+
+#[derive(Serialize)]
+struct RoomDetails {
+    #[serde(flatten)]
+    overview: RoomOverview,
+    members: Vec<MemberSummary>,
+}
+
+/// Overview of a room along with its roster across all instances
+async fn room_overview(
+    state: &AppState,
+    room_id: &str,
+) -> Option<(RoomOverview, Vec<MemberSummary>)> {
+    let server_state = &state.server_state;
+    let (host_id, capacity, passcode_protected, file_hash, listing, schedule, playback) =
+        server_state.rooms.get(room_id).map(|room| {
+            (
+                room.host_id,
+                room.capacity,
+                room.passcode_hash.is_some(),
+                room.file_hash.clone(),
+                room.listing.clone(),
+                room.schedule.clone(),
+                room.playback.clone(),
+            )
+        })?;
+    let (members, _) = server_state.room_snapshot(room_id).await?;
+    let overview = RoomOverview {
+        room_id: room_id.to_string(),
+        host_id,
+        member_count: members.len(),
+        capacity,
+        passcode_protected,
+        file_hash,
+        listing,
+        schedule,
+        estimated_position: playback.position_at(now_millis()),
+        playback,
+    };
+    Some((overview, members))
+}
+
+async fn list_rooms(
+    _auth: AdminAuth,
+    State(state): State<AppState>,
+) -> AdminResult<Vec<RoomOverview>> {
+    let room_ids: Vec<String> = state
+        .server_state
+        .rooms
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect();
+    let mut rooms = Vec::with_capacity(room_ids.len());
+    for room_id in room_ids {
+        if let Some((overview, _)) = room_overview(&state, &room_id).await {
+            rooms.push(overview);
+        }
+    }
+    rooms.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+    Ok(Json(rooms))
+}
+
+async fn room_details(
+    _auth: AdminAuth,
+    State(state): State<AppState>,
+    Path(room_id): Path<String>,
+) -> AdminResult<RoomDetails> {
+    let room_id = canonical_room_id(&state, &room_id);
+    let (overview, members) = room_overview(&state, &room_id)
+        .await
+        .ok_or_else(|| AdminError::not_found("Room"))?;
+    Ok(Json(RoomDetails { overview, members }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ReasonBody {
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ClosedRoom {
+    room_id: String,
+    notified: usize,
+}
+
+async fn close_room(
+    _auth: AdminAuth,
+    State(state): State<AppState>,
+    Path(room_id): Path<String>,
+    body: Option<Json<ReasonBody>>,
+) -> AdminResult<ClosedRoom> {
+    let room_id = canonical_room_id(&state, &room_id);
+    let reason = body.and_then(|Json(body)| body.reason);
+    let room_uid = state.server_state.room_uid(&room_id).unwrap_or_default();
+    let members = state
+        .server_state
+        .close_room(&room_id)
+        .await
+        .ok_or_else(|| AdminError::not_found("Room"))?;
+    state.audit.record(
+        &room_id,
+        None,
+        None,
+        AuditEvent::RoomClosed {
+            reason: reason.clone(),
+        },
+    );
+
+    let notice = Message::RoomClosed {
+        room_id: room_id.clone(),
+        reason: reason.clone(),
+    };
+    let senders = state.client_senders.read().await;
+    for member_id in &members {
+        state.server_state.clear_session(*member_id);
+        if let Some(tx) = senders.get(member_id) {
+            let _ = tx.send(notice.clone());
+        }
+    }
+    state.cluster.room_closed(&room_id, room_uid, reason);
+    state.webhooks.emit(
+        &room_id,
+        WebhookEvent::RoomClosed {
+            reason: CloseReason::Admin,
+        },
+    );
+    tracing::info!(%room_id, members = members.len(), "Admin closed room");
+    Ok(Json(ClosedRoom {
+        room_id,
+        notified: members.len(),
+    }))
+}
+
+#[derive(Serialize)]
+struct KickedClient {
+    client_id: Uuid,
+    room_id: String,
+}
+
+async fn kick_client(
+    _auth: AdminAuth,
+    State(state): State<AppState>,
+    Path(client_id): Path<Uuid>,
+    body: Option<Json<ReasonBody>>,
+) -> AdminResult<KickedClient> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    let display_name = state.server_state.display_name(client_id);
+    let room_id = state
+        .server_state
+        .leave_room(client_id)
+        .await
+        .ok_or_else(|| AdminError::not_found("Client in a room"))?;
+    state.server_state.clear_session(client_id);
+    state.server_state.block_rejoin(&room_id, client_id);
+    record_audit(
+        &state,
+        &room_id,
+        client_id,
+        AuditEvent::Kicked {
+            reason: reason.clone(),
+        },
+    );
+
+    if let Some(tx) = state.client_senders.read().await.get(&client_id) {
+        let _ = tx.send(Message::Kicked { reason });
+    }
+    broadcast_room_state(&state, &room_id).await;
+    notify_member_left(&state, &room_id, client_id, display_name).await;
+    tracing::info!(%client_id, %room_id, "Admin kicked client");
+    Ok(Json(KickedClient { client_id, room_id }))
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    limit: Option<usize>,
+}
+
+/// Recent audit events for a room, oldest first; works after the room has closed
+async fn room_audit(
+    _auth: AdminAuth,
+    State(state): State<AppState>,
+    Path(room_id): Path<String>,
+    Query(query): Query<AuditQuery>,
+) -> AdminResult<Vec<serde_json::Value>> {
+    if !state.audit.is_enabled() {
+        return Err(AdminError::new(
+            StatusCode::NOT_FOUND,
+            "Audit log disabled; set HANG_AUDIT_LOG to enable it",
+        ));
+    }
+    let room_id = canonical_room_id(&state, &room_id);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+    Ok(Json(state.audit.recent(&room_id, limit).await))
+}
+
+#[derive(Deserialize)]
+struct NoticeBody {
+    message: String,
+    /// Limit the notice to one room; broadcast to every client when omitted
+    room_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct NoticeDelivery {
+    delivered: usize,
+}
+
+async fn send_notice(
+    _auth: AdminAuth,
+    State(state): State<AppState>,
+    Json(body): Json<NoticeBody>,
+) -> AdminResult<NoticeDelivery> {
+    let message = body.message.trim().to_string();
+    if message.is_empty() {
+        return Err(AdminError::new(
+            StatusCode::BAD_REQUEST,
+            "Notice message is empty",
+        ));
+    }
+
+    let recipients: Vec<Uuid> = match &body.room_id {
+        Some(room_id) => {
+            let room_id = canonical_room_id(&state, room_id);
+            if !state.server_state.rooms.contains_key(&room_id) {
+                return Err(AdminError::not_found("Room"));
+            }
+            state.server_state.get_room_members(&room_id).await
+        }
+        None => state.client_senders.read().await.keys().copied().collect(),
+    };
+
+    let notice = Message::ServerNotice { message };
+    let senders = state.client_senders.read().await;
+    let mut delivered = 0;
+    for client_id in recipients {
+        if let Some(tx) = senders.get(&client_id) {
+            if tx.send(notice.clone()).is_ok() {
+                delivered += 1;
+            }
+        }
+    }
+    Ok(Json(NoticeDelivery { delivered }))
+}
@@ -0,0 +1,325 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::config::ServerConfig;
+use crate::protocol::SyncCommand;
+use crate::state::now_millis;
+
+/// Something that happened in a room, kept for abuse reports
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    RoomCreated {
+        display_name: String,
+    },
+    Joined {
+        display_name: String,
+        spectator: bool,
+    },
+    /// Failed join, including wrong or missing passcodes
+    JoinRejected {
+        display_name: String,
+        reason: String,
+    },
+    Resumed {
+        display_name: String,
+    },
+    /// The host minted an invite token; the token itself is not logged
+    InviteCreated {
+        max_uses: Option<u32>,
+        expires_at_ms: Option<u64>,
+    },
+    InviteRevoked,
+    Left {
+        display_name: String,
+    },
+    Sync {
+        command: SyncCommand,
+    },
+    /// A spectator tried to control playback
+    SyncRejected {
+        command: SyncCommand,
+    },
+    /// A member shared a subtitle file; its content is not logged
+    SubtitleShared {
+        file_name: String,
+        bytes: usize,
+    },
+    BookmarkShared {
+        position: f64,
+        label: String,
+    },
+    Kicked {
+        reason: Option<String>,
+    },
+    RoomClosed {
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct AuditRecord {
+    timestamp_ms: u64,
+    room_id: String,
+    /// `None` for operator actions on the room as a whole
+    client_id: Option<Uuid>,
+    remote_ip: Option<IpAddr>,
+    #[serde(flatten)]
+    event: AuditEvent,
+}
+
+/// Handle for appending to the audit log; cheap to clone, writes happen on a background thread
+#[derive(Clone)]
+pub struct AuditLog {
+    inner: Option<AuditSink>,
+}
+
+#[derive(Clone)]
+struct AuditSink {
+    tx: mpsc::UnboundedSender<AuditRecord>,
+    files: RotatingFiles,
+}
+
+impl AuditLog {
+    /// Starts the writer thread, or returns a no-op handle when no path is configured
+    pub fn spawn(config: &ServerConfig) -> Self {
+        let Some(path) = config.audit_log_path.clone() else {
+            return Self { inner: None };
+        };
+        let files = RotatingFiles {
+            path,
+            max_bytes: config.audit_log_max_bytes,
+            keep: config.audit_log_files,
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let writer = files.clone();
+        // A plain thread rather than `spawn_blocking`, which would hold up runtime shutdown
+        if let Err(err) = std::thread::Builder::new()
+            .name("hang-audit".to_string())
+            .spawn(move || writer.run(rx))
+        {
+            tracing::error!(error = %err, "Failed to start audit log writer");
+            return Self { inner: None };
+        }
+        tracing::info!(path = %files.path.display(), "Audit log enabled");
+        Self {
+            inner: Some(AuditSink { tx, files }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    pub fn record(
+        &self,
+        room_id: &str,
+        client_id: Option<Uuid>,
+        remote_ip: Option<IpAddr>,
+        event: AuditEvent,
+    ) {
+        let Some(sink) = &self.inner else {
+            return;
+        };
+        let _ = sink.tx.send(AuditRecord {
+            timestamp_ms: now_millis(),
+            room_id: room_id.to_string(),
+            client_id,
+            remote_ip,
+            event,
+        });
+    }
+
+    /// Most recent events for `room_id`, oldest first, read back from the log files
+    pub async fn recent(&self, room_id: &str, limit: usize) -> Vec<serde_json::Value> {
+        let Some(sink) = &self.inner else {
+            return Vec::new();
+        };
+        let files = sink.files.clone();
+        let room_id = room_id.to_string();
+        tokio::task::spawn_blocking(move || files.scan(&room_id, limit))
+            .await
+            .unwrap_or_default()
+    }
+}
+
+/// `audit.log`, rotated to `audit.log.1` ... `audit.log.<keep>` once it grows past `max_bytes`
+#[derive(Clone)]
+struct RotatingFiles {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFiles {
+    fn run(self, mut rx: mpsc::UnboundedReceiver<AuditRecord>) {
+        let mut file = None;
+        while let Some(record) = rx.blocking_recv() {
+            let mut line = match serde_json::to_vec(&record) {
+                Ok(line) => line,
+                Err(err) => {
+                    tracing::error!(error = %err, "Failed to serialize audit event");
+                    continue;
+                }
+            };
+            line.push(b'\n');
+            if let Err(err) = self.append(&mut file, &line) {
+                tracing::error!(path = %self.path.display(), error = %err, "Failed to write audit log");
+                file = None;
+            }
+        }
+    }
+
+    fn append(&self, file: &mut Option<File>, line: &[u8]) -> std::io::Result<()> {
+        let size = fs::metadata(&self.path).map(|meta| meta.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            *file = None;
+            self.rotate()?;
+        }
+        if file.is_none() {
+            if let Some(parent) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            *file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?,
+            );
+        }
+        match file {
+            Some(file) => file.write_all(line),
+            None => Ok(()),
+        }
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn scan(&self, room_id: &str, limit: usize) -> Vec<serde_json::Value> {
+        let mut recent = VecDeque::with_capacity(limit);
+        let oldest_first = (1..=self.keep)
+            .rev()
+            .map(|index| self.rotated(index))
+            .chain(std::iter::once(self.path.clone()));
+        for path in oldest_first {
+            for event in Self::read_room_events(&path, room_id) {
+                if recent.len() == limit {
+                    recent.pop_front();
+                }
+                recent.push_back(event);
+            }
+        }
+        recent.into()
+    }
+
+    fn read_room_events<'a>(
+        path: &Path,
+        room_id: &'a str,
+    ) -> impl Iterator<Item = serde_json::Value> + 'a {
+        let lines = File::open(path)
+            .map(|file| BufReader::new(file).lines())
+            .into_iter()
+            .flatten();
+        lines
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(&line).ok())
+            .filter(move |event| event["room_id"] == room_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_files(max_bytes: u64, keep: usize) -> RotatingFiles {
+        let dir = std::env::temp_dir().join(format!("hang-audit-{}", Uuid::new_v4()));
+        RotatingFiles {
+            path: dir.join("audit.log"),
+            max_bytes,
+            keep,
+        }
+    }
+
+    /// Same length for every `index` below 100, so tests can size files in whole lines
+    fn line(index: usize) -> Vec<u8> {
+        let record = AuditRecord {
+            timestamp_ms: 0,
+            room_id: "123-456".to_string(),
+            client_id: None,
+            remote_ip: None,
+            event: AuditEvent::Left {
+                display_name: format!("guest-{index:02}"),
+            },
+        };
+        let mut line = serde_json::to_vec(&record).unwrap();
+        line.push(b'\n');
+        line
+    }
+
+    fn names(events: &[serde_json::Value]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|event| event["display_name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rotates_past_max_bytes_and_keeps_only_the_newest_files() {
+        let files = scratch_files(2 * line(0).len() as u64, 2);
+        let mut file = None;
+        for index in 0..7 {
+            files.append(&mut file, &line(index)).unwrap();
+        }
+
+        // Two lines per file: guests 0 and 1 fell off the end with the third rotation
+        for path in [&files.path, &files.rotated(1), &files.rotated(2)] {
+            assert!(fs::metadata(path).unwrap().len() <= files.max_bytes);
+        }
+        assert!(!files.rotated(3).exists());
+        assert_eq!(
+            names(&files.scan("123-456", 10)),
+            ["guest-02", "guest-03", "guest-04", "guest-05", "guest-06"]
+        );
+        assert_eq!(names(&files.scan("123-456", 2)), ["guest-05", "guest-06"]);
+        assert!(files.scan("654-321", 10).is_empty());
+
+        fs::remove_dir_all(files.path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn keeping_no_files_truncates_instead_of_rotating() {
+        let files = scratch_files(line(0).len() as u64, 0);
+        let mut file = None;
+        for index in 0..3 {
+            files.append(&mut file, &line(index)).unwrap();
+        }
+
+        assert!(!files.rotated(1).exists());
+        assert_eq!(names(&files.scan("123-456", 10)), ["guest-02"]);
+
+        fs::remove_dir_all(files.path.parent().unwrap()).unwrap();
+    }
+}
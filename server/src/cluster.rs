@@ -0,0 +1,475 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::protocol::{MemberSummary, Message, RoomMirror};
+use crate::pubsub::{Delivery, PubSub};
+use crate::AppState;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// Peers silent for this long are assumed dead and their members dropped
+const PEER_TIMEOUT: Duration = Duration::from_secs(35);
+
+/// Room changes exchanged between instances so members of one room can sit on different servers.
+/// Events about a room carry its `uid`, so a room whose code collided with one elsewhere
+/// ignores the other room's traffic.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ClusterEvent {
+    /// Room settings, sent when the room is created or changes hands
+    Room {
+        room_id: String,
+        room: RoomMirror,
+    },
+    /// The sender's members of a room, replacing what it sent before
+    Roster {
+        room_id: String,
+        #[serde(default)]
+        room_uid: Uuid,
+        members: Vec<MemberSummary>,
+    },
+    /// A message for every member of the room
+    Deliver {
+        room_id: String,
+        #[serde(default)]
+        room_uid: Uuid,
+        message: Message,
+    },
+    /// The room was closed by an operator
+    Closed {
+        room_id: String,
+        #[serde(default)]
+        room_uid: Uuid,
+        reason: Option<String>,
+    },
+    /// An instance that just (re)subscribed asks its peers to announce their rooms
+    Hello,
+    Heartbeat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    origin: Uuid,
+    #[serde(flatten)]
+    event: ClusterEvent,
+}
+
+/// Handle for publishing this instance's room changes; cheap to clone
+#[derive(Clone)]
+pub struct Cluster {
+    instance_id: Uuid,
+    pubsub: Arc<dyn PubSub>,
+}
+
+impl Cluster {
+    pub fn new(pubsub: Arc<dyn PubSub>) -> Self {
+        Self {
+            instance_id: Uuid::new_v4(),
+            pubsub,
+        }
+    }
+
+    fn publish(&self, event: ClusterEvent) {
+        let envelope = Envelope {
+            origin: self.instance_id,
+            event,
+        };
+        match serde_json::to_string(&envelope) {
+            Ok(payload) => self.pubsub.publish(payload),
+            Err(err) => tracing::error!(error = %err, "Failed to serialize cluster event"),
+        }
+    }
+
+    /// Shares the room's settings so other instances can accept joins for it
+    pub fn announce_room(&self, app: &AppState, room_id: &str) {
+        if let Some(room) = app.server_state.room_for_mirror(room_id) {
+            self.publish(ClusterEvent::Room {
+                room_id: room_id.to_string(),
+                room,
+            });
+        }
+    }
+
+    pub fn publish_roster(&self, app: &AppState, room_id: &str, members: Vec<MemberSummary>) {
+        self.publish(ClusterEvent::Roster {
+            room_id: room_id.to_string(),
+            room_uid: app.server_state.room_uid(room_id).unwrap_or_default(),
+            members,
+        });
+    }
+
+    /// Relays a message to the room's members on other instances
+    pub fn deliver(&self, app: &AppState, room_id: &str, message: &Message) {
+        self.publish(ClusterEvent::Deliver {
+            room_id: room_id.to_string(),
+            room_uid: app.server_state.room_uid(room_id).unwrap_or_default(),
+            message: message.clone(),
+        });
+    }
+
+    /// Takes the uid explicitly since the room is already gone from this instance
+    pub fn room_closed(&self, room_id: &str, room_uid: Uuid, reason: Option<String>) {
+        self.publish(ClusterEvent::Closed {
+            room_id: room_id.to_string(),
+            room_uid,
+            reason,
+        });
+    }
+}
+
+/// Applies events from other instances and keeps peers informed that this one is alive
+pub fn spawn(app: AppState) {
+    let mut events = app.cluster.pubsub.subscribe();
+    tokio::spawn(async move {
+        let cluster = app.cluster.clone();
+        let mut peers: HashMap<Uuid, Instant> = HashMap::new();
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            tokio::select! {
+                delivery = events.next() => {
+                    let payload = match delivery {
+                        Some(Delivery::Payload(payload)) => payload,
+                        Some(Delivery::Subscribed) => {
+                            resync(&app, &mut peers).await;
+                            continue;
+                        }
+                        None => {
+                            tracing::warn!("Cluster event stream ended");
+                            return;
+                        }
+                    };
+                    let envelope = match serde_json::from_str::<Envelope>(&payload) {
+                        Ok(envelope) => envelope,
+                        Err(err) => {
+                            tracing::warn!(error = %err, "Ignoring malformed cluster event");
+                            continue;
+                        }
+                    };
+                    if envelope.origin == cluster.instance_id {
+                        continue;
+                    }
+                    if peers.insert(envelope.origin, Instant::now()).is_none() {
+                        tracing::info!(peer = %envelope.origin, "Cluster peer discovered");
+                    }
+                    apply(&app, envelope.origin, envelope.event).await;
+                }
+                _ = heartbeat.tick() => {
+                    cluster.publish(ClusterEvent::Heartbeat);
+                    let now = Instant::now();
+                    let dead: Vec<Uuid> = peers
+                        .iter()
+                        .filter(|(_, seen)| now.duration_since(**seen) > PEER_TIMEOUT)
+                        .map(|(peer, _)| *peer)
+                        .collect();
+                    for peer in dead {
+                        peers.remove(&peer);
+                        tracing::warn!(%peer, "Cluster peer timed out; dropping its members");
+                        for room_id in app.server_state.forget_instance(peer).await {
+                            send_room_state(&app, &room_id).await;
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Starts over after a (re)subscribe, since any event published while the subscription was
+/// down is lost: rosters from peers are dropped, and peers are asked to announce their rooms
+/// again while this instance announces its own.
+async fn resync(app: &AppState, peers: &mut HashMap<Uuid, Instant>) {
+    for (peer, _) in peers.drain() {
+        for room_id in app.server_state.forget_instance(peer).await {
+            send_room_state(app, &room_id).await;
+        }
+    }
+    app.cluster.publish(ClusterEvent::Hello);
+    announce_local_rooms(app).await;
+}
+
+/// Publishes the settings and roster of every room with members here
+async fn announce_local_rooms(app: &AppState) {
+    let state = &app.server_state;
+    for room_id in state.rooms_with_local_members().await {
+        app.cluster.announce_room(app, &room_id);
+        let roster = state.local_roster(&room_id).await;
+        app.cluster.publish_roster(app, &room_id, roster);
+    }
+}
+
+async fn apply(app: &AppState, origin: Uuid, event: ClusterEvent) {
+    let state = &app.server_state;
+    match event {
+        ClusterEvent::Room { room_id, room } => {
+            state.mirror_room(&room_id, room);
+        }
+        ClusterEvent::Roster {
+            room_id,
+            room_uid,
+            members,
+        } => {
+            if !state.is_same_room(&room_id, room_uid) {
+                return;
+            }
+            if state.set_remote_members(&room_id, origin, members).await {
+                send_room_state(app, &room_id).await;
+            }
+        }
+        ClusterEvent::Deliver {
+            room_id,
+            room_uid,
+            message,
+        } => {
+            if !state.is_same_room(&room_id, room_uid) {
+                return;
+            }
+            match &message {
+                Message::SyncBroadcast { command, .. } => state.record_playback(&room_id, command),
+                Message::TracksSelected { selection } => {
+                    state.record_track_selection(&room_id, selection)
+                }
+                Message::BookmarkShared { bookmark, .. } => {
+                    state.record_bookmark(&room_id, bookmark)
+                }
+                Message::ScheduledStart { .. } => {
+                    if let Some(schedule) = state.room_schedule(&room_id) {
+                        state.begin_scheduled_start(&room_id, schedule.starts_at_ms);
+                    }
+                }
+                _ => {}
+            }
+            // Feeds are for overlays and don't need file contents
+            if !matches!(message, Message::SubtitleShared { .. }) {
+                state.publish_to_feed(&room_id, &message);
+            }
+            send_to_local_members(app, &room_id, &message).await;
+        }
+        ClusterEvent::Closed {
+            room_id,
+            room_uid,
+            reason,
+        } => {
+            if !state.is_same_room(&room_id, room_uid) {
+                return;
+            }
+            let Some(members) = state.close_room(&room_id).await else {
+                return;
+            };
+            let notice = Message::RoomClosed {
+                room_id: room_id.clone(),
+                reason,
+            };
+            let senders = app.client_senders.read().await;
+            for member_id in &members {
+                state.clear_session(*member_id);
+                if let Some(tx) = senders.get(member_id) {
+                    let _ = tx.send(notice.clone());
+                }
+            }
+        }
+        ClusterEvent::Hello => announce_local_rooms(app).await,
+        ClusterEvent::Heartbeat => {}
+    }
+}
+
+/// Sends the merged roster to this instance's members without echoing it back to peers
+async fn send_room_state(app: &AppState, room_id: &str) {
+    let Some((members, capacity)) = app.server_state.room_snapshot(room_id).await else {
+        return;
+    };
+    let update = Message::RoomMemberUpdate {
+        room_id: room_id.to_string(),
+        members,
+        capacity,
+    };
+    app.server_state.publish_to_feed(room_id, &update);
+    send_to_local_members(app, room_id, &update).await;
+}
+
+async fn send_to_local_members(app: &AppState, room_id: &str, message: &Message) {
+    let members = app.server_state.get_room_members(room_id).await;
+    let senders = app.client_senders.read().await;
+    for member_id in members {
+        if let Some(tx) = senders.get(&member_id) {
+            let _ = tx.send(message.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::SyncCommand;
+    use crate::pubsub::MemoryPubSub;
+    use crate::state::{JoinRequest, RoomRequest};
+    use crate::ServerConfig;
+    use futures_util::FutureExt;
+
+    fn room_request() -> RoomRequest {
+        RoomRequest {
+            file_hash: "movie-hash".to_string(),
+            passcode: None,
+            display_name: Some("Host".to_string()),
+            capacity: None,
+            listing: None,
+            requested_code: None,
+            schedule: None,
+            spectator_capacity: None,
+            sync_tracks: false,
+        }
+    }
+
+    fn pause_at(room_id: &str, room_uid: Uuid, timestamp: f64) -> ClusterEvent {
+        ClusterEvent::Deliver {
+            room_id: room_id.to_string(),
+            room_uid,
+            message: Message::SyncBroadcast {
+                from_client: Uuid::new_v4(),
+                command: SyncCommand::Pause { timestamp },
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn resubscribing_drops_peer_rosters_and_announces_local_rooms() {
+        let bus = MemoryPubSub::new();
+        let mut published = bus.subscribe();
+        let app = crate::build_app_state(ServerConfig::default(), Arc::new(bus));
+        let state = &app.server_state;
+        let (ours, ..) = state.create_room(Uuid::new_v4(), room_request());
+
+        // A room that lives on a peer, whose close we will have missed
+        let peer = Uuid::new_v4();
+        let (theirs, ..) = state.create_room(Uuid::new_v4(), room_request());
+        let mirror = state.room_for_mirror(&theirs).unwrap();
+        state.close_room(&theirs).await;
+        assert!(state.mirror_room(&theirs, mirror));
+        let remote_member = MemberSummary {
+            client_id: Uuid::new_v4(),
+            display_name: "Remote".to_string(),
+            is_host: true,
+            is_spectator: false,
+        };
+        assert!(
+            state
+                .set_remote_members(&theirs, peer, vec![remote_member])
+                .await
+        );
+
+        let mut peers = HashMap::from([(peer, Instant::now())]);
+        resync(&app, &mut peers).await;
+        assert!(peers.is_empty());
+        assert!(state.room_uid(&theirs).is_none());
+
+        let mut events = Vec::new();
+        while let Some(Some(delivery)) = published.next().now_or_never() {
+            if let Delivery::Payload(payload) = delivery {
+                events.push(serde_json::from_str::<Envelope>(&payload).unwrap().event);
+            }
+        }
+        assert!(matches!(events[0], ClusterEvent::Hello));
+        assert!(matches!(&events[1], ClusterEvent::Room { room_id, .. } if *room_id == ours));
+        assert!(
+            matches!(&events[2], ClusterEvent::Roster { room_id, members, .. } if *room_id == ours && members.len() == 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn announcements_leave_out_bearer_tokens_but_invites_still_work_elsewhere() {
+        let origin =
+            crate::build_app_state(ServerConfig::default(), Arc::new(MemoryPubSub::new()));
+        let host_id = Uuid::new_v4();
+        origin.server_state.add_client(host_id, None);
+        let mut request = room_request();
+        request.passcode = Some("hunter2".to_string());
+        let (room_id, ..) = origin.server_state.create_room(host_id, request);
+        let (_, token, _) = origin
+            .server_state
+            .create_invite(host_id, None, None)
+            .unwrap();
+        let feed_token = origin.server_state.feed_token(&room_id).unwrap();
+
+        let mirror = origin.server_state.room_for_mirror(&room_id).unwrap();
+        let announced = serde_json::to_string(&ClusterEvent::Room {
+            room_id: room_id.clone(),
+            room: mirror.clone(),
+        })
+        .unwrap();
+        assert!(!announced.contains(&token));
+        assert!(!announced.contains(&feed_token));
+
+        let peer = crate::build_app_state(ServerConfig::default(), Arc::new(MemoryPubSub::new()));
+        assert!(peer.server_state.mirror_room(&room_id, mirror));
+        assert_eq!(peer.server_state.feed_token(&room_id), None);
+        let guest_id = Uuid::new_v4();
+        peer.server_state.add_client(guest_id, None);
+        let join = JoinRequest {
+            file_hash: "movie-hash".to_string(),
+            passcode: None,
+            invite: Some(token),
+            display_name: None,
+            spectator: false,
+        };
+        assert!(peer
+            .server_state
+            .join_room(guest_id, &room_id, join)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn a_room_that_got_the_same_code_elsewhere_is_kept_apart() {
+        let app = crate::build_app_state(ServerConfig::default(), Arc::new(MemoryPubSub::new()));
+        let state = &app.server_state;
+        let host_id = Uuid::new_v4();
+        let (room_id, ..) = state.create_room(host_id, room_request());
+        let ours = state.room_uid(&room_id).unwrap();
+
+        // Another instance handed out the same code before it heard of ours
+        let peer = Uuid::new_v4();
+        let mut theirs = state.room_for_mirror(&room_id).unwrap();
+        theirs.uid = Uuid::new_v4();
+        theirs.host_id = Uuid::new_v4();
+        theirs.capacity = 2;
+        assert!(!state.mirror_room(&room_id, theirs.clone()));
+        let event = ClusterEvent::Room {
+            room_id: room_id.clone(),
+            room: theirs.clone(),
+        };
+        apply(&app, peer, event).await;
+        let room = state.room_for_mirror(&room_id).unwrap();
+        assert_eq!((room.host_id, room.uid), (host_id, ours));
+        assert_ne!(room.capacity, 2);
+
+        // Its traffic doesn't leak into our room either
+        apply(&app, peer, pause_at(&room_id, theirs.uid, 99.0)).await;
+        let roster = vec![MemberSummary {
+            client_id: theirs.host_id,
+            display_name: "Stranger".to_string(),
+            is_host: true,
+            is_spectator: false,
+        }];
+        let event = ClusterEvent::Roster {
+            room_id: room_id.clone(),
+            room_uid: theirs.uid,
+            members: roster,
+        };
+        apply(&app, peer, event).await;
+        let event = ClusterEvent::Closed {
+            room_id: room_id.clone(),
+            room_uid: theirs.uid,
+            reason: None,
+        };
+        apply(&app, peer, event).await;
+        assert_eq!(state.room_playback(&room_id).unwrap().position, 0.0);
+        assert_eq!(state.room_snapshot(&room_id).await.unwrap().0.len(), 1);
+
+        // Events about our room still apply
+        apply(&app, peer, pause_at(&room_id, ours, 12.0)).await;
+        assert_eq!(state.room_playback(&room_id).unwrap().position, 12.0);
+    }
+}
@@ -0,0 +1,216 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::logging::LogFormat;
+use crate::origin::AllowedOrigins;
+use crate::protocol::RoomCodeFormat;
+use crate::room_code::RoomCodeStyle;
+use crate::tls::TlsFiles;
+
+const DEFAULT_PORT: u16 = 3005;
+const DEFAULT_ROOM_CODE_STYLE: RoomCodeStyle = RoomCodeStyle::Numeric;
+const DEFAULT_RECONNECT_AFTER_MS: u64 = 3_000;
+const DEFAULT_SCHEDULE_GRACE_MINS: u64 = 30;
+const DEFAULT_AUDIT_LOG_MAX_MB: u64 = 10;
+const DEFAULT_AUDIT_LOG_FILES: usize = 5;
+const DEFAULT_TLS_RELOAD_SECS: u64 = 30;
+const DEFAULT_REDIS_CHANNEL: &str = "hang:cluster";
+const DEFAULT_MAX_SUBTITLE_KB: usize = 512;
+
+/// Runtime configuration sourced from environment variables
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// TCP port to listen on (`PORT`)
+    pub port: u16,
+    /// `compact` for humans or `json` for log pipelines (`HANG_LOG_FORMAT`)
+    pub log_format: LogFormat,
+    /// Delay advertised to clients before they reconnect after a planned shutdown
+    /// (`HANG_RECONNECT_AFTER_MS`)
+    pub reconnect_after_ms: u64,
+    /// Bearer token required by the `/admin` API; the API is disabled when unset
+    /// (`HANG_ADMIN_TOKEN`)
+    pub admin_token: Option<String>,
+    /// Shape of generated room codes and whether vanity codes are accepted
+    /// (`HANG_ROOM_CODE_STYLE`, `HANG_ROOM_CODE_LENGTH`, `HANG_VANITY_CODES`)
+    pub room_code_format: RoomCodeFormat,
+    /// How long an empty scheduled room stays reserved after its start time
+    /// (`HANG_SCHEDULE_GRACE_MINS`)
+    pub schedule_grace_ms: u64,
+    /// Endpoints that receive room lifecycle events (`HANG_WEBHOOK_URLS`, comma separated)
+    pub webhook_urls: Vec<String>,
+    /// Key used to sign webhook payloads (`HANG_WEBHOOK_SECRET`)
+    pub webhook_secret: Option<String>,
+    /// JSON-lines audit log of room activity; disabled when unset (`HANG_AUDIT_LOG`)
+    pub audit_log_path: Option<PathBuf>,
+    /// Size at which the audit log is rotated (`HANG_AUDIT_LOG_MAX_MB`)
+    pub audit_log_max_bytes: u64,
+    /// Rotated audit files kept next to the active one (`HANG_AUDIT_LOG_FILES`)
+    pub audit_log_files: usize,
+    /// Browser origins that may open WebSockets and call the public HTTP endpoints
+    /// (`HANG_ALLOWED_ORIGINS`, comma separated, `*` for any)
+    pub allowed_origins: AllowedOrigins,
+    /// Take the client address from `X-Forwarded-For` when running behind a proxy
+    /// (`HANG_TRUST_PROXY`)
+    pub trust_proxy: bool,
+    /// PEM certificate chain served over HTTPS/WSS (`HANG_TLS_CERT`)
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key for `tls_cert_path` (`HANG_TLS_KEY`)
+    pub tls_key_path: Option<PathBuf>,
+    /// How often the certificate files are checked for changes (`HANG_TLS_RELOAD_SECS`)
+    pub tls_reload_interval: Duration,
+    /// Redis server shared by all instances; rooms stay process-local when unset
+    /// (`HANG_REDIS_URL`)
+    pub redis_url: Option<String>,
+    /// Pub/sub channel the instances talk on (`HANG_REDIS_CHANNEL`)
+    pub redis_channel: String,
+    /// Largest subtitle file members may share with their room; 0 disables sharing
+    /// (`HANG_MAX_SUBTITLE_KB`)
+    pub max_subtitle_bytes: usize,
+}
+
+/// Defaults used for every variable that isn't set. On their own they keep everything inside
+/// the process (no webhooks, audit log, Redis, or TLS files) and only allow same-origin pages,
+/// which is also what in-process test and fuzz harnesses want.
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            log_format: LogFormat::Compact,
+            reconnect_after_ms: DEFAULT_RECONNECT_AFTER_MS,
+            admin_token: None,
+            room_code_format: RoomCodeFormat::new(DEFAULT_ROOM_CODE_STYLE, None, true),
+            schedule_grace_ms: DEFAULT_SCHEDULE_GRACE_MINS * 60_000,
+            webhook_urls: Vec::new(),
+            webhook_secret: None,
+            audit_log_path: None,
+            audit_log_max_bytes: DEFAULT_AUDIT_LOG_MAX_MB * 1024 * 1024,
+            audit_log_files: DEFAULT_AUDIT_LOG_FILES,
+            allowed_origins: AllowedOrigins::List(Vec::new()),
+            trust_proxy: false,
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_reload_interval: Duration::from_secs(DEFAULT_TLS_RELOAD_SECS),
+            redis_url: None,
+            redis_channel: DEFAULT_REDIS_CHANNEL.to_string(),
+            max_subtitle_bytes: DEFAULT_MAX_SUBTITLE_KB * 1024,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Starts from the defaults and overrides whatever the environment sets
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(port) = parse_var("PORT") {
+            config.port = port;
+        }
+        if let Some(format) = env::var("HANG_LOG_FORMAT")
+            .ok()
+            .and_then(|value| LogFormat::parse(&value))
+        {
+            config.log_format = format;
+        }
+        if let Some(delay) = parse_var("HANG_RECONNECT_AFTER_MS") {
+            config.reconnect_after_ms = delay;
+        }
+        config.admin_token = env::var("HANG_ADMIN_TOKEN")
+            .ok()
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty());
+        let style = env::var("HANG_ROOM_CODE_STYLE")
+            .ok()
+            .and_then(|value| RoomCodeStyle::parse(&value));
+        let length = parse_var("HANG_ROOM_CODE_LENGTH");
+        let vanity = parse_flag("HANG_VANITY_CODES");
+        if style.is_some() || length.is_some() || vanity.is_some() {
+            config.room_code_format = RoomCodeFormat::new(
+                style.unwrap_or(DEFAULT_ROOM_CODE_STYLE),
+                length,
+                vanity.unwrap_or(config.room_code_format.allow_vanity),
+            );
+        }
+        if let Some(mins) = parse_var::<u64>("HANG_SCHEDULE_GRACE_MINS") {
+            config.schedule_grace_ms = mins.saturating_mul(60_000);
+        }
+        if let Ok(value) = env::var("HANG_WEBHOOK_URLS") {
+            config.webhook_urls = value
+                .split(|ch: char| ch == ',' || ch.is_whitespace())
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        config.webhook_secret = env::var("HANG_WEBHOOK_SECRET")
+            .ok()
+            .filter(|secret| !secret.trim().is_empty());
+        config.audit_log_path = parse_path("HANG_AUDIT_LOG");
+        if let Some(mb) = parse_var::<u64>("HANG_AUDIT_LOG_MAX_MB") {
+            config.audit_log_max_bytes = mb.max(1).saturating_mul(1024 * 1024);
+        }
+        if let Some(files) = parse_var("HANG_AUDIT_LOG_FILES") {
+            config.audit_log_files = files;
+        }
+        if let Ok(value) = env::var("HANG_ALLOWED_ORIGINS") {
+            config.allowed_origins = AllowedOrigins::parse(&value);
+        }
+        if let Some(trust) = parse_flag("HANG_TRUST_PROXY") {
+            config.trust_proxy = trust;
+        }
+        config.tls_cert_path = parse_path("HANG_TLS_CERT");
+        config.tls_key_path = parse_path("HANG_TLS_KEY");
+        if let Some(secs) = parse_var::<u64>("HANG_TLS_RELOAD_SECS") {
+            config.tls_reload_interval = Duration::from_secs(secs.max(1));
+        }
+        config.redis_url = env::var("HANG_REDIS_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(channel) = env::var("HANG_REDIS_CHANNEL")
+            .ok()
+            .map(|channel| channel.trim().to_string())
+            .filter(|channel| !channel.is_empty())
+        {
+            config.redis_channel = channel;
+        }
+        if let Some(kb) = parse_var::<usize>("HANG_MAX_SUBTITLE_KB") {
+            config.max_subtitle_bytes = kb.saturating_mul(1024);
+        }
+        config
+    }
+
+    /// TLS settings when both a certificate and a key are configured
+    pub fn tls_files(&self) -> anyhow::Result<Option<TlsFiles>> {
+        match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(TlsFiles {
+                cert_path: cert_path.clone(),
+                key_path: key_path.clone(),
+                reload_interval: self.tls_reload_interval,
+            })),
+            (None, None) => Ok(None),
+            _ => anyhow::bail!("HANG_TLS_CERT and HANG_TLS_KEY must be set together"),
+        }
+    }
+}
+
+fn parse_var<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn parse_path(name: &str) -> Option<PathBuf> {
+    env::var(name)
+        .ok()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+fn parse_flag(name: &str) -> Option<bool> {
+    let value = env::var(name).ok()?;
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,1040 @@
+//! End-to-end tests: the real router on an ephemeral port, driven by the desktop client's
+//! `SyncClient` over WebSockets.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hang_client::protocol::{
+    Bookmark, MemberSummary, Message as ClientMessage, RoomSchedule, SyncCommand as ClientCommand,
+    TrackLabel, TrackSelection,
+};
+use hang_client::sync::{NewRoomOptions, SyncClient};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::protocol::RoomCodeFormat;
+use crate::pubsub::{MemoryPubSub, PubSub};
+use crate::room_code::RoomCodeStyle;
+use crate::schedule;
+use crate::state::now_millis;
+use crate::{build_app_state, build_router, max_ws_message_bytes, AppState, ServerConfig};
+
+/// Upper bound on any single wait, so a missing message fails the test instead of hanging it
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+const FILE_HASH: &str = "movie-hash";
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+struct TestServer {
+    ws_url: String,
+    http_url: String,
+    app: AppState,
+}
+
+impl TestServer {
+    async fn start() -> Self {
+        Self::start_with(ServerConfig::default()).await
+    }
+
+    async fn start_with(config: ServerConfig) -> Self {
+        Self::start_on(config, Arc::new(MemoryPubSub::new())).await
+    }
+
+    /// Starts an instance that shares `pubsub` with any other instance started on it
+    async fn start_on(config: ServerConfig, pubsub: Arc<dyn PubSub>) -> Self {
+        let app = build_app_state(config, pubsub);
+        let router = build_router(app.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+        Self {
+            ws_url: format!("ws://{addr}/ws"),
+            http_url: format!("http://{addr}"),
+            app,
+        }
+    }
+
+    /// Status of a GET to the admin API, optionally with a bearer token
+    async fn admin_status(&self, path: &str, token: Option<&str>) -> reqwest::StatusCode {
+        let mut request = reqwest::Client::new().get(format!("{}/admin{path}", self.http_url));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await.unwrap().status()
+    }
+
+    /// Opens a room's event feed and returns the response status
+    async fn feed_status(&self, room_id: &str, token: Option<&str>) -> reqwest::StatusCode {
+        let mut url = format!("{}/rooms/{room_id}/events", self.http_url);
+        if let Some(token) = token {
+            url.push_str(&format!("?token={token}"));
+        }
+        reqwest::get(url).await.unwrap().status()
+    }
+
+    /// Sends an authenticated admin request and returns the status with the JSON body
+    async fn admin(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> (reqwest::StatusCode, serde_json::Value) {
+        let mut request = reqwest::Client::new()
+            .request(method, format!("{}/admin{path}", self.http_url))
+            .bearer_auth(ADMIN_TOKEN);
+        if let Some(body) = body {
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_string());
+        }
+        let response = request.send().await.unwrap();
+        let status = response.status();
+        let text = response.text().await.unwrap();
+        (status, serde_json::from_str(&text).unwrap_or_default())
+    }
+
+    async fn client(&self) -> TestClient {
+        let (tx, inbox) = mpsc::unbounded_channel();
+        let sync = SyncClient::new();
+        sync.connect(&self.ws_url, move |message| {
+            let _ = tx.send(message);
+        })
+        .await
+        .unwrap();
+        let mut client = TestClient {
+            sync,
+            inbox,
+            client_id: None,
+            resume_token: None,
+            feed_token: None,
+        };
+        client
+            .wait_for(|message| matches!(message, ClientMessage::ServerHello { .. }).then_some(()))
+            .await;
+        client
+    }
+}
+
+struct TestClient {
+    sync: SyncClient,
+    inbox: mpsc::UnboundedReceiver<ClientMessage>,
+    client_id: Option<Uuid>,
+    resume_token: Option<String>,
+    feed_token: Option<String>,
+}
+
+impl TestClient {
+    async fn next(&mut self) -> ClientMessage {
+        tokio::time::timeout(RECV_TIMEOUT, self.inbox.recv())
+            .await
+            .expect("timed out waiting for the server")
+            .expect("connection closed")
+    }
+
+    /// Skips messages until `pick` accepts one
+    async fn wait_for<T>(&mut self, mut pick: impl FnMut(ClientMessage) -> Option<T>) -> T {
+        loop {
+            if let Some(value) = pick(self.next().await) {
+                return value;
+            }
+        }
+    }
+
+    async fn create(&mut self, options: NewRoomOptions) -> String {
+        self.sync
+            .create_room(FILE_HASH.to_string(), options)
+            .unwrap();
+        let (room_id, client_id, resume_token, feed_token) = self
+            .wait_for(|message| match message {
+                ClientMessage::RoomCreated {
+                    room_id,
+                    client_id,
+                    resume_token,
+                    feed_token,
+                    ..
+                } => Some((room_id, client_id, resume_token, feed_token)),
+                _ => None,
+            })
+            .await;
+        self.client_id = Some(client_id);
+        self.resume_token = Some(resume_token);
+        self.feed_token = feed_token;
+        room_id
+    }
+
+    /// Sends `JoinRoom` and returns the server's verdict
+    async fn join(
+        &mut self,
+        room_id: &str,
+        file_hash: &str,
+        passcode: Option<&str>,
+    ) -> ClientMessage {
+        self.sync
+            .join_room(
+                room_id.to_string(),
+                file_hash.to_string(),
+                passcode.map(str::to_string),
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        self.wait_for_verdict().await
+    }
+
+    /// Sends `JoinRoom` asking for a spectator seat
+    async fn spectate(&mut self, room_id: &str) -> ClientMessage {
+        self.sync
+            .join_room(
+                room_id.to_string(),
+                FILE_HASH.to_string(),
+                None,
+                None,
+                None,
+                true,
+            )
+            .unwrap();
+        self.wait_for_verdict().await
+    }
+
+    async fn resume(&mut self, token: &str) -> ClientMessage {
+        self.sync.resume_session(token.to_string(), None).unwrap();
+        self.wait_for_verdict().await
+    }
+
+    async fn wait_for_verdict(&mut self) -> ClientMessage {
+        let verdict = self
+            .wait_for(|message| match message {
+                ClientMessage::RoomJoined { .. }
+                | ClientMessage::RoomNotFound
+                | ClientMessage::RoomFull { .. }
+                | ClientMessage::FileHashMismatch { .. }
+                | ClientMessage::Error { .. } => Some(message),
+                _ => None,
+            })
+            .await;
+        if let ClientMessage::RoomJoined {
+            client_id,
+            resume_token,
+            feed_token,
+            ..
+        } = &verdict
+        {
+            self.client_id = Some(*client_id);
+            self.resume_token = Some(resume_token.clone());
+            self.feed_token = feed_token.clone();
+        }
+        verdict
+    }
+
+    /// Waits for a roster of `len` members, skipping stale updates
+    async fn roster_of(&mut self, len: usize) -> Vec<MemberSummary> {
+        self.wait_for(|message| match message {
+            ClientMessage::RoomMemberUpdate { members, .. } if members.len() == len => {
+                Some(members)
+            }
+            _ => None,
+        })
+        .await
+    }
+
+    async fn leave(&mut self) {
+        self.sync.leave_room().unwrap();
+        self.wait_for(|message| matches!(message, ClientMessage::RoomLeft).then_some(()))
+            .await;
+    }
+
+    fn id(&self) -> Uuid {
+        self.client_id.expect("client has not joined a room")
+    }
+}
+
+fn assert_error(message: ClientMessage, expected: &str) {
+    match message {
+        ClientMessage::Error { message } => assert_eq!(message, expected),
+        other => panic!("expected error {expected:?}, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn create_join_and_leave() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+
+    let room_id = host.create(NewRoomOptions::default()).await;
+    assert!(matches!(
+        guest.join("999-999", FILE_HASH, None).await,
+        ClientMessage::RoomNotFound
+    ));
+    match guest.join(&room_id, "other-file", None).await {
+        ClientMessage::FileHashMismatch { expected } => assert_eq!(expected, FILE_HASH),
+        other => panic!("expected a hash mismatch, got {other:?}"),
+    }
+    match guest.join(&room_id, FILE_HASH, None).await {
+        ClientMessage::RoomJoined {
+            room_id: joined,
+            is_host,
+            ..
+        } => {
+            assert_eq!(joined, room_id);
+            assert!(!is_host);
+        }
+        other => panic!("expected to join, got {other:?}"),
+    }
+
+    let members = host.roster_of(2).await;
+    let host_entry = members
+        .iter()
+        .find(|member| member.client_id == host.id())
+        .unwrap();
+    assert!(host_entry.is_host);
+    assert!(members
+        .iter()
+        .any(|member| member.client_id == guest.id() && !member.is_host));
+
+    guest.leave().await;
+    let members = host.roster_of(1).await;
+    assert_eq!(members[0].client_id, host.id());
+
+    host.leave().await;
+    assert!(!server.app.server_state.rooms.contains_key(&room_id));
+}
+
+#[tokio::test]
+async fn passcode_is_required_to_join() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+
+    let room_id = host
+        .create(NewRoomOptions {
+            passcode: Some("hunter2".to_string()),
+            ..Default::default()
+        })
+        .await;
+
+    assert_error(
+        guest.join(&room_id, FILE_HASH, None).await,
+        "Passcode required",
+    );
+    assert_error(
+        guest.join(&room_id, FILE_HASH, Some("hunter3")).await,
+        "Invalid passcode",
+    );
+    match guest.join(&room_id, FILE_HASH, Some("hunter2")).await {
+        ClientMessage::RoomJoined {
+            passcode_enabled, ..
+        } => assert!(passcode_enabled),
+        other => panic!("expected to join, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn full_rooms_turn_guests_away_until_a_seat_frees_up() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut first = server.client().await;
+    let mut second = server.client().await;
+
+    let room_id = host
+        .create(NewRoomOptions {
+            capacity: Some(2),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(matches!(
+        first.join(&room_id, FILE_HASH, None).await,
+        ClientMessage::RoomJoined { .. }
+    ));
+    match second.join(&room_id, FILE_HASH, None).await {
+        ClientMessage::RoomFull { capacity } => assert_eq!(capacity, 2),
+        other => panic!("expected the room to be full, got {other:?}"),
+    }
+
+    first.leave().await;
+    host.roster_of(1).await;
+    assert!(matches!(
+        second.join(&room_id, FILE_HASH, None).await,
+        ClientMessage::RoomJoined { .. }
+    ));
+}
+
+#[tokio::test]
+async fn spectators_have_their_own_seats_and_cannot_control_playback() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let mut spectator = server.client().await;
+    let mut late_spectator = server.client().await;
+    let mut late_guest = server.client().await;
+
+    let room_id = host
+        .create(NewRoomOptions {
+            capacity: Some(2),
+            spectator_capacity: Some(1),
+            ..Default::default()
+        })
+        .await;
+    guest.join(&room_id, FILE_HASH, None).await;
+
+    // Seats are all taken, but the spectator gallery is separate
+    match spectator.spectate(&room_id).await {
+        ClientMessage::RoomJoined { spectator, .. } => assert!(spectator),
+        other => panic!("expected to spectate, got {other:?}"),
+    }
+    // A full gallery is reported like a full room, with the spectator seat count
+    assert!(matches!(
+        late_spectator.spectate(&room_id).await,
+        ClientMessage::RoomFull { capacity: 1 }
+    ));
+    assert!(matches!(
+        late_guest.join(&room_id, FILE_HASH, None).await,
+        ClientMessage::RoomFull { capacity: 2 }
+    ));
+
+    spectator
+        .sync
+        .send_sync_command(ClientCommand::Pause { timestamp: 5.0 })
+        .unwrap();
+    let rejection = spectator
+        .wait_for(|message| matches!(message, ClientMessage::Error { .. }).then_some(message))
+        .await;
+    assert_error(rejection, "Spectators can't control playback");
+
+    // The guest only ever sees the host's command, never the spectator's
+    host.sync
+        .send_sync_command(ClientCommand::Pause { timestamp: 7.0 })
+        .unwrap();
+    let (from_client, command) = guest
+        .wait_for(|message| match message {
+            ClientMessage::SyncBroadcast {
+                from_client,
+                command,
+            } => Some((from_client, command)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(from_client, host.id());
+    assert!(matches!(command, ClientCommand::Pause { timestamp } if timestamp == 7.0));
+}
+
+#[tokio::test]
+async fn members_on_different_instances_share_a_room() {
+    let pubsub: Arc<dyn PubSub> = Arc::new(MemoryPubSub::new());
+    let admin_config = ServerConfig {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..ServerConfig::default()
+    };
+    let first = TestServer::start_on(admin_config, pubsub.clone()).await;
+    let second = TestServer::start_on(ServerConfig::default(), pubsub).await;
+    let mut host = first.client().await;
+    let mut guest = second.client().await;
+
+    let room_id = host.create(NewRoomOptions::default()).await;
+    let deadline = tokio::time::Instant::now() + RECV_TIMEOUT;
+    while !second.app.server_state.rooms.contains_key(&room_id) {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "room was never announced"
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(matches!(
+        guest.join(&room_id, FILE_HASH, None).await,
+        ClientMessage::RoomJoined { .. }
+    ));
+
+    // Both rosters list both members, whichever instance they are connected to
+    for peer in [&mut host, &mut guest] {
+        let members = peer.roster_of(2).await;
+        assert!(members.iter().any(|member| member.is_host));
+    }
+
+    // The admin list and detail views count members on every instance
+    let (_, rooms) = first.admin(reqwest::Method::GET, "/rooms", None).await;
+    assert_eq!(rooms[0]["member_count"], 2);
+    let (_, details) = first
+        .admin(reqwest::Method::GET, &format!("/rooms/{room_id}"), None)
+        .await;
+    assert_eq!(details["member_count"], 2);
+    assert_eq!(details["members"].as_array().map(Vec::len), Some(2));
+
+    host.sync
+        .send_sync_command(ClientCommand::Pause { timestamp: 42.0 })
+        .unwrap();
+    let from = guest
+        .wait_for(|message| match message {
+            ClientMessage::SyncBroadcast {
+                from_client,
+                command: ClientCommand::Pause { .. },
+            } => Some(from_client),
+            _ => None,
+        })
+        .await;
+    assert_eq!(from, host.id());
+    guest
+        .sync
+        .send_sync_command(ClientCommand::Play { timestamp: 42.0 })
+        .unwrap();
+    let from = host
+        .wait_for(|message| match message {
+            ClientMessage::SyncBroadcast {
+                from_client,
+                command: ClientCommand::Play { .. },
+            } => Some(from_client),
+            _ => None,
+        })
+        .await;
+    assert_eq!(from, guest.id());
+
+    guest.leave().await;
+    let members = host.roster_of(1).await;
+    assert_eq!(members[0].client_id, host.id());
+}
+
+#[tokio::test]
+async fn resume_session_rejoins_after_a_dropped_connection() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+    let token = guest.resume_token.clone().unwrap();
+
+    guest.sync.disconnect();
+    host.roster_of(1).await;
+
+    let mut returning = server.client().await;
+    match returning.resume(&token).await {
+        ClientMessage::RoomJoined {
+            room_id: joined,
+            is_host,
+            ..
+        } => {
+            assert_eq!(joined, room_id);
+            assert!(!is_host);
+        }
+        other => panic!("expected to resume, got {other:?}"),
+    }
+    let members = host.roster_of(2).await;
+    assert!(members
+        .iter()
+        .any(|member| member.client_id == returning.id()));
+
+    // Tokens are single use
+    let mut replay = server.client().await;
+    assert_error(
+        replay.resume(&token).await,
+        "Session token invalid or expired",
+    );
+}
+
+#[tokio::test]
+async fn host_reclaims_the_room_after_reconnecting() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    guest.roster_of(2).await;
+    let token = host.resume_token.clone().unwrap();
+
+    host.sync.disconnect();
+    let members = guest.roster_of(1).await;
+    assert!(!members[0].is_host);
+
+    let mut returning = server.client().await;
+    match returning.resume(&token).await {
+        ClientMessage::RoomJoined { is_host, .. } => assert!(is_host),
+        other => panic!("expected to resume, got {other:?}"),
+    }
+    let members = guest.roster_of(2).await;
+    let new_host = members.iter().find(|member| member.is_host).unwrap();
+    assert_eq!(new_host.client_id, returning.id());
+
+    // Host-only actions follow the seat to the new connection
+    returning.sync.create_invite(None, None).unwrap();
+    returning
+        .wait_for(|message| matches!(message, ClientMessage::InviteCreated { .. }).then_some(()))
+        .await;
+    guest.sync.create_invite(None, None).unwrap();
+    guest
+        .wait_for(|message| matches!(message, ClientMessage::Error { .. }).then_some(()))
+        .await;
+}
+
+#[tokio::test]
+async fn broadcasts_keep_each_senders_order() {
+    const COMMANDS: usize = 25;
+
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+
+    let host_seeks: Vec<f64> = (0..COMMANDS).map(|i| i as f64).collect();
+    let guest_seeks: Vec<f64> = (0..COMMANDS).map(|i| 1000.0 + i as f64).collect();
+    for (host_seek, guest_seek) in host_seeks.iter().zip(&guest_seeks) {
+        host.sync
+            .send_sync_command(ClientCommand::Seek {
+                timestamp: *host_seek,
+            })
+            .unwrap();
+        guest
+            .sync
+            .send_sync_command(ClientCommand::Seek {
+                timestamp: *guest_seek,
+            })
+            .unwrap();
+    }
+
+    let (host_id, guest_id) = (host.id(), guest.id());
+    for peer in [&mut host, &mut guest] {
+        let mut received: Vec<(Uuid, f64)> = Vec::new();
+        while received.len() < COMMANDS * 2 {
+            let broadcast = peer
+                .wait_for(|message| match message {
+                    ClientMessage::SyncBroadcast {
+                        from_client,
+                        command: ClientCommand::Seek { timestamp },
+                    } => Some((from_client, timestamp)),
+                    _ => None,
+                })
+                .await;
+            received.push(broadcast);
+        }
+        let from = |sender: Uuid| -> Vec<f64> {
+            received
+                .iter()
+                .filter(|(from_client, _)| *from_client == sender)
+                .map(|(_, timestamp)| *timestamp)
+                .collect()
+        };
+        assert_eq!(from(host_id), host_seeks);
+        assert_eq!(from(guest_id), guest_seeks);
+    }
+}
+
+#[tokio::test]
+async fn shared_subtitles_reach_other_members_only() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+
+    let srt = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+    guest
+        .sync
+        .share_subtitle("subs/Movie.en.srt".to_string(), srt.to_string())
+        .unwrap();
+    let (from_client, file_name, content) = host
+        .wait_for(|message| match message {
+            ClientMessage::SubtitleShared {
+                from_client,
+                file_name,
+                content,
+                ..
+            } => Some((from_client, file_name, content)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(from_client, guest.id());
+    assert_eq!(file_name, "Movie.en.srt");
+    assert_eq!(content, srt);
+
+    let too_large = "x".repeat(server.app.config.max_subtitle_bytes + 1);
+    host.sync
+        .share_subtitle("Movie.srt".to_string(), too_large)
+        .unwrap();
+    let rejection = host
+        .wait_for(|message| matches!(message, ClientMessage::Error { .. }).then_some(message))
+        .await;
+    assert!(matches!(rejection, ClientMessage::Error { message } if message.contains("too large")));
+
+    // The sharer never gets its own file back
+    host.sync
+        .send_sync_command(ClientCommand::Pause { timestamp: 1.0 })
+        .unwrap();
+    let next = guest
+        .wait_for(|message| match message {
+            ClientMessage::SubtitleShared { .. } | ClientMessage::SyncBroadcast { .. } => {
+                Some(message)
+            }
+            _ => None,
+        })
+        .await;
+    assert!(matches!(next, ClientMessage::SyncBroadcast { .. }));
+}
+
+#[tokio::test]
+async fn oversized_messages_drop_the_connection() {
+    let server = TestServer::start_with(ServerConfig {
+        max_subtitle_bytes: 1024,
+        ..ServerConfig::default()
+    })
+    .await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+
+    // A subtitle at the limit still fits, even though escaping doubles it on the wire
+    let escaped = "\"\n".repeat(512);
+    guest
+        .sync
+        .share_subtitle("Movie.srt".to_string(), escaped.clone())
+        .unwrap();
+    let content = host
+        .wait_for(|message| match message {
+            ClientMessage::SubtitleShared { content, .. } => Some(content),
+            _ => None,
+        })
+        .await;
+    assert_eq!(content, escaped);
+
+    let oversized = "x".repeat(max_ws_message_bytes(&server.app.config) + 1);
+    guest
+        .sync
+        .share_subtitle("Movie.srt".to_string(), oversized)
+        .unwrap();
+    let members = host.roster_of(1).await;
+    assert_eq!(members[0].client_id, host.id());
+}
+
+#[tokio::test]
+async fn shared_bookmarks_reach_current_and_later_members() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+
+    let bookmark = Bookmark {
+        id: "fight".to_string(),
+        position: 4210.5,
+        label: "  Best fight scene\n".to_string(),
+        author: "Someone else".to_string(),
+    };
+    guest.sync.share_bookmark(bookmark.clone()).unwrap();
+    let (from_client, relayed) = host
+        .wait_for(|message| match message {
+            ClientMessage::BookmarkShared {
+                from_client,
+                bookmark,
+            } => Some((from_client, bookmark)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(from_client, guest.id());
+    assert_eq!(relayed.label, "Best fight scene");
+    assert_eq!(relayed.position, 4210.5);
+    assert_ne!(relayed.author, "Someone else");
+
+    host.sync
+        .share_bookmark(Bookmark {
+            label: " ".to_string(),
+            ..bookmark.clone()
+        })
+        .unwrap();
+    let rejection = host
+        .wait_for(|message| matches!(message, ClientMessage::Error { .. }).then_some(message))
+        .await;
+    assert_error(rejection, "Bookmarks need a label");
+
+    // Sharing the same id again renames the bookmark instead of adding another
+    guest
+        .sync
+        .share_bookmark(Bookmark {
+            label: "Final fight".to_string(),
+            ..bookmark
+        })
+        .unwrap();
+    host.wait_for(|message| match message {
+        ClientMessage::BookmarkShared { bookmark, .. } => Some(bookmark),
+        _ => None,
+    })
+    .await;
+
+    let mut late = server.client().await;
+    match late.join(&room_id, FILE_HASH, None).await {
+        ClientMessage::RoomJoined { bookmarks, .. } => {
+            assert_eq!(bookmarks.len(), 1);
+            assert_eq!(bookmarks[0].label, "Final fight");
+            assert_eq!(bookmarks[0].author, relayed.author);
+        }
+        other => panic!("expected to join, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn host_track_choice_reaches_current_and_later_members() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let room_id = host
+        .create(NewRoomOptions {
+            sync_tracks: true,
+            ..Default::default()
+        })
+        .await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+
+    let selection = TrackSelection {
+        audio: Some(TrackLabel {
+            title: "Track 2 - [Japanese]".to_string(),
+            lang: String::new(),
+        }),
+        subtitle: None,
+    };
+    host.sync.select_tracks(selection.clone()).unwrap();
+    let relayed = guest
+        .wait_for(|message| match message {
+            ClientMessage::TracksSelected { selection } => Some(selection),
+            _ => None,
+        })
+        .await;
+    assert_eq!(relayed, selection);
+
+    guest.sync.select_tracks(selection.clone()).unwrap();
+    let rejection = guest
+        .wait_for(|message| matches!(message, ClientMessage::Error { .. }).then_some(message))
+        .await;
+    assert_error(rejection, "Only the host can choose tracks for the room");
+
+    let mut late = server.client().await;
+    match late.join(&room_id, FILE_HASH, None).await {
+        ClientMessage::RoomJoined {
+            sync_tracks,
+            track_selection,
+            ..
+        } => {
+            assert!(sync_tracks);
+            assert_eq!(track_selection, Some(selection.clone()));
+        }
+        other => panic!("expected to join, got {other:?}"),
+    }
+
+    let mut other_host = server.client().await;
+    other_host.create(NewRoomOptions::default()).await;
+    other_host.sync.select_tracks(selection).unwrap();
+    let rejection = other_host
+        .wait_for(|message| matches!(message, ClientMessage::Error { .. }).then_some(message))
+        .await;
+    assert_error(rejection, "Track sync is off for this room");
+}
+
+#[tokio::test]
+async fn admin_api_requires_the_configured_token() {
+    let disabled = TestServer::start_with(ServerConfig {
+        admin_token: None,
+        ..ServerConfig::default()
+    })
+    .await;
+    assert_eq!(
+        disabled.admin_status("/rooms", Some(ADMIN_TOKEN)).await,
+        reqwest::StatusCode::FORBIDDEN
+    );
+
+    let server = TestServer::start_with(ServerConfig {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..ServerConfig::default()
+    })
+    .await;
+    assert_eq!(
+        server.admin_status("/rooms", None).await,
+        reqwest::StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        server.admin_status("/rooms", Some("wrong-token")).await,
+        reqwest::StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        server
+            .admin_status("/rooms", Some(&format!("{ADMIN_TOKEN}x")))
+            .await,
+        reqwest::StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        server.admin_status("/rooms", Some(ADMIN_TOKEN)).await,
+        reqwest::StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn admin_endpoints_accept_any_spelling_of_a_room_code() {
+    let server = TestServer::start_with(ServerConfig {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        room_code_format: RoomCodeFormat::new(RoomCodeStyle::Alphanumeric, None, true),
+        ..ServerConfig::default()
+    })
+    .await;
+    let mut host = server.client().await;
+    let room_id = host.create(NewRoomOptions::default()).await;
+    let spelled = room_id.replace('-', "").to_uppercase();
+
+    let (status, details) = server
+        .admin(reqwest::Method::GET, &format!("/rooms/{spelled}"), None)
+        .await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(details["room_id"], room_id.as_str());
+
+    let (status, delivery) = server
+        .admin(
+            reqwest::Method::POST,
+            "/notice",
+            Some(serde_json::json!({ "message": "Hello", "room_id": spelled })),
+        )
+        .await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(delivery["delivered"], 1);
+
+    let (status, closed) = server
+        .admin(reqwest::Method::DELETE, &format!("/rooms/{spelled}"), None)
+        .await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(closed["room_id"], room_id.as_str());
+    host.wait_for(|message| matches!(message, ClientMessage::RoomClosed { .. }).then_some(()))
+        .await;
+}
+
+#[tokio::test]
+async fn kicked_clients_are_disconnected_and_cannot_rejoin() {
+    let server = TestServer::start_with(ServerConfig {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..ServerConfig::default()
+    })
+    .await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let room_id = host.create(NewRoomOptions::default()).await;
+    guest.join(&room_id, FILE_HASH, None).await;
+    host.roster_of(2).await;
+
+    let (status, kicked) = server
+        .admin(
+            reqwest::Method::POST,
+            &format!("/clients/{}/kick", guest.id()),
+            Some(serde_json::json!({ "reason": "Spoilers" })),
+        )
+        .await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(kicked["room_id"], room_id.as_str());
+    let reason = guest
+        .wait_for(|message| match message {
+            ClientMessage::Kicked { reason } => Some(reason),
+            _ => None,
+        })
+        .await;
+    assert_eq!(reason.as_deref(), Some("Spoilers"));
+    let closed = tokio::time::timeout(RECV_TIMEOUT, guest.inbox.recv())
+        .await
+        .expect("kicked connection was left open");
+    assert!(closed.is_none());
+    host.roster_of(1).await;
+
+    // Reconnecting gets a new client id, but the address is still blocked
+    let mut returning = server.client().await;
+    assert_error(
+        returning.join(&room_id, FILE_HASH, None).await,
+        "You were removed from this room; try again later",
+    );
+}
+
+#[tokio::test]
+async fn scheduled_start_fires_once_and_marks_the_room_started() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let mut guest = server.client().await;
+    let room_id = host
+        .create(NewRoomOptions {
+            schedule: Some(RoomSchedule {
+                starts_at_ms: now_millis() + 300,
+                title: "Movie night".to_string(),
+                media: None,
+                started: false,
+            }),
+            ..Default::default()
+        })
+        .await;
+    guest.join(&room_id, FILE_HASH, None).await;
+
+    let started = |message| matches!(message, ClientMessage::ScheduledStart { .. }).then_some(());
+    host.wait_for(started).await;
+    guest.wait_for(started).await;
+    let schedule = server.app.server_state.room_schedule(&room_id).unwrap();
+    assert!(schedule.started);
+
+    // A second timer for the same start, e.g. from a repeated announcement, stays quiet
+    schedule::spawn_start_timer(server.app.clone(), room_id.clone(), schedule.starts_at_ms);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    host.sync
+        .send_sync_command(ClientCommand::Pause { timestamp: 1.0 })
+        .unwrap();
+    let next = guest
+        .wait_for(|message| match message {
+            ClientMessage::ScheduledStart { .. } | ClientMessage::SyncBroadcast { .. } => {
+                Some(message)
+            }
+            _ => None,
+        })
+        .await;
+    assert!(matches!(next, ClientMessage::SyncBroadcast { .. }));
+}
+
+#[tokio::test]
+async fn feed_accepts_the_passcode_or_feed_token_but_not_resume_tokens() {
+    let server = TestServer::start().await;
+    let mut host = server.client().await;
+    let room_id = host
+        .create(NewRoomOptions {
+            passcode: Some("hunter2".to_string()),
+            ..Default::default()
+        })
+        .await;
+    let feed_token = host
+        .feed_token
+        .clone()
+        .expect("RoomCreated carries a feed token");
+    let resume_token = host.resume_token.clone().unwrap();
+    assert_ne!(feed_token, resume_token);
+
+    let unauthorized = reqwest::StatusCode::UNAUTHORIZED;
+    assert_eq!(server.feed_status(&room_id, None).await, unauthorized);
+    assert_eq!(
+        server.feed_status(&room_id, Some(&resume_token)).await,
+        unauthorized
+    );
+    assert_eq!(
+        server.feed_status(&room_id, Some("hunter2")).await,
+        reqwest::StatusCode::OK
+    );
+    assert_eq!(
+        server.feed_status(&room_id, Some(&feed_token)).await,
+        reqwest::StatusCode::OK
+    );
+
+    // Guests learn the same token when they join
+    let mut guest = server.client().await;
+    guest.join(&room_id, FILE_HASH, Some("hunter2")).await;
+    assert_eq!(guest.feed_token, Some(feed_token));
+}
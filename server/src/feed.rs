@@ -0,0 +1,107 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::protocol::{MemberSummary, Message, PlaybackState};
+use crate::state::now_millis;
+use crate::AppState;
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    /// Room passcode or the feed token members receive, required when the room has a passcode
+    token: Option<String>,
+}
+
+/// First event on every feed, so overlays can render before anything changes
+#[derive(Serialize)]
+struct FeedSnapshot {
+    room_id: String,
+    members: Vec<MemberSummary>,
+    capacity: usize,
+    playback: PlaybackState,
+    /// Playback position extrapolated to the time of the snapshot
+    estimated_position: f64,
+}
+
+/// `GET /rooms/:room_id/events`: read-only SSE stream of roster and playback changes.
+/// Spectators are not room members and do not count towards capacity.
+pub async fn room_events(
+    State(state): State<AppState>,
+    Path(room_id): Path<String>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let server_state = &state.server_state;
+    let room_id = server_state.room_code_format().canonicalize(&room_id);
+
+    // Subscribe before taking the snapshot so no update falls between the two
+    let receiver = server_state
+        .subscribe_feed(&room_id, query.token.as_deref())
+        .map_err(|err| {
+            let status = if err == "Room not found" {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::UNAUTHORIZED
+            };
+            (status, err)
+        })?;
+    let (members, capacity) = server_state
+        .room_snapshot(&room_id)
+        .await
+        .unwrap_or_default();
+    let playback = server_state.room_playback(&room_id).unwrap_or_default();
+    let snapshot = FeedSnapshot {
+        room_id: room_id.clone(),
+        members,
+        capacity,
+        estimated_position: playback.position_at(now_millis()),
+        playback,
+    };
+    tracing::debug!(%room_id, "Feed subscriber connected");
+
+    let first = Event::default().event("snapshot").json_data(&snapshot);
+    let updates = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Some((message_event(&message), receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!(skipped, "Feed subscriber lagged");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    // SSE responses are not upgraded sockets, so graceful shutdown would wait on them forever
+    let mut shutdown = state.shutdown.subscribe();
+    let stream = stream::once(async move { first })
+        .chain(updates)
+        .filter_map(|event| async move {
+            event
+                .map_err(|err| tracing::error!(error = %err, "Failed to encode feed event"))
+                .ok()
+        })
+        .map(Ok)
+        .take_until(async move {
+            let _ = shutdown.wait_for(|stopping| *stopping).await;
+        });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Feed events reuse the WebSocket message JSON, named after its `type`
+fn message_event(message: &Message) -> Result<Event, axum::Error> {
+    let name = match message {
+        Message::RoomMemberUpdate { .. } => "RoomMemberUpdate",
+        Message::SyncBroadcast { .. } => "SyncBroadcast",
+        Message::ScheduledStart { .. } => "ScheduledStart",
+        Message::RoomClosed { .. } => "RoomClosed",
+        _ => "message",
+    };
+    Event::default().event(name).json_data(message)
+}
@@ -0,0 +1,65 @@
+//! Entry points for the cargo-fuzz targets in `fuzz/`. Not part of the server's API.
+
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::config::ServerConfig;
+use crate::protocol::Message;
+use crate::pubsub::MemoryPubSub;
+use crate::{build_app_state, handle_message, AppState};
+
+/// An in-process server with one connected client that already hosts a room, so inputs can
+/// reach the in-room handlers as well as the lobby ones
+pub struct MessageHarness {
+    runtime: Runtime,
+    app: AppState,
+    client_id: Uuid,
+    inbox: mpsc::UnboundedReceiver<Message>,
+}
+
+impl MessageHarness {
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the fuzzing runtime");
+        let client_id = Uuid::new_v4();
+        let (app, inbox) = runtime.block_on(async {
+            let app = build_app_state(ServerConfig::default(), Arc::new(MemoryPubSub::new()));
+            let (tx, inbox) = mpsc::unbounded_channel();
+            app.server_state.add_client(client_id, None);
+            app.client_senders.write().await.insert(client_id, tx);
+            (app, inbox)
+        });
+        let mut harness = Self {
+            runtime,
+            app,
+            client_id,
+            inbox,
+        };
+        harness.handle(r#"{"type":"CreateRoom","payload":{"file_hash":"fuzz"}}"#);
+        harness
+    }
+
+    /// Feeds one WebSocket text frame through the handler, as if the client had sent it
+    pub fn handle(&mut self, text: &str) {
+        let _ = self
+            .runtime
+            .block_on(handle_message(text, self.client_id, &self.app));
+        while self.inbox.try_recv().is_ok() {}
+    }
+}
+
+impl Default for MessageHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Renders the `/join` page for query parameters taken straight from a link
+pub fn join_page(room: Option<String>, invite: Option<String>, file: Option<String>) -> String {
+    crate::render_join_page(room, invite, file, None)
+}
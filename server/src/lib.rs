@@ -0,0 +1,1494 @@
+use axum::{
+    extract::{
+        ws::{Message as AxumWsMessage, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse},
+    routing::get,
+    Json, Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch, RwLock};
+use tracing::Instrument;
+use url::form_urlencoded;
+use uuid::Uuid;
+
+mod admin;
+mod audit;
+mod cluster;
+mod config;
+#[cfg(test)]
+mod e2e;
+mod feed;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+mod logging;
+mod origin;
+mod protocol;
+mod pubsub;
+mod room_code;
+mod schedule;
+mod state;
+mod subtitles;
+mod tls;
+mod webhooks;
+
+use audit::{AuditEvent, AuditLog};
+use cluster::Cluster;
+use config::ServerConfig;
+use protocol::{Message, PublicRoomSummary, RoomSchedule, SyncCommand};
+use state::{JoinError, JoinRequest, RoomRequest, ServerState};
+use webhooks::{CloseReason, WebhookEvent, Webhooks};
+
+type ClientSender = mpsc::UnboundedSender<Message>;
+type ClientSenders = Arc<RwLock<HashMap<Uuid, ClientSender>>>;
+
+/// How long to wait for connected clients to receive the shutdown notice
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Room for the JSON around a shared subtitle, and the whole budget for every other message
+const WS_ENVELOPE_BYTES: usize = 64 * 1024;
+
+#[derive(Clone)]
+struct AppState {
+    server_state: ServerState,
+    client_senders: ClientSenders,
+    webhooks: Webhooks,
+    audit: AuditLog,
+    cluster: Cluster,
+    config: Arc<ServerConfig>,
+    /// Flipped to `true` once a shutdown signal has been received
+    shutdown: Arc<watch::Sender<bool>>,
+}
+
+const INDEX_HTML: &str = include_str!("../static/index.html");
+const THANK_YOU_HTML: &str = include_str!("../static/thank-you.html");
+const WATCH_HTML: &str = include_str!("../static/watch.html");
+
+fn print_banner(port: u16, tls: bool) {
+    let (http, ws) = if tls {
+        ("https", "wss")
+    } else {
+        ("http", "ws")
+    };
+    let version = env!("CARGO_PKG_VERSION");
+    println!();
+    println!("  ╭─────────────────────────────────────────╮");
+    println!("  │                                         │");
+    println!("  │   ▶  H A N G   S E R V E R              │");
+    println!("  │      Watch Together, Stay Together      │");
+    println!("  │                                         │");
+    println!("  ├─────────────────────────────────────────┤");
+    println!("  │                                         │");
+    println!("  │   Version:    {:<25} │", version);
+    println!("  │   Port:       {:<25} │", port);
+    println!("  │   Status:     Ready                     │");
+    println!("  │                                         │");
+    println!("  ├─────────────────────────────────────────┤");
+    println!("  │                                         │");
+    println!("  │   Endpoints:                            │");
+    println!(
+        "  │     • {:<33} │",
+        format!("{}://localhost:{}/", http, port)
+    );
+    println!(
+        "  │     • {:<33} │",
+        format!("{}://localhost:{}/ws", ws, port)
+    );
+    println!("  │     • /healthz (health check)           │");
+    println!("  │     • /rooms (public room directory)    │");
+    println!("  │     • /join/:room_id (invite page)      │");
+    println!("  │     • /watch (browser client)           │");
+    println!("  │     • /admin (requires admin token)     │");
+    println!("  │                                         │");
+    println!("  ╰─────────────────────────────────────────╯");
+    println!();
+}
+
+/// Runs the server configured from the environment until a shutdown signal arrives
+pub async fn run() -> anyhow::Result<()> {
+    let config = ServerConfig::from_env();
+    logging::init(config.log_format);
+    let port = config.port;
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let tls_files = config.tls_files()?;
+
+    // Keep stdout machine-readable when logging JSON
+    if config.log_format == logging::LogFormat::Compact {
+        print_banner(port, tls_files.is_some());
+    }
+
+    let pubsub: Arc<dyn pubsub::PubSub> = match &config.redis_url {
+        Some(url) => Arc::new(pubsub::RedisPubSub::connect(
+            url,
+            config.redis_channel.clone(),
+        )?),
+        None => Arc::new(pubsub::MemoryPubSub::new()),
+    };
+    let app_state = build_app_state(config, pubsub);
+    let app = build_router(app_state.clone());
+
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!(%addr, tls = tls_files.is_some(), "Server listening");
+    match tls_files {
+        Some(files) => tls::serve(listener, app, files, shutdown_signal(app_state.clone())).await?,
+        None => {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown_signal(app_state.clone()))
+            .await?
+        }
+    }
+
+    wait_for_clients_to_drain(&app_state.client_senders).await;
+    tracing::info!("Server stopped");
+    Ok(())
+}
+
+/// Shared state for one server instance; also starts the background tasks that need it
+fn build_app_state(config: ServerConfig, pubsub: Arc<dyn pubsub::PubSub>) -> AppState {
+    let (shutdown_tx, _) = watch::channel(false);
+    let app_state = AppState {
+        server_state: ServerState::new(&config),
+        client_senders: Arc::new(RwLock::new(HashMap::new())),
+        webhooks: Webhooks::spawn(&config),
+        audit: AuditLog::spawn(&config),
+        cluster: Cluster::new(pubsub),
+        config: Arc::new(config),
+        shutdown: Arc::new(shutdown_tx),
+    };
+    schedule::spawn_reaper(app_state.clone());
+    cluster::spawn(app_state.clone());
+    app_state
+}
+
+fn build_router(app_state: AppState) -> Router {
+    // Read-only endpoints that pages on other origins may call
+    let public_api = Router::new()
+        .route("/healthz", get(health_check))
+        .route("/rooms", get(list_public_rooms))
+        .route("/rooms/:room_id/events", get(feed::room_events))
+        .layer(app_state.config.allowed_origins.cors_layer());
+
+    Router::new()
+        .route("/", get(serve_index))
+        .route("/thank-you", get(serve_thank_you))
+        .route("/thank-you.html", get(serve_thank_you))
+        .route("/watch", get(serve_web_client))
+        .merge(public_api)
+        .route("/ws", get(ws_endpoint))
+        .route("/join", get(join_page))
+        .route("/join/:room_id", get(join_page_with_path))
+        .nest("/admin", admin::router())
+        .layer(axum::middleware::from_fn(logging::request_span))
+        .with_state(app_state)
+}
+
+/// Resolves once Ctrl-C or SIGTERM is received, after telling every client to reconnect.
+async fn shutdown_signal(state: AppState) {
+    wait_for_termination().await;
+
+    let reconnect_after_ms = state.config.reconnect_after_ms;
+    let senders = state.client_senders.read().await;
+    tracing::info!(
+        clients = senders.len(),
+        reconnect_after_ms,
+        "Shutdown requested; notifying clients to reconnect"
+    );
+    let notice = Message::ServerShuttingDown { reconnect_after_ms };
+    for tx in senders.values() {
+        let _ = tx.send(notice.clone());
+    }
+    drop(senders);
+
+    // Upgraded sockets are not tracked by axum's graceful shutdown, so each
+    // connection watches this flag and closes itself once the notice is flushed.
+    let _ = state.shutdown.send(true);
+}
+
+async fn wait_for_termination() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+async fn wait_for_clients_to_drain(client_senders: &ClientSenders) {
+    let deadline = tokio::time::Instant::now() + SHUTDOWN_DRAIN_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if client_senders.read().await.is_empty() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let remaining = client_senders.read().await.len();
+    tracing::warn!(remaining, "Clients still connected at shutdown");
+}
+
+async fn ws_endpoint(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> axum::response::Response {
+    let remote_ip = client_ip(&state.config, &headers, peer);
+    if let Err(origin) = state.config.allowed_origins.check_upgrade(&headers) {
+        tracing::warn!(%origin, %remote_ip, "Rejected WebSocket from disallowed origin");
+        return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
+    let client_id = Uuid::new_v4();
+    // Every log line for this socket carries these fields; `room_id` is filled in on join
+    let span = tracing::info_span!(
+        "connection",
+        %client_id,
+        remote_addr = %remote_ip,
+        room_id = tracing::field::Empty,
+    );
+    let max_message_size = max_ws_message_bytes(&state.config);
+    ws.max_message_size(max_message_size)
+        .max_frame_size(max_message_size)
+        .on_upgrade(move |socket| {
+            handle_connection(socket, state, client_id, remote_ip).instrument(span)
+        })
+        .into_response()
+}
+
+/// Largest WebSocket message a client may send. The biggest legitimate one is a shared
+/// subtitle, whose content can double in size once newlines and quotes are JSON-escaped.
+fn max_ws_message_bytes(config: &ServerConfig) -> usize {
+    config
+        .max_subtitle_bytes
+        .saturating_mul(2)
+        .saturating_add(WS_ENVELOPE_BYTES)
+}
+
+/// The peer address, or the first `X-Forwarded-For` hop when the proxy is trusted
+fn client_ip(config: &ServerConfig, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    if config.trust_proxy {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|first| first.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    peer.ip()
+}
+
+async fn serve_index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+async fn serve_web_client() -> Html<&'static str> {
+    Html(WATCH_HTML)
+}
+
+async fn serve_thank_you() -> Html<&'static str> {
+    Html(THANK_YOU_HTML)
+}
+
+async fn health_check() -> &'static str {
+    "ok"
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct InviteQuery {
+    room: Option<String>,
+    /// Invite token minted by the host; passcodes are never part of a link
+    invite: Option<String>,
+    file: Option<String>,
+}
+
+async fn list_public_rooms(State(state): State<AppState>) -> Json<Vec<PublicRoomSummary>> {
+    Json(state.server_state.public_rooms().await)
+}
+
+async fn join_page(
+    State(state): State<AppState>,
+    Query(query): Query<InviteQuery>,
+) -> Html<String> {
+    let schedule = lookup_schedule(&state, query.room.as_deref());
+    Html(render_join_page(
+        query.room,
+        query.invite,
+        query.file,
+        schedule,
+    ))
+}
+
+async fn join_page_with_path(
+    State(state): State<AppState>,
+    Path(room_id): Path<String>,
+    Query(mut query): Query<InviteQuery>,
+) -> Html<String> {
+    if query.room.is_none() {
+        query.room = Some(room_id);
+    }
+    let schedule = lookup_schedule(&state, query.room.as_deref());
+    Html(render_join_page(
+        query.room,
+        query.invite,
+        query.file,
+        schedule,
+    ))
+}
+
+fn lookup_schedule(state: &AppState, room: Option<&str>) -> Option<RoomSchedule> {
+    let room_id = state.server_state.room_code_format().canonicalize(room?);
+    state.server_state.room_schedule(&room_id)
+}
+
+async fn handle_connection(socket: WebSocket, state: AppState, client_id: Uuid, remote_ip: IpAddr) {
+    let server_state = state.server_state.clone();
+    let client_senders = state.client_senders.clone();
+    server_state.add_client(client_id, Some(remote_ip));
+
+    tracing::info!("Client connected");
+
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let mut shutdown_rx = state.shutdown.subscribe();
+
+    // Register client sender
+    client_senders.write().await.insert(client_id, tx.clone());
+    let _ = tx.send(Message::ServerHello {
+        room_code_format: server_state.room_code_format().clone(),
+        max_subtitle_bytes: state.config.max_subtitle_bytes,
+    });
+
+    // Spawn task to send messages to client
+    let mut send_shutdown_rx = shutdown_rx.clone();
+    let send_task = tokio::spawn(
+        async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    msg = rx.recv() => {
+                        let Some(msg) = msg else {
+                            break;
+                        };
+                        if !send_json(&mut ws_sender, &msg).await {
+                            break;
+                        }
+                        // A kicked client is disconnected once it has been told why
+                        if matches!(msg, Message::Kicked { .. }) {
+                            let _ = ws_sender.close().await;
+                            break;
+                        }
+                    }
+                    _ = send_shutdown_rx.changed() => {
+                        // The shutdown notice is queued before the flag flips, so flush what's left
+                        while let Ok(msg) = rx.try_recv() {
+                            if !send_json(&mut ws_sender, &msg).await {
+                                break;
+                            }
+                        }
+                        let _ = ws_sender.close().await;
+                        break;
+                    }
+                }
+            }
+        }
+        .in_current_span(),
+    );
+
+    // Handle incoming messages until the socket closes or the server shuts down
+    let mut shutting_down = *shutdown_rx.borrow();
+    while !shutting_down {
+        let msg = tokio::select! {
+            msg = ws_receiver.next() => msg,
+            _ = shutdown_rx.changed() => {
+                shutting_down = true;
+                break;
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+        match msg {
+            Ok(AxumWsMessage::Text(text)) => {
+                if let Err(e) = handle_message(&text, client_id, &state).await {
+                    tracing::warn!(error = %e, "Rejected client message");
+                    let _ = tx.send(Message::Error {
+                        message: e.to_string(),
+                    });
+                }
+            }
+            Ok(AxumWsMessage::Close(_)) => {
+                tracing::debug!("Client closed the socket");
+                break;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "WebSocket error");
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    // Cleanup
+    if shutting_down {
+        let _ = tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, send_task).await;
+    } else {
+        send_task.abort();
+    }
+    client_senders.write().await.remove(&client_id);
+    let room_id = server_state
+        .clients
+        .get(&client_id)
+        .and_then(|client| client.room_id.clone());
+    let display_name = server_state.display_name(client_id);
+    if let Some(room_id) = &room_id {
+        record_audit(
+            &state,
+            room_id,
+            client_id,
+            AuditEvent::Left {
+                display_name: display_name.clone(),
+            },
+        );
+    }
+    server_state.remove_client(client_id).await;
+    if let Some(room_id) = room_id {
+        broadcast_room_state(&state, &room_id).await;
+        notify_member_left(&state, &room_id, client_id, display_name).await;
+    }
+}
+
+/// Appends to the audit log with the client's address filled in
+fn record_audit(state: &AppState, room_id: &str, client_id: Uuid, event: AuditEvent) {
+    let remote_ip = state.server_state.remote_ip(client_id);
+    state
+        .audit
+        .record(room_id, Some(client_id), remote_ip, event);
+}
+
+/// Reports a member leaving `room_id`, and the room closing if that emptied it
+async fn notify_member_left(
+    state: &AppState,
+    room_id: &str,
+    client_id: Uuid,
+    display_name: String,
+) {
+    let member_count = state.server_state.get_room_members(room_id).await.len();
+    state.webhooks.emit(
+        room_id,
+        WebhookEvent::MemberLeft {
+            client_id,
+            display_name,
+            member_count,
+        },
+    );
+    if !state.server_state.rooms.contains_key(room_id) {
+        state.webhooks.emit(
+            room_id,
+            WebhookEvent::RoomClosed {
+                reason: CloseReason::Empty,
+            },
+        );
+    }
+}
+
+async fn send_json(
+    ws_sender: &mut futures_util::stream::SplitSink<WebSocket, AxumWsMessage>,
+    msg: &Message,
+) -> bool {
+    let json = match serde_json::to_string(msg) {
+        Ok(j) => j,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to serialize message");
+            return true;
+        }
+    };
+
+    if let Err(e) = ws_sender.send(AxumWsMessage::Text(json)).await {
+        tracing::debug!(error = %e, "Failed to send message");
+        return false;
+    }
+    true
+}
+
+fn render_join_page(
+    room: Option<String>,
+    invite: Option<String>,
+    file: Option<String>,
+    schedule: Option<RoomSchedule>,
+) -> String {
+    let room = room.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    });
+
+    let invite = invite.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    });
+
+    let file = file.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    });
+
+    let heading = room
+        .as_ref()
+        .map(|room_id| format!("Join Hang Room {}", html_escape(room_id)))
+        .unwrap_or_else(|| "Hang Invite".to_string());
+
+    let access_block = if invite.is_some() {
+        "<div class=\"info muted\">This invite lets you in without a passcode.</div>"
+    } else {
+        "<div class=\"info muted\">If the room has a passcode, ask the host for it.</div>"
+    };
+
+    let file_block = file
+        .as_ref()
+        .map(|value| {
+            format!(
+                "<div class=\"info\">Expected file: <code>{}</code></div>",
+                html_escape(value)
+            )
+        })
+        .unwrap_or_else(|| {
+            "<div class=\"info muted\">Host did not specify a file name.</div>".to_string()
+        });
+
+    let schedule_block = schedule
+        .as_ref()
+        .map(|schedule| {
+            let media_line = schedule
+                .media
+                .as_ref()
+                .map(|media| {
+                    format!(
+                        "<div class=\"info\">Watching: <code>{}</code></div>",
+                        html_escape(media)
+                    )
+                })
+                .unwrap_or_default();
+            format!(
+                "<div class=\"schedule\">\
+                 <div class=\"info\">Watch party: <strong>{title}</strong></div>\
+                 {media_line}\
+                 <div class=\"countdown\" id=\"countdown\" data-start=\"{start}\">{initial}</div>\
+                 <div class=\"info muted\" id=\"start-local\"></div>\
+                 </div>",
+                title = html_escape(&schedule.title),
+                media_line = media_line,
+                start = schedule.starts_at_ms,
+                initial = if schedule.started {
+                    "Started"
+                } else {
+                    "Starting soon"
+                },
+            )
+        })
+        .unwrap_or_default();
+
+    let countdown_script = if schedule.is_some() {
+        COUNTDOWN_SCRIPT
+    } else {
+        ""
+    };
+
+    let protocol_url = room
+        .as_ref()
+        .map(|room_id| build_protocol_url(room_id, invite.as_deref(), file.as_deref()));
+
+    let launch_section = room
+        .as_ref()
+        .zip(protocol_url.as_ref())
+        .map(|(room_id, url)| {
+            let web_url = build_web_client_url(room_id, invite.as_deref(), file.as_deref());
+            format!(
+                "<a class=\"primary\" href=\"{href}\">Open Hang Client</a>\
+                 <a class=\"secondary\" href=\"{web_href}\">Watch in Browser</a>",
+                href = html_escape_attr(url),
+                web_href = html_escape_attr(&web_url)
+            )
+        })
+        .unwrap_or_else(|| {
+            "<p class=\"muted\">Missing room code. Ask the host for a valid invite link.</p>"
+                .to_string()
+        });
+
+    let auto_launch_script = protocol_url
+        .as_ref()
+        .map(|url| {
+            let js_url =
+                serde_json::to_string(url).unwrap_or_else(|_| "\"hang://join\"".to_string());
+            format!(
+                "<script>setTimeout(function(){{window.location.href={};}}, 450);</script>",
+                js_url
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang=\"en\">
+  <head>
+    <meta charset=\"utf-8\" />
+    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />
+    <title>Hang Invite</title>
+    <style>
+      :root {{
+        color-scheme: dark;
+        --bg: #060606;
+        --card: rgba(14, 14, 14, 0.9);
+        --accent: #ff8a00;
+        --text: #f4f4f4;
+        --muted: #9f9f9f;
+      }}
+      body {{
+        margin: 0;
+        font-family: 'Inter', system-ui, -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif;
+        min-height: 100vh;
+        background: radial-gradient(circle at top, rgba(255, 138, 0, 0.2), transparent 45%), var(--bg);
+        color: var(--text);
+        display: flex;
+        align-items: center;
+        justify-content: center;
+        padding: 3rem 1.25rem;
+      }}
+      .card {{
+        width: min(520px, 100%);
+        background: var(--card);
+        border-radius: 28px;
+        padding: clamp(1.75rem, 4vw, 3rem);
+        box-shadow: 0 20px 70px rgba(0, 0, 0, 0.45);
+        border: 1px solid rgba(255, 255, 255, 0.05);
+      }}
+      h1 {{
+        margin-top: 0;
+        font-size: 1.8rem;
+        letter-spacing: 0.01em;
+      }}
+      .info {{
+        margin-top: 1rem;
+        font-size: 1rem;
+      }}
+      .muted {{
+        color: var(--muted);
+      }}
+      code {{
+        background: rgba(255, 255, 255, 0.08);
+        padding: 0.25rem 0.45rem;
+        border-radius: 0.65rem;
+        font-size: 0.95rem;
+      }}
+      .actions {{
+        margin-top: 2rem;
+        display: flex;
+        flex-direction: column;
+        gap: 0.6rem;
+      }}
+      .primary {{
+        background: linear-gradient(135deg, #ff8a00, #ff6c00);
+        color: #050505;
+        text-decoration: none;
+        text-align: center;
+        font-weight: 600;
+        padding: 0.9rem 1rem;
+        border-radius: 999px;
+      }}
+      .schedule {{
+        margin-top: 1.25rem;
+        padding: 1rem 1.25rem;
+        border-radius: 18px;
+        background: rgba(255, 138, 0, 0.08);
+        border: 1px solid rgba(255, 138, 0, 0.25);
+      }}
+      .countdown {{
+        margin-top: 0.75rem;
+        font-size: 2rem;
+        font-weight: 700;
+        font-variant-numeric: tabular-nums;
+        color: var(--accent);
+      }}
+      .secondary {{
+        border: 1px solid rgba(255, 255, 255, 0.15);
+        border-radius: 999px;
+        text-align: center;
+        padding: 0.85rem 1rem;
+        color: var(--text);
+        text-decoration: none;
+        font-weight: 500;
+      }}
+    </style>
+    {auto_launch_script}
+  </head>
+  <body>
+    <div class=\"card\">
+      <h1>{heading}</h1>
+      {schedule_block}
+      {file_block}
+      {access_block}
+      <div class=\"info muted\">1. Install the Hang desktop client, or watch in your browser.</div>
+      <div class=\"info muted\">2. Load the same video file locally before joining.</div>
+      <div class=\"actions\">
+        {launch_section}
+        <a class=\"secondary\" href=\"/downloads/hang-client.exe\">Download Hang Client</a>
+      </div>
+    </div>
+    {countdown_script}
+  </body>
+</html>
+"#,
+        heading = heading,
+        schedule_block = schedule_block,
+        countdown_script = countdown_script,
+        file_block = file_block,
+        access_block = access_block,
+        launch_section = launch_section,
+        auto_launch_script = auto_launch_script
+    )
+}
+
+/// Ticks the join page countdown towards `data-start` (Unix ms) in the viewer's local time
+const COUNTDOWN_SCRIPT: &str = r#"<script>
+(function () {
+  var el = document.getElementById('countdown');
+  if (!el) { return; }
+  var start = Number(el.getAttribute('data-start'));
+  var local = document.getElementById('start-local');
+  if (local) { local.textContent = 'Starts ' + new Date(start).toLocaleString(); }
+  function pad(n) { return n < 10 ? '0' + n : '' + n; }
+  function tick() {
+    var remaining = Math.floor((start - Date.now()) / 1000);
+    if (remaining <= 0) { el.textContent = 'Started'; return; }
+    var days = Math.floor(remaining / 86400);
+    var hours = Math.floor((remaining % 86400) / 3600);
+    var minutes = Math.floor((remaining % 3600) / 60);
+    var seconds = remaining % 60;
+    el.textContent = (days > 0 ? days + 'd ' : '') + pad(hours) + ':' + pad(minutes) + ':' + pad(seconds);
+    setTimeout(tick, 1000);
+  }
+  tick();
+})();
+</script>"#;
+
+fn build_protocol_url(room: &str, invite: Option<&str>, file: Option<&str>) -> String {
+    format!("hang://join?{}", invite_query(room, invite, file))
+}
+
+/// Same invite parameters for the browser client, served by this server
+fn build_web_client_url(room: &str, invite: Option<&str>, file: Option<&str>) -> String {
+    format!("/watch?{}", invite_query(room, invite, file))
+}
+
+fn invite_query(room: &str, invite: Option<&str>, file: Option<&str>) -> String {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    serializer.append_pair("room", room);
+    if let Some(token) = invite.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed)
+        }
+    }) {
+        serializer.append_pair("invite", token);
+    }
+    if let Some(file_name) = file.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed)
+        }
+    }) {
+        serializer.append_pair("file", file_name);
+    }
+    serializer.finish()
+}
+
+fn html_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn html_escape_attr(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+async fn handle_message(text: &str, client_id: Uuid, app: &AppState) -> anyhow::Result<()> {
+    let state = &app.server_state;
+    let client_senders = &app.client_senders;
+    let msg: Message = serde_json::from_str(text)?;
+
+    match msg {
+        Message::CreateRoom {
+            file_hash,
+            passcode,
+            display_name,
+            capacity,
+            listing,
+            requested_code,
+            schedule,
+            spectator_capacity,
+            sync_tracks,
+        } => {
+            let schedule = match schedule
+                .map(|schedule| schedule::validate(schedule, state::now_millis()))
+                .transpose()
+            {
+                Ok(schedule) => schedule,
+                Err(message) => {
+                    if let Some(tx) = client_senders.read().await.get(&client_id) {
+                        let _ = tx.send(Message::Error { message });
+                    }
+                    return Ok(());
+                }
+            };
+            let canonical_hash = file_hash.clone();
+            let public = listing.is_some();
+            let (room_id, passcode_enabled, room_capacity, resolved_name) = state.create_room(
+                client_id,
+                RoomRequest {
+                    file_hash,
+                    passcode,
+                    display_name,
+                    capacity,
+                    listing,
+                    requested_code,
+                    schedule: schedule.clone(),
+                    spectator_capacity,
+                    sync_tracks,
+                },
+            );
+            if let Some(schedule) = &schedule {
+                tracing::info!(
+                    %room_id,
+                    title = %schedule.title,
+                    starts_at_ms = schedule.starts_at_ms,
+                    "Room reserved for a watch party"
+                );
+                schedule::spawn_start_timer(app.clone(), room_id.clone(), schedule.starts_at_ms);
+            }
+            record_audit(
+                app,
+                &room_id,
+                client_id,
+                AuditEvent::RoomCreated {
+                    display_name: resolved_name.clone(),
+                },
+            );
+            app.webhooks.emit(
+                &room_id,
+                WebhookEvent::RoomCreated {
+                    host_id: client_id,
+                    host_name: resolved_name.clone(),
+                    capacity: room_capacity,
+                    public,
+                    scheduled_start_ms: schedule.as_ref().map(|schedule| schedule.starts_at_ms),
+                },
+            );
+            let resume_token = state.remember_session(client_id, &room_id, &canonical_hash, true);
+            tracing::Span::current().record("room_id", room_id.as_str());
+            tracing::info!(
+                %room_id,
+                display_name = %resolved_name,
+                capacity = room_capacity,
+                "Room created"
+            );
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                let _ = tx.send(Message::RoomCreated {
+                    room_id: room_id.clone(),
+                    client_id,
+                    passcode_enabled,
+                    file_hash: canonical_hash,
+                    resume_token,
+                    capacity: room_capacity,
+                    display_name: resolved_name,
+                    schedule,
+                    sync_tracks,
+                    feed_token: state.feed_token(&room_id),
+                });
+            }
+            app.cluster.announce_room(app, &room_id);
+            broadcast_room_state(app, &room_id).await;
+        }
+
+        Message::JoinRoom {
+            room_id,
+            file_hash,
+            passcode,
+            display_name,
+            spectator,
+            invite,
+        } => {
+            let room_id = state.room_code_format().canonicalize(&room_id);
+            let used_invite = invite.is_some();
+            let result = state
+                .join_room(
+                    client_id,
+                    &room_id,
+                    JoinRequest {
+                        file_hash,
+                        passcode,
+                        invite,
+                        display_name,
+                        spectator,
+                    },
+                )
+                .await;
+            if let Err(reason) = &result {
+                record_audit(
+                    app,
+                    &room_id,
+                    client_id,
+                    AuditEvent::JoinRejected {
+                        display_name: state.display_name(client_id),
+                        reason: reason.to_string(),
+                    },
+                );
+            }
+            let response = match result {
+                Ok((is_host, canonical_hash, room_capacity, resolved_name)) => {
+                    let resume_token =
+                        state.remember_session(client_id, &room_id, &canonical_hash, is_host);
+                    let spectator = state.is_spectator(&room_id, client_id);
+                    let role = if is_host {
+                        "host"
+                    } else if spectator {
+                        "spectator"
+                    } else {
+                        "guest"
+                    };
+                    tracing::Span::current().record("room_id", room_id.as_str());
+                    tracing::info!(%room_id, display_name = %resolved_name, role, "Joined room");
+                    record_audit(
+                        app,
+                        &room_id,
+                        client_id,
+                        AuditEvent::Joined {
+                            display_name: resolved_name.clone(),
+                            spectator,
+                        },
+                    );
+                    app.webhooks.emit(
+                        &room_id,
+                        WebhookEvent::MemberJoined {
+                            client_id,
+                            display_name: resolved_name.clone(),
+                            member_count: state.get_room_members(&room_id).await.len(),
+                        },
+                    );
+                    if used_invite {
+                        // Share the remaining uses with other instances
+                        app.cluster.announce_room(app, &room_id);
+                    }
+                    let (sync_tracks, track_selection) = state.track_sync(&room_id);
+                    Message::RoomJoined {
+                        room_id: room_id.clone(),
+                        client_id,
+                        is_host,
+                        passcode_enabled: state
+                            .rooms
+                            .get(&room_id)
+                            .map(|room| room.passcode_hash.is_some())
+                            .unwrap_or(false),
+                        file_hash: canonical_hash,
+                        resume_token,
+                        capacity: room_capacity,
+                        display_name: resolved_name,
+                        schedule: state.room_schedule(&room_id),
+                        spectator,
+                        sync_tracks,
+                        track_selection,
+                        bookmarks: state.room_bookmarks(&room_id),
+                        feed_token: state.feed_token(&room_id),
+                    }
+                }
+                Err(JoinError::RoomNotFound) => Message::RoomNotFound,
+                Err(JoinError::FileHashMismatch { expected }) => {
+                    Message::FileHashMismatch { expected }
+                }
+                Err(JoinError::Full { capacity, .. }) => Message::RoomFull { capacity },
+                Err(JoinError::AccessDenied(message)) => Message::Error { message },
+            };
+
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                let _ = tx.send(response);
+            }
+
+            broadcast_room_state(app, &room_id).await;
+        }
+
+        Message::LeaveRoom => {
+            let display_name = state.display_name(client_id);
+            let current_room = state
+                .clients
+                .get(&client_id)
+                .and_then(|c| c.room_id.clone());
+            if let Some(room_id) = &current_room {
+                record_audit(
+                    app,
+                    room_id,
+                    client_id,
+                    AuditEvent::Left {
+                        display_name: display_name.clone(),
+                    },
+                );
+            }
+            if let Some(room_id) = state.leave_room(client_id).await {
+                broadcast_room_state(app, &room_id).await;
+                notify_member_left(app, &room_id, client_id, display_name).await;
+            }
+            state.clear_session(client_id);
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                let _ = tx.send(Message::RoomLeft);
+            }
+        }
+        Message::ResumeSession {
+            token,
+            display_name,
+        } => {
+            let response = state.resume_session(client_id, &token, display_name).await;
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                match response {
+                    Ok(outcome) => {
+                        tracing::Span::current().record("room_id", outcome.room_id.as_str());
+                        tracing::info!(
+                            room_id = %outcome.room_id,
+                            display_name = %outcome.display_name,
+                            was_host = outcome.was_host,
+                            "Resumed session"
+                        );
+                        record_audit(
+                            app,
+                            &outcome.room_id,
+                            client_id,
+                            AuditEvent::Resumed {
+                                display_name: outcome.display_name.clone(),
+                            },
+                        );
+                        let (sync_tracks, track_selection) = state.track_sync(&outcome.room_id);
+                        let _ = tx.send(Message::RoomJoined {
+                            room_id: outcome.room_id.clone(),
+                            client_id,
+                            is_host: outcome.was_host,
+                            passcode_enabled: outcome.passcode_enabled,
+                            file_hash: outcome.file_hash.clone(),
+                            resume_token: outcome.resume_token.clone(),
+                            capacity: outcome.capacity,
+                            display_name: outcome.display_name.clone(),
+                            schedule: state.room_schedule(&outcome.room_id),
+                            spectator: outcome.spectator,
+                            sync_tracks,
+                            track_selection,
+                            bookmarks: state.room_bookmarks(&outcome.room_id),
+                            feed_token: state.feed_token(&outcome.room_id),
+                        });
+                        app.webhooks.emit(
+                            &outcome.room_id,
+                            WebhookEvent::MemberJoined {
+                                client_id,
+                                display_name: outcome.display_name.clone(),
+                                member_count: state.get_room_members(&outcome.room_id).await.len(),
+                            },
+                        );
+                        if outcome.was_host {
+                            app.cluster.announce_room(app, &outcome.room_id);
+                            app.webhooks.emit(
+                                &outcome.room_id,
+                                WebhookEvent::HostChanged {
+                                    previous_host_id: outcome.previous_host_id,
+                                    host_id: client_id,
+                                },
+                            );
+                        }
+                        broadcast_room_state(app, &outcome.room_id).await;
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Message::Error { message: err });
+                    }
+                }
+            }
+        }
+
+        Message::CreateInvite {
+            max_uses,
+            expires_in_secs,
+        } => {
+            let response = match state.create_invite(client_id, max_uses, expires_in_secs) {
+                Ok((room_id, token, expires_at_ms)) => {
+                    tracing::info!(%room_id, ?max_uses, ?expires_at_ms, "Invite created");
+                    record_audit(
+                        app,
+                        &room_id,
+                        client_id,
+                        AuditEvent::InviteCreated {
+                            max_uses,
+                            expires_at_ms,
+                        },
+                    );
+                    app.cluster.announce_room(app, &room_id);
+                    Message::InviteCreated {
+                        room_id,
+                        token,
+                        max_uses,
+                        expires_at_ms,
+                    }
+                }
+                Err(message) => Message::Error { message },
+            };
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                let _ = tx.send(response);
+            }
+        }
+
+        Message::RevokeInvite { token } => {
+            let response = match state.revoke_invite(client_id, &token) {
+                Ok(room_id) => {
+                    tracing::info!(%room_id, "Invite revoked");
+                    record_audit(app, &room_id, client_id, AuditEvent::InviteRevoked);
+                    app.cluster.announce_room(app, &room_id);
+                    Message::InviteRevoked { token }
+                }
+                Err(message) => Message::Error { message },
+            };
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                let _ = tx.send(response);
+            }
+        }
+
+        Message::ShareSubtitle { file_name, content } => {
+            let room_id = state
+                .clients
+                .get(&client_id)
+                .and_then(|c| c.room_id.clone());
+            let Some(room_id) = room_id else {
+                anyhow::bail!("Join a room before sharing subtitles");
+            };
+            let file_name = subtitles::validate_shared_subtitle(
+                &file_name,
+                &content,
+                app.config.max_subtitle_bytes,
+            )
+            .map_err(anyhow::Error::msg)?;
+            tracing::info!(%room_id, %file_name, bytes = content.len(), "Subtitle shared");
+            record_audit(
+                app,
+                &room_id,
+                client_id,
+                AuditEvent::SubtitleShared {
+                    file_name: file_name.clone(),
+                    bytes: content.len(),
+                },
+            );
+            let shared = Message::SubtitleShared {
+                from_client: client_id,
+                display_name: state.display_name(client_id),
+                file_name,
+                content,
+            };
+            relay_to_others(app, &room_id, client_id, shared).await;
+        }
+
+        Message::SelectTracks { selection } => {
+            let (room_id, selection) = state
+                .select_tracks(client_id, selection)
+                .map_err(anyhow::Error::msg)?;
+            tracing::debug!(%room_id, ?selection, "Host selected tracks");
+            relay_to_others(
+                app,
+                &room_id,
+                client_id,
+                Message::TracksSelected { selection },
+            )
+            .await;
+        }
+
+        Message::ShareBookmark { bookmark } => {
+            let (room_id, bookmark) = state
+                .share_bookmark(client_id, bookmark)
+                .map_err(anyhow::Error::msg)?;
+            tracing::debug!(%room_id, position = bookmark.position, "Bookmark shared");
+            record_audit(
+                app,
+                &room_id,
+                client_id,
+                AuditEvent::BookmarkShared {
+                    position: bookmark.position,
+                    label: bookmark.label.clone(),
+                },
+            );
+            let shared = Message::BookmarkShared {
+                from_client: client_id,
+                bookmark,
+            };
+            relay_to_others(app, &room_id, client_id, shared).await;
+        }
+
+        Message::ListPublicRooms => {
+            let rooms = state.public_rooms().await;
+            if let Some(tx) = client_senders.read().await.get(&client_id) {
+                let _ = tx.send(Message::PublicRoomList { rooms });
+            }
+        }
+
+        Message::SyncCommand(command) => {
+            // Get client's room
+            let room_id = state
+                .clients
+                .get(&client_id)
+                .and_then(|c| c.room_id.clone());
+
+            if let Some(room_id) = room_id {
+                if state.is_spectator(&room_id, client_id) {
+                    record_audit(
+                        app,
+                        &room_id,
+                        client_id,
+                        AuditEvent::SyncRejected { command },
+                    );
+                    if let Some(tx) = client_senders.read().await.get(&client_id) {
+                        let _ = tx.send(Message::Error {
+                            message: "Spectators can't control playback".to_string(),
+                        });
+                    }
+                    return Ok(());
+                }
+                state.record_playback(&room_id, &command);
+                record_audit(
+                    app,
+                    &room_id,
+                    client_id,
+                    AuditEvent::Sync {
+                        command: command.clone(),
+                    },
+                );
+                match &command {
+                    SyncCommand::Play { timestamp } => app.webhooks.emit(
+                        &room_id,
+                        WebhookEvent::Play {
+                            client_id: Some(client_id),
+                            position: *timestamp,
+                        },
+                    ),
+                    SyncCommand::Pause { timestamp } => app.webhooks.emit(
+                        &room_id,
+                        WebhookEvent::Pause {
+                            client_id,
+                            position: *timestamp,
+                        },
+                    ),
+                    _ => {}
+                }
+                // Broadcast to all room members
+                broadcast_to_room(app, &room_id, client_id, command).await;
+            }
+        }
+
+        _ => {
+            tracing::warn!(message = ?msg, "Unexpected message from client");
+        }
+    }
+
+    Ok(())
+}
+
+async fn broadcast_to_room(app: &AppState, room_id: &str, from_client: Uuid, command: SyncCommand) {
+    let state = &app.server_state;
+    let members = state.get_room_members(room_id).await;
+    let senders = app.client_senders.read().await;
+
+    tracing::debug!(
+        %room_id,
+        %from_client,
+        ?command,
+        members = members.len(),
+        "Broadcasting sync command"
+    );
+
+    let broadcast_msg = Message::SyncBroadcast {
+        from_client,
+        command,
+    };
+    state.publish_to_feed(room_id, &broadcast_msg);
+    app.cluster.deliver(app, room_id, &broadcast_msg);
+
+    for member_id in members {
+        if let Some(tx) = senders.get(&member_id) {
+            let _ = tx.send(broadcast_msg.clone());
+        }
+    }
+}
+
+/// Sends a message to every member of the room except `from_client`, on every instance
+async fn relay_to_others(app: &AppState, room_id: &str, from_client: Uuid, message: Message) {
+    app.cluster.deliver(app, room_id, &message);
+    let members = app.server_state.get_room_members(room_id).await;
+    let senders = app.client_senders.read().await;
+    for member_id in members {
+        if member_id == from_client {
+            continue;
+        }
+        if let Some(tx) = senders.get(&member_id) {
+            let _ = tx.send(message.clone());
+        }
+    }
+}
+
+/// Sends the roster to everyone in the room and shares this instance's part of it with peers
+async fn broadcast_room_state(app: &AppState, room_id: &str) {
+    let state = &app.server_state;
+    // An empty roster tells peers this instance has nobody left in the room
+    app.cluster
+        .publish_roster(app, room_id, state.local_roster(room_id).await);
+    let Some((roster, capacity)) = state.room_snapshot(room_id).await else {
+        return;
+    };
+    if roster.is_empty() {
+        return;
+    }
+    let update = Message::RoomMemberUpdate {
+        room_id: room_id.to_string(),
+        members: roster.clone(),
+        capacity,
+    };
+    state.publish_to_feed(room_id, &update);
+    let senders = app.client_senders.read().await;
+    for member in &roster {
+        if let Some(tx) = senders.get(&member.client_id) {
+            let _ = tx.send(update.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Characters that let a value open a tag or break out of an attribute or script string
+    const MARKUP: [char; 4] = ['<', '>', '"', '\''];
+
+    fn markup_counts(html: &str) -> [usize; 4] {
+        MARKUP.map(|ch| html.matches(ch).count())
+    }
+
+    /// Inert stand-in for a value that keeps whether the page treats it as present
+    fn inert(value: &Option<String>) -> Option<String> {
+        value
+            .as_ref()
+            .filter(|value| !value.trim().is_empty())
+            .map(|_| "x".to_string())
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        prop_oneof![any::<String>(), "[<>\"'&=?#%/ a-z]{0,24}"]
+    }
+
+    proptest! {
+        #[test]
+        fn join_page_never_echoes_markup(
+            room in prop::option::of(text()),
+            invite in prop::option::of(text()),
+            file in prop::option::of(text()),
+            schedule in prop::option::of((text(), prop::option::of(text()))),
+        ) {
+            let schedule = schedule.map(|(title, media)| RoomSchedule {
+                starts_at_ms: 0,
+                title,
+                media,
+                started: false,
+            });
+            let baseline_schedule = schedule.as_ref().map(|schedule| RoomSchedule {
+                title: "x".to_string(),
+                media: schedule.media.as_ref().map(|_| "x".to_string()),
+                ..schedule.clone()
+            });
+            let baseline = render_join_page(inert(&room), inert(&invite), inert(&file), baseline_schedule);
+            let page = render_join_page(room, invite, file, schedule);
+            prop_assert_eq!(markup_counts(&page), markup_counts(&baseline));
+        }
+    }
+
+    #[test]
+    fn join_page_escapes_script_injection() {
+        let payload = "</code><script>alert(1)</script>\"'";
+        let page = render_join_page(
+            Some(payload.to_string()),
+            Some(payload.to_string()),
+            Some(payload.to_string()),
+            Some(RoomSchedule {
+                starts_at_ms: 0,
+                title: payload.to_string(),
+                media: Some(payload.to_string()),
+                started: false,
+            }),
+        );
+        assert!(!page.contains("<script>alert"));
+        assert!(page.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    }
+}
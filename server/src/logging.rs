@@ -0,0 +1,57 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+const DEFAULT_FILTER: &str = "hang_server=info";
+
+/// Log line format (`HANG_LOG_FORMAT`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable single lines with span context
+    Compact,
+    /// One JSON object per line, with the current span's fields under `span`
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "compact" | "text" | "pretty" => Some(Self::Compact),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn init(format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into());
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Compact => builder.with_target(false).compact().init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
+
+/// Middleware that wraps each HTTP request in a span with a short correlation id
+pub async fn request_span(request: Request, next: Next) -> Response {
+    let request_id = Uuid::new_v4().simple().to_string();
+    let span = tracing::info_span!(
+        "request",
+        request_id = &request_id[..12],
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    async move {
+        let response = next.run(request).await;
+        tracing::debug!(status = response.status().as_u16(), "Request finished");
+        response
+    }
+    .instrument(span)
+    .await
+}
@@ -0,0 +1,155 @@
+use axum::http::{header, HeaderMap, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// How long browsers may cache a preflight response
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// Web pages allowed to open WebSockets and call the public HTTP endpoints (`HANG_ALLOWED_ORIGINS`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowedOrigins {
+    /// `*`: any page may connect
+    Any,
+    /// Only pages served by this server plus these origins, e.g. `https://watch.example.com`
+    List(Vec<String>),
+}
+
+impl AllowedOrigins {
+    pub fn parse(raw: &str) -> Self {
+        let entries: Vec<&str> = raw
+            .split(|ch: char| ch == ',' || ch.is_whitespace())
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        if entries.contains(&"*") {
+            return Self::Any;
+        }
+        Self::List(
+            entries
+                .into_iter()
+                .map(|origin| origin.trim_end_matches('/').to_ascii_lowercase())
+                .collect(),
+        )
+    }
+
+    /// Decides whether a WebSocket upgrade may go ahead, returning the rejected origin if not.
+    /// Native clients send no `Origin` and are always allowed; browsers always send one.
+    pub fn check_upgrade(&self, headers: &HeaderMap) -> Result<(), String> {
+        let Some(origin) = headers.get(header::ORIGIN) else {
+            return Ok(());
+        };
+        let origin = origin.to_str().unwrap_or_default().to_ascii_lowercase();
+        let allowed = match self {
+            Self::Any => true,
+            Self::List(list) => list.contains(&origin) || is_same_origin(&origin, headers),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(origin)
+        }
+    }
+
+    /// CORS for the read-only endpoints; same-origin pages don't need it
+    pub fn cors_layer(&self) -> CorsLayer {
+        let allow_origin = match self {
+            Self::Any => AllowOrigin::any(),
+            Self::List(list) => AllowOrigin::list(
+                list.iter()
+                    .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+            ),
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::HEAD])
+            .allow_headers([header::CONTENT_TYPE])
+            .max_age(PREFLIGHT_MAX_AGE)
+    }
+}
+
+/// The page was served by this server, i.e. the origin names the host the request was sent to
+fn is_same_origin(origin: &str, headers: &HeaderMap) -> bool {
+    let Some(host) = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+    else {
+        return false;
+    };
+    let Ok(origin) = url::Url::parse(origin) else {
+        return false;
+    };
+    let Ok(target) = url::Url::parse(&format!("{}://{}", origin.scheme(), host)) else {
+        return false;
+    };
+    origin.host_str().is_some()
+        && origin.host_str() == target.host_str()
+        && origin.port_or_known_default() == target.port_or_known_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(origin: Option<&str>, host: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_str(host).unwrap());
+        if let Some(origin) = origin {
+            headers.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_lists_and_wildcard() {
+        assert_eq!(
+            AllowedOrigins::parse("https://Watch.example.com/, http://localhost:8080"),
+            AllowedOrigins::List(vec![
+                "https://watch.example.com".to_string(),
+                "http://localhost:8080".to_string(),
+            ])
+        );
+        assert_eq!(
+            AllowedOrigins::parse("https://a.example, *"),
+            AllowedOrigins::Any
+        );
+        assert_eq!(AllowedOrigins::parse(""), AllowedOrigins::List(Vec::new()));
+    }
+
+    #[test]
+    fn native_clients_and_same_origin_pages_are_allowed() {
+        let allowed = AllowedOrigins::List(Vec::new());
+        assert!(allowed
+            .check_upgrade(&headers(None, "hang.example:3005"))
+            .is_ok());
+        assert!(allowed
+            .check_upgrade(&headers(
+                Some("http://hang.example:3005"),
+                "hang.example:3005"
+            ))
+            .is_ok());
+        assert!(allowed
+            .check_upgrade(&headers(Some("https://hang.example"), "hang.example:443"))
+            .is_ok());
+        assert!(allowed
+            .check_upgrade(&headers(
+                Some("http://hang.example:8080"),
+                "hang.example:3005"
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn foreign_origins_need_to_be_listed() {
+        let request = headers(Some("https://evil.example"), "hang.example");
+        assert_eq!(
+            AllowedOrigins::List(Vec::new()).check_upgrade(&request),
+            Err("https://evil.example".to_string())
+        );
+        assert!(AllowedOrigins::parse("https://evil.example")
+            .check_upgrade(&request)
+            .is_ok());
+        assert!(AllowedOrigins::Any.check_upgrade(&request).is_ok());
+        assert!(AllowedOrigins::List(Vec::new())
+            .check_upgrade(&headers(Some("null"), "hang.example"))
+            .is_err());
+    }
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 use uuid::Uuid;
 
 /// Messages sent between client and server
@@ -11,21 +13,76 @@ pub enum Message {
         passcode: Option<String>,
         display_name: Option<String>,
         capacity: Option<usize>,
+        /// Present when the room should be advertised in the public directory
+        #[serde(default)]
+        listing: Option<RoomListing>,
+        /// Vanity code such as `friday-anime`; a generated code is used if it is taken
+        #[serde(default)]
+        requested_code: Option<String>,
+        /// Reserve the room for a watch party starting later
+        #[serde(default)]
+        schedule: Option<RoomSchedule>,
+        /// Seats for spectators, on top of `capacity`
+        #[serde(default)]
+        spectator_capacity: Option<usize>,
+        /// Members follow the host's audio and subtitle track choices
+        #[serde(default)]
+        sync_tracks: bool,
     },
     JoinRoom {
         room_id: String,
         file_hash: String,
         passcode: Option<String>,
         display_name: Option<String>,
+        /// Watch without playback control, using a spectator seat
+        #[serde(default)]
+        spectator: bool,
+        /// Invite token from the host, accepted instead of the passcode
+        #[serde(default)]
+        invite: Option<String>,
     },
     ResumeSession {
         token: String,
         display_name: Option<String>,
     },
     LeaveRoom,
+    ListPublicRooms,
     SyncCommand(SyncCommand),
+    /// Host mints an invite token so links don't have to carry the passcode
+    CreateInvite {
+        /// Joins the token admits before it stops working; unlimited if absent
+        #[serde(default)]
+        max_uses: Option<u32>,
+        #[serde(default)]
+        expires_in_secs: Option<u64>,
+    },
+    RevokeInvite {
+        token: String,
+    },
+    /// Host's current tracks, for rooms created with `sync_tracks`
+    SelectTracks {
+        selection: TrackSelection,
+    },
+    /// Offer a text subtitle file to everyone else in the room; the server relays it without
+    /// keeping a copy
+    ShareSubtitle {
+        file_name: String,
+        content: String,
+    },
+    /// Mark a moment for the whole room; the server keeps the room's bookmarks for members
+    /// who join later. Sharing a bookmark id again replaces it.
+    ShareBookmark {
+        bookmark: Bookmark,
+    },
 
     // Server -> Client
+    /// Sent once after connecting so clients can validate input against this server
+    ServerHello {
+        room_code_format: RoomCodeFormat,
+        /// Largest subtitle file `ShareSubtitle` accepts, in bytes; 0 when sharing is disabled
+        #[serde(default)]
+        max_subtitle_bytes: usize,
+    },
     RoomCreated {
         room_id: String,
         client_id: Uuid,
@@ -34,6 +91,14 @@ pub enum Message {
         resume_token: String,
         capacity: usize,
         display_name: String,
+        #[serde(default)]
+        schedule: Option<RoomSchedule>,
+        #[serde(default)]
+        sync_tracks: bool,
+        /// Read-only credential for the room's event feed; unlike `resume_token` it never
+        /// grants a seat, so it is safe to put in a URL
+        #[serde(default)]
+        feed_token: Option<String>,
     },
     RoomJoined {
         room_id: String,
@@ -44,6 +109,31 @@ pub enum Message {
         resume_token: String,
         capacity: usize,
         display_name: String,
+        #[serde(default)]
+        schedule: Option<RoomSchedule>,
+        #[serde(default)]
+        spectator: bool,
+        #[serde(default)]
+        sync_tracks: bool,
+        /// The host's latest track choice when `sync_tracks` is on
+        #[serde(default)]
+        track_selection: Option<TrackSelection>,
+        /// Bookmarks members have shared with the room so far
+        #[serde(default)]
+        bookmarks: Vec<Bookmark>,
+        /// Read-only credential for the room's event feed
+        #[serde(default)]
+        feed_token: Option<String>,
+    },
+    InviteCreated {
+        room_id: String,
+        token: String,
+        max_uses: Option<u32>,
+        /// Unix time in milliseconds after which the token is rejected
+        expires_at_ms: Option<u64>,
+    },
+    InviteRevoked {
+        token: String,
     },
     RoomLeft,
     RoomNotFound,
@@ -57,16 +147,88 @@ pub enum Message {
         from_client: Uuid,
         command: SyncCommand,
     },
+    /// The host switched tracks in a room with `sync_tracks`
+    TracksSelected {
+        selection: TrackSelection,
+    },
+    /// Subtitle file another member shared with the room
+    SubtitleShared {
+        from_client: Uuid,
+        display_name: String,
+        file_name: String,
+        content: String,
+    },
+    /// Bookmark another member shared with the room
+    BookmarkShared {
+        from_client: Uuid,
+        bookmark: Bookmark,
+    },
     RoomMemberUpdate {
         room_id: String,
         members: Vec<MemberSummary>,
         capacity: usize,
     },
+    /// A scheduled watch party reached its start time; play from the beginning
+    ScheduledStart {
+        room_id: String,
+    },
+    PublicRoomList {
+        rooms: Vec<PublicRoomSummary>,
+    },
+    /// The server is going away; clients should reconnect after the given delay
+    ServerShuttingDown {
+        reconnect_after_ms: u64,
+    },
+    /// The room was closed by an operator; members are no longer in it
+    RoomClosed {
+        room_id: String,
+        reason: Option<String>,
+    },
+    /// This client was removed from its room by an operator
+    Kicked {
+        reason: Option<String>,
+    },
+    /// Free-form announcement from the server operator
+    ServerNotice {
+        message: String,
+    },
     Error {
         message: String,
     },
 }
 
+/// Audio and subtitle tracks picked by the host. Track ids differ between copies of a video,
+/// so tracks are named by their label instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackSelection {
+    /// `None` leaves each member's audio track alone
+    #[serde(default)]
+    pub audio: Option<TrackLabel>,
+    /// `None` turns subtitles off
+    #[serde(default)]
+    pub subtitle: Option<TrackLabel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackLabel {
+    pub title: String,
+    #[serde(default)]
+    pub lang: String,
+}
+
+/// Labelled moment in the room's video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// Chosen by the member who created it, so copies can be matched up
+    pub id: String,
+    /// Seconds from the start of the video
+    pub position: f64,
+    pub label: String,
+    /// Display name of the member who shared it; filled in by the server
+    #[serde(default)]
+    pub author: String,
+}
+
 /// Synchronization commands for video playback
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action")]
@@ -85,6 +247,156 @@ pub struct Room {
     pub file_hash: String,
     pub passcode_hash: Option<String>,
     pub capacity: usize,
+    /// Seats for spectators; they don't count towards `capacity`
+    pub spectator_capacity: usize,
+    /// Members who joined as spectators and may not send sync commands.
+    /// Only this instance's members; remote spectators are flagged in their roster.
+    pub spectators: HashSet<Uuid>,
+    pub playback: PlaybackState,
+    pub listing: Option<RoomListing>,
+    pub schedule: Option<RoomSchedule>,
+    /// Outstanding invites, keyed by the SHA-256 digest of their token
+    pub invites: HashMap<String, Invite>,
+    pub sync_tracks: bool,
+    /// Last tracks the host picked, sent to members as they join
+    pub track_selection: Option<TrackSelection>,
+    /// Shared bookmarks in the order they were first shared
+    pub bookmarks: Vec<Bookmark>,
+    /// Lets members subscribe to the event feed without the passcode; empty on rooms mirrored
+    /// from another instance, which then accept only the passcode
+    pub feed_token: String,
+    /// Tells apart rooms that got the same code on two instances before they heard of each
+    /// other; nil on rooms announced by instances that predate it
+    pub uid: Uuid,
+    /// Addresses of members an admin kicked from this instance, with the time (unix ms) until
+    /// which they may not rejoin
+    pub kicked: HashMap<IpAddr, u64>,
+}
+
+/// What an instance announces about its room so the others can accept joins for it.
+/// Bearer tokens stay on the instance: invites travel only as digests, the feed token not at all.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomMirror {
+    pub host_id: Uuid,
+    pub file_hash: String,
+    pub passcode_hash: Option<String>,
+    pub capacity: usize,
+    pub spectator_capacity: usize,
+    pub playback: PlaybackState,
+    pub listing: Option<RoomListing>,
+    pub schedule: Option<RoomSchedule>,
+    /// Outstanding invites, keyed by the SHA-256 digest of their token
+    #[serde(default)]
+    pub invites: HashMap<String, Invite>,
+    #[serde(default)]
+    pub sync_tracks: bool,
+    #[serde(default)]
+    pub track_selection: Option<TrackSelection>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub uid: Uuid,
+}
+
+/// Invite token minted by the host; admits guests without the passcode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invite {
+    /// Joins left before the token is removed, or `None` for unlimited
+    pub uses_left: Option<u32>,
+    pub expires_at_ms: Option<u64>,
+}
+
+impl Invite {
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at_ms.is_some_and(|expires| now_ms >= expires)
+    }
+}
+
+/// Title and description shown for rooms in the public directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomListing {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Directory entry for a public room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicRoomSummary {
+    pub room_id: String,
+    pub title: String,
+    pub description: String,
+    pub host_name: String,
+    /// Members holding a regular seat; spectators are not included
+    pub member_count: usize,
+    pub capacity: usize,
+    pub passcode_protected: bool,
+    pub is_playing: bool,
+    /// Start time of a scheduled watch party, in Unix milliseconds
+    #[serde(default)]
+    pub starts_at_ms: Option<u64>,
+}
+
+/// Last known playback state of a room, derived from relayed sync commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybackState {
+    pub is_playing: bool,
+    /// Position in seconds at `updated_at_ms`
+    pub position: f64,
+    pub rate: f64,
+    /// Unix time in milliseconds of the last command, if any
+    pub updated_at_ms: Option<u64>,
+}
+
+impl Default for PlaybackState {
+    fn default() -> Self {
+        Self {
+            is_playing: false,
+            position: 0.0,
+            rate: 1.0,
+            updated_at_ms: None,
+        }
+    }
+}
+
+impl PlaybackState {
+    pub fn apply(&mut self, command: &SyncCommand, now_ms: u64) {
+        // Fold elapsed play time into the position before applying a rate change
+        let position = self.position_at(now_ms);
+        match command {
+            SyncCommand::Play { timestamp } => {
+                self.is_playing = true;
+                self.position = *timestamp;
+            }
+            SyncCommand::Pause { timestamp } => {
+                self.is_playing = false;
+                self.position = *timestamp;
+            }
+            SyncCommand::Seek { timestamp } => {
+                self.position = *timestamp;
+            }
+            SyncCommand::Speed { rate } => {
+                self.position = position;
+                self.rate = *rate;
+            }
+            SyncCommand::Stop => {
+                self.is_playing = false;
+                self.position = 0.0;
+            }
+        }
+        self.updated_at_ms = Some(now_ms);
+    }
+
+    /// Estimated position at `now_ms`, extrapolating while playing
+    pub fn position_at(&self, now_ms: u64) -> f64 {
+        match self.updated_at_ms {
+            Some(updated) if self.is_playing => {
+                let elapsed = now_ms.saturating_sub(updated) as f64 / 1000.0;
+                self.position + elapsed * self.rate
+            }
+            _ => self.position,
+        }
+    }
 }
 
 /// Client connection metadata
@@ -92,6 +404,8 @@ pub struct Room {
 pub struct ClientInfo {
     pub room_id: Option<String>,
     pub display_name: String,
+    /// Address the client connected from, recorded in the audit log
+    pub remote_ip: Option<IpAddr>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,4 +413,35 @@ pub struct MemberSummary {
     pub client_id: Uuid,
     pub display_name: String,
     pub is_host: bool,
+    #[serde(default)]
+    pub is_spectator: bool,
+}
+
+/// Shape of the room codes issued and accepted by the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomCodeFormat {
+    /// Characters used in generated codes
+    pub charset: String,
+    /// Number of characters in a generated code, excluding separators
+    pub length: usize,
+    /// Generated codes are split into hyphen-separated groups of this size
+    pub group_size: usize,
+    /// Whether hosts may request a vanity code
+    pub allow_vanity: bool,
+    pub vanity_min_len: usize,
+    pub vanity_max_len: usize,
+}
+
+/// Planned start of a watch party reserved ahead of time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSchedule {
+    /// Unix time in milliseconds
+    pub starts_at_ms: u64,
+    pub title: String,
+    /// File name or URL the party will watch
+    #[serde(default)]
+    pub media: Option<String>,
+    /// Set by the server once playback has been triggered
+    #[serde(default)]
+    pub started: bool,
 }
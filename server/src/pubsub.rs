@@ -0,0 +1,496 @@
+use anyhow::Context;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc};
+
+/// Payloads buffered for a subscriber before it starts skipping ahead
+const MEMORY_CAPACITY: usize = 1024;
+/// Payloads queued for the Redis publisher before new ones are dropped
+const PUBLISH_QUEUE_CAPACITY: usize = 1024;
+const PUBLISH_ATTEMPTS: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest bulk string Redis itself will store; longer announced lengths are bogus
+const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+
+/// Message transport shared by every hang-server instance in a deployment
+pub trait PubSub: Send + Sync {
+    /// Queues `payload` for every subscriber, including this instance's own.
+    /// Never blocks; payloads that cannot be queued are dropped with a warning.
+    fn publish(&self, payload: String);
+
+    /// Payloads published from now on by any instance, preceded by `Delivery::Subscribed`
+    /// and again by it after every gap in which payloads may have been missed
+    fn subscribe(&self) -> BoxStream<'static, Delivery>;
+}
+
+/// What a subscription yields
+#[derive(Debug, PartialEq)]
+pub enum Delivery {
+    /// The subscription is live, either for the first time or after a gap. Anything
+    /// published before this was not delivered, so subscribers should resync.
+    Subscribed,
+    Payload(String),
+}
+
+/// In-process bus: a single instance on its own, or several `ServerState`s in one test
+#[derive(Clone)]
+pub struct MemoryPubSub {
+    tx: broadcast::Sender<String>,
+}
+
+impl MemoryPubSub {
+    pub fn new() -> Self {
+        Self {
+            tx: broadcast::channel(MEMORY_CAPACITY).0,
+        }
+    }
+}
+
+impl PubSub for MemoryPubSub {
+    fn publish(&self, payload: String) {
+        let _ = self.tx.send(payload);
+    }
+
+    fn subscribe(&self) -> BoxStream<'static, Delivery> {
+        let live = stream::unfold(self.tx.subscribe(), |mut rx| async move {
+            match rx.recv().await {
+                Ok(payload) => Some((Delivery::Payload(payload), rx)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "Pub/sub subscriber lagged");
+                    Some((Delivery::Subscribed, rx))
+                }
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        });
+        stream::once(async { Delivery::Subscribed })
+            .chain(live)
+            .boxed()
+    }
+}
+
+/// Redis `PUBLISH`/`SUBSCRIBE` on a single channel, reconnecting with backoff.
+/// Publishing and each subscription use their own connection, as Redis requires.
+pub struct RedisPubSub {
+    target: RedisTarget,
+    channel: String,
+    tx: mpsc::Sender<String>,
+}
+
+#[derive(Debug, Clone)]
+struct RedisTarget {
+    /// `host:port`
+    addr: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl RedisPubSub {
+    /// Parses `redis://[[user]:password@]host[:port]` and starts the publisher
+    pub fn connect(url: &str, channel: String) -> anyhow::Result<Self> {
+        let target = RedisTarget::parse(url)?;
+        let (tx, rx) = mpsc::channel(PUBLISH_QUEUE_CAPACITY);
+        tokio::spawn(run_publisher(target.clone(), channel.clone(), rx));
+        tracing::info!(addr = %target.addr, %channel, "Redis pub/sub enabled");
+        Ok(Self {
+            target,
+            channel,
+            tx,
+        })
+    }
+}
+
+impl PubSub for RedisPubSub {
+    fn publish(&self, payload: String) {
+        if let Err(err) = self.tx.try_send(payload) {
+            tracing::warn!(error = %err, "Dropping pub/sub message");
+        }
+    }
+
+    fn subscribe(&self) -> BoxStream<'static, Delivery> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_subscriber(
+            self.target.clone(),
+            self.channel.clone(),
+            tx,
+        ));
+        stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|delivery| (delivery, rx))
+        })
+        .boxed()
+    }
+}
+
+impl RedisTarget {
+    fn parse(raw: &str) -> anyhow::Result<Self> {
+        let url = url::Url::parse(raw).context("Invalid HANG_REDIS_URL")?;
+        if url.scheme() != "redis" {
+            anyhow::bail!("HANG_REDIS_URL must use redis:// (TLS is not supported)");
+        }
+        let host = url.host_str().context("HANG_REDIS_URL has no host")?;
+        let port = url.port().unwrap_or(6379);
+        let username = Some(url.username())
+            .filter(|user| !user.is_empty())
+            .map(str::to_string);
+        Ok(Self {
+            addr: format!("{}:{}", host, port),
+            username,
+            password: url.password().map(str::to_string),
+        })
+    }
+
+    async fn connect(&self) -> io::Result<RedisConnection> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&self.addr))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connect timed out"))??;
+        let mut connection = RedisConnection {
+            stream: BufReader::new(stream),
+        };
+        if let Some(password) = &self.password {
+            let mut args = vec!["AUTH"];
+            args.extend(self.username.as_deref());
+            args.push(password);
+            connection.command(&args).await?;
+        }
+        Ok(connection)
+    }
+}
+
+struct RedisConnection {
+    stream: BufReader<TcpStream>,
+}
+
+impl RedisConnection {
+    async fn send(&mut self, args: &[&str]) -> io::Result<()> {
+        self.stream.get_mut().write_all(&encode_command(args)).await
+    }
+
+    /// Sends a command and returns its reply, turning Redis errors into `io::Error`s
+    async fn command(&mut self, args: &[&str]) -> io::Result<Reply> {
+        self.send(args).await?;
+        match read_reply(&mut self.stream).await? {
+            Reply::Error(message) => Err(io::Error::other(message)),
+            reply => Ok(reply),
+        }
+    }
+}
+
+async fn run_publisher(target: RedisTarget, channel: String, mut rx: mpsc::Receiver<String>) {
+    let mut connection: Option<RedisConnection> = None;
+    let mut backoff = INITIAL_BACKOFF;
+    while let Some(payload) = rx.recv().await {
+        // Events go stale quickly, so give up on one rather than replay it much later
+        for attempt in 1..=PUBLISH_ATTEMPTS {
+            let result = match connection.as_mut() {
+                Some(open) => open
+                    .command(&["PUBLISH", &channel, &payload])
+                    .await
+                    .map(drop),
+                None => match target.connect().await {
+                    Ok(mut fresh) => {
+                        let result = fresh
+                            .command(&["PUBLISH", &channel, &payload])
+                            .await
+                            .map(drop);
+                        connection = Some(fresh);
+                        result
+                    }
+                    Err(err) => Err(err),
+                },
+            };
+            match result {
+                Ok(()) => {
+                    backoff = INITIAL_BACKOFF;
+                    break;
+                }
+                Err(err) => {
+                    tracing::warn!(addr = %target.addr, error = %err, attempt, "Redis publish failed");
+                    connection = None;
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+async fn run_subscriber(target: RedisTarget, channel: String, tx: mpsc::UnboundedSender<Delivery>) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let session = async {
+            let mut connection = target.connect().await?;
+            connection.command(&["SUBSCRIBE", &channel]).await?;
+            tracing::info!(addr = %target.addr, %channel, "Subscribed to Redis channel");
+            backoff = INITIAL_BACKOFF;
+            if tx.send(Delivery::Subscribed).is_err() {
+                return Ok::<(), io::Error>(());
+            }
+            loop {
+                // Pushes arrive as ["message", channel, payload]
+                let Reply::Array(Some(parts)) = read_reply(&mut connection.stream).await? else {
+                    continue;
+                };
+                if let [Reply::Bulk(Some(kind)), _, Reply::Bulk(Some(payload))] = parts.as_slice() {
+                    if kind == b"message" {
+                        let payload = String::from_utf8_lossy(payload).into_owned();
+                        if tx.send(Delivery::Payload(payload)).is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        };
+        let result = tokio::select! {
+            result = session => result,
+            _ = tx.closed() => return,
+        };
+        match result {
+            Ok(()) => return,
+            Err(err) => {
+                tracing::warn!(addr = %target.addr, error = %err, "Redis subscription lost, reconnecting");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// A RESP reply, as much of it as the pub/sub commands need
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+fn encode_command(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+fn read_reply<'a, R>(
+    reader: &'a mut R,
+) -> Pin<Box<dyn Future<Output = io::Result<Reply>> + Send + 'a>>
+where
+    R: AsyncBufRead + Unpin + Send,
+{
+    Box::pin(async move {
+        let line = read_line(reader).await?;
+        let kind = line.as_bytes()[0];
+        // Type markers are ASCII; for anything else this is empty and the match rejects it
+        let rest = line.get(1..).unwrap_or_default();
+        match kind {
+            b'+' => Ok(Reply::Simple(rest.to_string())),
+            b'-' => Ok(Reply::Error(rest.to_string())),
+            b':' => Ok(Reply::Integer(parse_len(rest)?)),
+            b'$' => {
+                let Ok(len) = usize::try_from(parse_len(rest)?) else {
+                    return Ok(Reply::Bulk(None));
+                };
+                if len > MAX_BULK_LEN {
+                    return Err(invalid_data(format!(
+                        "RESP bulk string too long ({} bytes)",
+                        len
+                    )));
+                }
+                let mut data = vec![0; len + 2];
+                reader.read_exact(&mut data).await?;
+                data.truncate(len);
+                Ok(Reply::Bulk(Some(data)))
+            }
+            b'*' => {
+                let Ok(len) = usize::try_from(parse_len(rest)?) else {
+                    return Ok(Reply::Array(None));
+                };
+                let mut items = Vec::with_capacity(len.min(64));
+                for _ in 0..len {
+                    items.push(read_reply(reader).await?);
+                }
+                Ok(Reply::Array(Some(items)))
+            }
+            _ => Err(invalid_data(format!(
+                "unexpected RESP type byte {:#04x}",
+                kind
+            ))),
+        }
+    })
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed",
+        ));
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() {
+        return Err(invalid_data("empty RESP line".to_string()));
+    }
+    Ok(line.to_string())
+}
+
+fn parse_len(raw: &str) -> io::Result<i64> {
+    raw.parse()
+        .map_err(|_| invalid_data(format!("invalid RESP length {:?}", raw)))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> io::Result<Reply> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut reader = BufReader::new(bytes);
+            read_reply(&mut reader).await
+        })
+    }
+
+    #[test]
+    fn encodes_commands_as_bulk_string_arrays() {
+        assert_eq!(
+            encode_command(&["PUBLISH", "hang", "{}"]),
+            b"*3\r\n$7\r\nPUBLISH\r\n$4\r\nhang\r\n$2\r\n{}\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn parses_pushed_messages() {
+        let reply = parse(b"*3\r\n$7\r\nmessage\r\n$4\r\nhang\r\n$7\r\n{\"a\":1}\r\n").unwrap();
+        assert_eq!(
+            reply,
+            Reply::Array(Some(vec![
+                Reply::Bulk(Some(b"message".to_vec())),
+                Reply::Bulk(Some(b"hang".to_vec())),
+                Reply::Bulk(Some(b"{\"a\":1}".to_vec())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parses_scalars_and_nulls() {
+        assert_eq!(parse(b"+OK\r\n").unwrap(), Reply::Simple("OK".into()));
+        assert_eq!(
+            parse(b"-ERR nope\r\n").unwrap(),
+            Reply::Error("ERR nope".into())
+        );
+        assert_eq!(parse(b":2\r\n").unwrap(), Reply::Integer(2));
+        assert_eq!(parse(b"$-1\r\n").unwrap(), Reply::Bulk(None));
+        assert_eq!(parse(b"*-1\r\n").unwrap(), Reply::Array(None));
+        assert!(parse(b"$5\r\nab").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_types_and_oversized_bulk_strings() {
+        assert!(parse("é1\r\n".as_bytes()).is_err());
+        assert!(parse(b"?1\r\n").is_err());
+        assert!(parse(b"$536870913\r\n").is_err());
+        assert!(parse(b"$18446744073709551615\r\n").is_err());
+    }
+
+    #[test]
+    fn parses_redis_urls() {
+        let target = RedisTarget::parse("redis://:secret@cache.internal").unwrap();
+        assert_eq!(target.addr, "cache.internal:6379");
+        assert_eq!(target.username, None);
+        assert_eq!(target.password.as_deref(), Some("secret"));
+
+        let target = RedisTarget::parse("redis://hang:pw@10.0.0.5:6380/2").unwrap();
+        assert_eq!(target.addr, "10.0.0.5:6380");
+        assert_eq!(target.username.as_deref(), Some("hang"));
+        assert!(RedisTarget::parse("rediss://cache.internal").is_err());
+    }
+
+    #[test]
+    fn memory_bus_reaches_every_subscriber() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let bus = MemoryPubSub::new();
+            let mut first = bus.subscribe();
+            let mut second = bus.clone().subscribe();
+            bus.publish("hello".to_string());
+            for subscriber in [&mut first, &mut second] {
+                assert_eq!(subscriber.next().await, Some(Delivery::Subscribed));
+                assert_eq!(
+                    subscriber.next().await,
+                    Some(Delivery::Payload("hello".to_string()))
+                );
+            }
+        });
+    }
+
+    /// Accepts one subscriber connection, confirms its SUBSCRIBE and pushes `payload` to it
+    async fn serve_subscriber(listener: &tokio::net::TcpListener, payload: &str) -> TcpStream {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        let command = read_reply(&mut stream).await.unwrap();
+        let Reply::Array(Some(args)) = command else {
+            panic!("expected a command, got {:?}", command);
+        };
+        assert_eq!(args[0], Reply::Bulk(Some(b"SUBSCRIBE".to_vec())));
+        let socket = stream.get_mut();
+        socket
+            .write_all(b"*3\r\n$9\r\nsubscribe\r\n$4\r\nhang\r\n:1\r\n")
+            .await
+            .unwrap();
+        let push = format!(
+            "*3\r\n$7\r\nmessage\r\n$4\r\nhang\r\n${}\r\n{}\r\n",
+            payload.len(),
+            payload
+        );
+        socket.write_all(push.as_bytes()).await.unwrap();
+        stream.into_inner()
+    }
+
+    #[tokio::test]
+    async fn redis_subscriber_reports_each_resubscribe() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = RedisTarget {
+            addr: listener.local_addr().unwrap().to_string(),
+            username: None,
+            password: None,
+        };
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_subscriber(target, "hang".to_string(), tx));
+
+        let first = serve_subscriber(&listener, "before").await;
+        assert_eq!(rx.recv().await, Some(Delivery::Subscribed));
+        assert_eq!(
+            rx.recv().await,
+            Some(Delivery::Payload("before".to_string()))
+        );
+
+        // Whatever is published while the connection is down never arrives,
+        // so the subscriber has to say it started over
+        drop(first);
+        let _second = serve_subscriber(&listener, "after").await;
+        assert_eq!(rx.recv().await, Some(Delivery::Subscribed));
+        assert_eq!(
+            rx.recv().await,
+            Some(Delivery::Payload("after".to_string()))
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use rand::Rng;
+
+use crate::protocol::RoomCodeFormat;
+
+const NUMERIC_CHARSET: &str = "0123456789";
+/// Lowercase letters and digits without look-alikes (0/o, 1/i/l)
+const ALPHANUMERIC_CHARSET: &str = "23456789abcdefghjkmnpqrstuvwxyz";
+const MIN_CODE_LENGTH: usize = 4;
+const MAX_CODE_LENGTH: usize = 16;
+const VANITY_MIN_LEN: usize = 3;
+const VANITY_MAX_LEN: usize = 32;
+
+/// Character set used for generated room codes (`HANG_ROOM_CODE_STYLE`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomCodeStyle {
+    /// `123-456`
+    Numeric,
+    /// `k7dq-m3xa`
+    Alphanumeric,
+}
+
+impl RoomCodeStyle {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "numeric" | "digits" => Some(Self::Numeric),
+            "alphanumeric" | "alnum" => Some(Self::Alphanumeric),
+            _ => None,
+        }
+    }
+}
+
+impl RoomCodeFormat {
+    pub fn new(style: RoomCodeStyle, length: Option<usize>, allow_vanity: bool) -> Self {
+        let (charset, default_length, group_size) = match style {
+            RoomCodeStyle::Numeric => (NUMERIC_CHARSET, 6, 3),
+            RoomCodeStyle::Alphanumeric => (ALPHANUMERIC_CHARSET, 8, 4),
+        };
+        Self {
+            charset: charset.to_string(),
+            length: length
+                .unwrap_or(default_length)
+                .clamp(MIN_CODE_LENGTH, MAX_CODE_LENGTH),
+            group_size,
+            allow_vanity,
+            vanity_min_len: VANITY_MIN_LEN,
+            vanity_max_len: VANITY_MAX_LEN,
+        }
+    }
+
+    /// Random code in this format; callers must still check it is unused
+    pub fn generate(&self) -> String {
+        let charset: Vec<char> = self.charset.chars().collect();
+        let mut rng = rand::thread_rng();
+        let compact: String = (0..self.length)
+            .map(|_| charset[rng.gen_range(0..charset.len())])
+            .collect();
+        self.group(&compact)
+    }
+
+    /// Normalizes user input so `K7DQM3XA`, `k7dq m3xa` and `k7dq-m3xa` all resolve to one room
+    pub fn canonicalize(&self, input: &str) -> String {
+        let lowered = input.trim().to_lowercase();
+        let compact: String = lowered
+            .chars()
+            .filter(|ch| *ch != '-' && !ch.is_whitespace())
+            .collect();
+        if compact.chars().count() == self.length
+            && compact.chars().all(|ch| self.charset.contains(ch))
+        {
+            self.group(&compact)
+        } else {
+            lowered
+        }
+    }
+
+    /// Checks a host-requested vanity code, returning its canonical form
+    pub fn validate_vanity(&self, input: &str) -> Result<String, String> {
+        if !self.allow_vanity {
+            return Err("Vanity room codes are disabled on this server".to_string());
+        }
+        let code = input.trim().to_lowercase();
+        let len = code.chars().count();
+        if len < self.vanity_min_len || len > self.vanity_max_len {
+            return Err(format!(
+                "Vanity codes must be {}-{} characters",
+                self.vanity_min_len, self.vanity_max_len
+            ));
+        }
+        if !code
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+        {
+            return Err("Vanity codes may only use letters, digits, and hyphens".to_string());
+        }
+        if code.starts_with('-') || code.ends_with('-') || code.contains("--") {
+            return Err("Vanity codes cannot start, end, or repeat hyphens".to_string());
+        }
+        if !code.chars().any(|ch| ch.is_ascii_lowercase()) {
+            return Err("Vanity codes must contain a letter".to_string());
+        }
+        Ok(self.canonicalize(&code))
+    }
+
+    fn group(&self, compact: &str) -> String {
+        let chars: Vec<char> = compact.chars().collect();
+        chars
+            .chunks(self.group_size.max(1))
+            .map(|chunk| chunk.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_are_grouped_from_the_charset() {
+        let numeric = RoomCodeFormat::new(RoomCodeStyle::Numeric, None, true);
+        let alphanumeric = RoomCodeFormat::new(RoomCodeStyle::Alphanumeric, Some(10), true);
+        for _ in 0..200 {
+            let code = numeric.generate();
+            assert_eq!(code.len(), 7);
+            assert_eq!(code.as_bytes()[3], b'-');
+            assert!(code
+                .replace('-', "")
+                .chars()
+                .all(|ch| NUMERIC_CHARSET.contains(ch)));
+
+            let code = alphanumeric.generate();
+            let groups: Vec<&str> = code.split('-').collect();
+            assert_eq!(
+                groups.iter().map(|group| group.len()).collect::<Vec<_>>(),
+                [4, 4, 2]
+            );
+            assert!(groups
+                .concat()
+                .chars()
+                .all(|ch| ALPHANUMERIC_CHARSET.contains(ch)));
+            assert_eq!(alphanumeric.canonicalize(&code), code);
+        }
+        assert_eq!(
+            RoomCodeFormat::new(RoomCodeStyle::Numeric, Some(100), true).length,
+            MAX_CODE_LENGTH
+        );
+        assert_eq!(
+            RoomCodeFormat::new(RoomCodeStyle::Numeric, Some(1), true).length,
+            MIN_CODE_LENGTH
+        );
+    }
+
+    #[test]
+    fn canonicalize_accepts_any_spelling_of_a_generated_code() {
+        let format = RoomCodeFormat::new(RoomCodeStyle::Alphanumeric, None, true);
+        for input in ["K7DQM3XA", "k7dq m3xa", " k7dq-m3xa ", "k7-dq-m3-xa"] {
+            assert_eq!(format.canonicalize(input), "k7dq-m3xa");
+        }
+        let numeric = RoomCodeFormat::new(RoomCodeStyle::Numeric, None, true);
+        assert_eq!(numeric.canonicalize("123456"), "123-456");
+        // Anything that isn't a generated code is only trimmed and lowercased
+        assert_eq!(format.canonicalize(" Friday-Anime "), "friday-anime");
+        assert_eq!(numeric.canonicalize("12345"), "12345");
+    }
+
+    #[test]
+    fn vanity_codes_are_validated() {
+        let format = RoomCodeFormat::new(RoomCodeStyle::Numeric, None, true);
+        assert_eq!(
+            format.validate_vanity(" Friday-Anime ").unwrap(),
+            "friday-anime"
+        );
+        assert!(format.validate_vanity("ab").is_err());
+        assert!(format
+            .validate_vanity(&"a".repeat(VANITY_MAX_LEN + 1))
+            .is_err());
+        assert!(format.validate_vanity("movie night").is_err());
+        assert!(format.validate_vanity("-movie").is_err());
+        assert!(format.validate_vanity("movie-").is_err());
+        assert!(format.validate_vanity("movie--night").is_err());
+        assert!(format.validate_vanity("123-456").is_err());
+
+        let disabled = RoomCodeFormat::new(RoomCodeStyle::Numeric, None, false);
+        assert!(disabled.validate_vanity("friday-anime").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use crate::protocol::{Message, RoomSchedule};
+use crate::state::now_millis;
+use crate::webhooks::{CloseReason, WebhookEvent};
+use crate::AppState;
+
+/// Furthest ahead a watch party can be reserved
+const MAX_LEAD_TIME_MS: u64 = 14 * 24 * 60 * 60 * 1000;
+/// Start times this far in the past are treated as "now" to absorb clock skew
+const START_TOLERANCE_MS: u64 = 60 * 1000;
+const MAX_TITLE_LEN: usize = 80;
+const MAX_MEDIA_LEN: usize = 512;
+const REAPER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Checks a host-supplied schedule and returns the cleaned-up copy to store
+pub fn validate(schedule: RoomSchedule, now_ms: u64) -> Result<RoomSchedule, String> {
+    if schedule.starts_at_ms.saturating_add(START_TOLERANCE_MS) < now_ms {
+        return Err("Scheduled start time is in the past".to_string());
+    }
+    if schedule.starts_at_ms > now_ms.saturating_add(MAX_LEAD_TIME_MS) {
+        return Err("Watch parties can be scheduled at most 14 days ahead".to_string());
+    }
+
+    let title: String = schedule
+        .title
+        .trim()
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .take(MAX_TITLE_LEN)
+        .collect();
+    if title.is_empty() {
+        return Err("Scheduled watch parties need a title".to_string());
+    }
+    let media = schedule
+        .media
+        .map(|media| media.trim().chars().take(MAX_MEDIA_LEN).collect::<String>())
+        .filter(|media| !media.is_empty());
+
+    Ok(RoomSchedule {
+        starts_at_ms: schedule.starts_at_ms.max(now_ms),
+        title,
+        media,
+        started: false,
+    })
+}
+
+/// Sleeps until the scheduled start, then tells everyone in the room to play from the top
+pub fn spawn_start_timer(app: AppState, room_id: String, starts_at_ms: u64) {
+    tokio::spawn(async move {
+        let state = &app.server_state;
+        let wait_ms = starts_at_ms.saturating_sub(now_millis());
+        tokio::time::sleep(Duration::from_millis(wait_ms)).await;
+
+        if !state.begin_scheduled_start(&room_id, starts_at_ms) {
+            return;
+        }
+        let members = state.get_room_members(&room_id).await;
+        tracing::info!(%room_id, waiting = members.len(), "Scheduled watch party started");
+
+        let notice = Message::ScheduledStart {
+            room_id: room_id.clone(),
+        };
+        state.publish_to_feed(&room_id, &notice);
+        app.cluster.deliver(&app, &room_id, &notice);
+        let senders = app.client_senders.read().await;
+        for member_id in members {
+            if let Some(tx) = senders.get(&member_id) {
+                let _ = tx.send(notice.clone());
+            }
+        }
+        app.webhooks.emit(
+            &room_id,
+            WebhookEvent::Play {
+                client_id: None,
+                position: 0.0,
+            },
+        );
+    });
+}
+
+/// Periodically frees scheduled rooms nobody showed up for
+pub fn spawn_reaper(app: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REAPER_INTERVAL);
+        loop {
+            interval.tick().await;
+            for room_id in app.server_state.reap_expired_reservations().await {
+                app.webhooks.emit(
+                    &room_id,
+                    WebhookEvent::RoomClosed {
+                        reason: CloseReason::Expired,
+                    },
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW_MS: u64 = 1_700_000_000_000;
+
+    fn schedule(starts_at_ms: u64) -> RoomSchedule {
+        RoomSchedule {
+            starts_at_ms,
+            title: "  Movie night\n".to_string(),
+            media: Some("   ".to_string()),
+            started: true,
+        }
+    }
+
+    #[test]
+    fn rejects_start_times_in_the_past_or_too_far_ahead() {
+        assert!(validate(schedule(NOW_MS - START_TOLERANCE_MS - 1), NOW_MS).is_err());
+        assert!(validate(schedule(NOW_MS + MAX_LEAD_TIME_MS + 1), NOW_MS).is_err());
+        assert!(validate(schedule(NOW_MS + MAX_LEAD_TIME_MS), NOW_MS).is_ok());
+
+        // A start a few seconds ago is clock skew, not an error
+        let skewed = validate(schedule(NOW_MS - 5_000), NOW_MS).unwrap();
+        assert_eq!(skewed.starts_at_ms, NOW_MS);
+    }
+
+    #[test]
+    fn cleans_up_what_the_host_sent() {
+        let cleaned = validate(schedule(NOW_MS + 60_000), NOW_MS).unwrap();
+        assert_eq!(cleaned.title, "Movie night");
+        assert_eq!(cleaned.media, None);
+        assert!(!cleaned.started);
+
+        let untitled = RoomSchedule {
+            title: " ".to_string(),
+            ..schedule(NOW_MS + 60_000)
+        };
+        assert!(validate(untitled, NOW_MS).is_err());
+    }
+}
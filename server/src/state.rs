@@ -1,15 +1,39 @@
 use dashmap::DashMap;
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
 
-use crate::protocol::{ClientInfo, MemberSummary, Room};
+use crate::config::ServerConfig;
+use crate::protocol::{
+    Bookmark, ClientInfo, Invite, MemberSummary, Message, PlaybackState, PublicRoomSummary, Room,
+    RoomCodeFormat, RoomListing, RoomMirror, RoomSchedule, SyncCommand, TrackLabel, TrackSelection,
+};
 
-const LOG_TAG: &str = "[Hang Server]";
 const DEFAULT_CAPACITY: usize = 12;
 const MIN_CAPACITY: usize = 2;
 const MAX_CAPACITY: usize = 32;
+const DEFAULT_SPECTATOR_CAPACITY: usize = 100;
+const MAX_SPECTATOR_CAPACITY: usize = 500;
+const MAX_LISTING_TITLE_LEN: usize = 64;
+const MAX_LISTING_DESCRIPTION_LEN: usize = 280;
+const MAX_TRACK_LABEL_LEN: usize = 128;
+const MAX_BOOKMARK_LABEL_LEN: usize = 80;
+const MAX_BOOKMARK_ID_LEN: usize = 64;
+/// Shared bookmarks a room may hold at once
+const MAX_BOOKMARKS_PER_ROOM: usize = 200;
+/// Outstanding invite tokens a room may hold at once
+const MAX_INVITES_PER_ROOM: usize = 32;
+/// Longest lifetime a host may give an invite
+const MAX_INVITE_LIFETIME_SECS: u64 = 30 * 24 * 60 * 60;
+/// Events buffered per room feed before slow subscribers start skipping ahead
+const FEED_CAPACITY: usize = 64;
+/// How long a kicked client's address is kept out of the room
+const KICK_BLOCK_MS: u64 = 5 * 60 * 1000;
 
 /// Shared server state
 #[derive(Clone)]
@@ -20,42 +44,133 @@ pub struct ServerState {
     pub clients: Arc<DashMap<Uuid, ClientInfo>>,
     /// Room membership: room_id -> Vec<client_id>
     pub room_members: Arc<DashMap<String, Arc<RwLock<Vec<Uuid>>>>>,
+    /// Members connected to other instances: room_id -> instance_id -> roster
+    remote_members: Arc<DashMap<String, HashMap<Uuid, Vec<MemberSummary>>>>,
     /// Resume tokens issued for reconnect support
     resume_tokens: Arc<DashMap<String, ResumeRecord>>,
     /// Mapping of client id to the last token we issued
     client_tokens: Arc<DashMap<Uuid, String>>,
+    /// Format used for generated codes and to normalize codes typed by users
+    code_format: Arc<RoomCodeFormat>,
+    /// Time an empty scheduled room is kept after its start
+    schedule_grace_ms: u64,
+    /// Read-only event feeds for spectators: room_id -> channel
+    room_feeds: Arc<DashMap<String, broadcast::Sender<Message>>>,
+}
+
+/// Settings a host sends when creating a room
+pub struct RoomRequest {
+    pub file_hash: String,
+    pub passcode: Option<String>,
+    pub display_name: Option<String>,
+    pub capacity: Option<usize>,
+    pub listing: Option<RoomListing>,
+    pub requested_code: Option<String>,
+    pub schedule: Option<RoomSchedule>,
+    pub spectator_capacity: Option<usize>,
+    pub sync_tracks: bool,
+}
+
+/// Credentials and preferences a client sends when joining a room
+pub struct JoinRequest {
+    pub file_hash: String,
+    pub passcode: Option<String>,
+    /// Invite token, accepted instead of the passcode
+    pub invite: Option<String>,
+    pub display_name: Option<String>,
+    pub spectator: bool,
+}
+
+/// Why a client could not join a room
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinError {
+    RoomNotFound,
+    FileHashMismatch { expected: String },
+    /// Every seat of the kind the client asked for is taken
+    Full { capacity: usize, spectator: bool },
+    /// Missing or wrong passcode, or an invite that no longer works
+    AccessDenied(String),
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RoomNotFound => f.write_str("Room not found"),
+            Self::FileHashMismatch { .. } => f.write_str("File hash mismatch"),
+            Self::Full {
+                spectator: false, ..
+            } => f.write_str("Room is full"),
+            Self::Full {
+                spectator: true, ..
+            } => f.write_str("No spectator seats left"),
+            Self::AccessDenied(reason) => f.write_str(reason),
+        }
+    }
 }
 
 impl ServerState {
-    pub fn new() -> Self {
+    pub fn new(config: &ServerConfig) -> Self {
         Self {
             rooms: Arc::new(DashMap::new()),
             clients: Arc::new(DashMap::new()),
             room_members: Arc::new(DashMap::new()),
+            remote_members: Arc::new(DashMap::new()),
             resume_tokens: Arc::new(DashMap::new()),
             client_tokens: Arc::new(DashMap::new()),
+            code_format: Arc::new(config.room_code_format.clone()),
+            schedule_grace_ms: config.schedule_grace_ms,
+            room_feeds: Arc::new(DashMap::new()),
         }
     }
 
+    pub fn room_code_format(&self) -> &RoomCodeFormat {
+        &self.code_format
+    }
+
     pub fn create_room(
         &self,
         host_id: Uuid,
-        file_hash: String,
-        passcode: Option<String>,
-        display_name: Option<String>,
-        capacity: Option<usize>,
+        request: RoomRequest,
     ) -> (String, bool, usize, String) {
-        let room_id = self.generate_room_code();
+        let RoomRequest {
+            file_hash,
+            passcode,
+            display_name,
+            capacity,
+            listing,
+            requested_code,
+            schedule,
+            spectator_capacity,
+            sync_tracks,
+        } = request;
+        let room_id = requested_code
+            .and_then(|code| self.claim_vanity_code(&code))
+            .unwrap_or_else(|| self.generate_room_code());
         let passcode_hash = passcode
             .filter(|code| !code.is_empty())
             .map(|code| Self::hash_passcode(&code, &room_id));
         let assigned_name = self.apply_display_name(host_id, display_name);
         let room_capacity = Self::normalize_capacity(capacity);
+        let listing = listing.map(|listing| Self::sanitize_listing(listing, &assigned_name));
         let room = Room {
             host_id,
             file_hash: file_hash.clone(),
             passcode_hash: passcode_hash.clone(),
             capacity: room_capacity,
+            spectator_capacity: spectator_capacity
+                .unwrap_or(DEFAULT_SPECTATOR_CAPACITY)
+                .min(MAX_SPECTATOR_CAPACITY),
+            spectators: HashSet::new(),
+            playback: PlaybackState::default(),
+            listing,
+            schedule,
+            invites: HashMap::new(),
+            sync_tracks,
+            track_selection: None,
+            bookmarks: Vec::new(),
+            feed_token: Uuid::new_v4().simple().to_string(),
+            uid: Uuid::new_v4(),
+            kicked: HashMap::new(),
         };
 
         self.rooms.insert(room_id.clone(), room);
@@ -67,7 +182,7 @@ impl ServerState {
             client.room_id = Some(room_id.clone());
         }
 
-        tracing::info!("{LOG_TAG} Room {} created by client {}", room_id, host_id);
+        tracing::debug!(%room_id, %host_id, "Room registered");
         (
             room_id,
             passcode_hash.is_some(),
@@ -80,59 +195,87 @@ impl ServerState {
         &self,
         client_id: Uuid,
         room_id: &str,
-        file_hash: &str,
-        passcode: Option<String>,
-        display_name: Option<String>,
-    ) -> Result<(bool, String, usize, String), String> {
+        request: JoinRequest,
+    ) -> Result<(bool, String, usize, String), JoinError> {
+        let JoinRequest {
+            file_hash,
+            passcode,
+            invite,
+            display_name,
+            spectator,
+        } = request;
         let assigned_name = self.apply_display_name(client_id, display_name);
+        let remote_ip = self.remote_ip(client_id);
         // Check if room exists
-        let room = self
-            .rooms
-            .get(room_id)
-            .ok_or_else(|| "Room not found".to_string())?;
+        let room = self.rooms.get(room_id).ok_or(JoinError::RoomNotFound)?;
 
         // Verify file hash matches
         if room.file_hash != file_hash {
-            return Err("File hash mismatch".to_string());
+            return Err(JoinError::FileHashMismatch {
+                expected: room.file_hash.clone(),
+            });
         }
 
-        let capacity = room.capacity;
-
-        if let Some(expected) = &room.passcode_hash {
-            let provided = passcode
-                .as_ref()
-                .filter(|code| !code.is_empty())
-                .ok_or_else(|| "Passcode required".to_string())?;
-            let computed = Self::hash_passcode(provided, room_id);
-            if &computed != expected {
-                return Err("Invalid passcode".to_string());
-            }
+        let now = now_millis();
+        if remote_ip.is_some_and(|ip| room.kicked.get(&ip).is_some_and(|until| *until > now)) {
+            return Err(JoinError::AccessDenied(
+                "You were removed from this room; try again later".to_string(),
+            ));
         }
 
+        let capacity = room.capacity;
+        let invite = Self::check_access(&room, room_id, passcode.as_deref(), invite.as_deref())
+            .map_err(JoinError::AccessDenied)?;
+
         let is_host = room.host_id == client_id;
+        // The host always holds a regular seat
+        let spectator = spectator && !is_host;
         let canonical_hash = room.file_hash.clone();
         drop(room);
 
         // Add client to room members
-        if let Some(members) = self.room_members.get(room_id) {
-            let mut members = members.write().await;
-            let already_member = members.contains(&client_id);
-            if !already_member && members.len() >= capacity {
-                return Err("Room is full".to_string());
+        let members_lock = self
+            .room_members
+            .get(room_id)
+            .map(|entry| Arc::clone(&*entry));
+        let Some(members_lock) = members_lock else {
+            return Err(JoinError::RoomNotFound);
+        };
+        let mut members = members_lock.write().await;
+        if !members.contains(&client_id) {
+            let (remote_seated, remote_spectators) = self.remote_counts(room_id);
+            let mut room = self
+                .rooms
+                .get_mut(room_id)
+                .ok_or(JoinError::RoomNotFound)?;
+            if spectator {
+                if room.spectators.len() + remote_spectators >= room.spectator_capacity {
+                    return Err(JoinError::Full {
+                        capacity: room.spectator_capacity,
+                        spectator: true,
+                    });
+                }
+                room.spectators.insert(client_id);
+            } else if members.len() - room.spectators.len() + remote_seated >= capacity {
+                return Err(JoinError::Full {
+                    capacity,
+                    spectator: false,
+                });
             }
-            if !already_member {
-                members.push(client_id);
+            // Only a join that actually takes a seat uses up the invite
+            if let Some(key) = &invite {
+                Self::consume_invite(&mut room, key);
             }
-        } else {
-            return Err("Room not found".to_string());
+            members.push(client_id);
         }
+        drop(members);
 
         // Update client's room
         if let Some(mut client) = self.clients.get_mut(&client_id) {
             client.room_id = Some(room_id.to_string());
         }
 
-        tracing::info!("{LOG_TAG} Client {} joined room {}", client_id, room_id);
+        tracing::debug!(%client_id, %room_id, "Client added to room");
         Ok((is_host, canonical_hash, capacity, assigned_name))
     }
 
@@ -141,34 +284,229 @@ impl ServerState {
         let room_id = self.clients.get(&client_id).and_then(|c| c.room_id.clone());
 
         if let Some(room_id) = room_id.clone() {
+            // Clear client's room
+            if let Some(mut client) = self.clients.get_mut(&client_id) {
+                client.room_id = None;
+            }
+            if let Some(mut room) = self.rooms.get_mut(&room_id) {
+                room.spectators.remove(&client_id);
+            }
+
             // Remove from room members
-            if let Some(members) = self.room_members.get(&room_id) {
-                let mut members = members.write().await;
+            // Clone the lock out so the map shard isn't held while removing the room below
+            let members_lock = self
+                .room_members
+                .get(&room_id)
+                .map(|entry| Arc::clone(&*entry));
+            if let Some(members_lock) = members_lock {
+                let mut members = members_lock.write().await;
                 members.retain(|id| *id != client_id);
 
-                // If room is empty, clean it up
+                // If room is empty everywhere, clean it up unless it is reserved for a watch party
                 if members.is_empty() {
                     drop(members);
-                    self.room_members.remove(&room_id);
-                    self.rooms.remove(&room_id);
-                    self.clear_tokens_for_room(&room_id);
-                    tracing::info!("{LOG_TAG} Room {} deleted (empty)", room_id);
-                    return Some(room_id);
+                    if self.remove_if_deserted(&room_id).await {
+                        return Some(room_id);
+                    }
                 }
             }
 
-            // Clear client's room
-            if let Some(mut client) = self.clients.get_mut(&client_id) {
-                client.room_id = None;
-            }
-
-            tracing::info!("{LOG_TAG} Client {} left room {}", client_id, room_id);
+            tracing::info!(%client_id, %room_id, "Client left room");
             Some(room_id)
         } else {
             None
         }
     }
 
+    /// Deletes a room nobody is in on any instance, unless it is reserved for a watch party.
+    /// Returns whether the room was deleted.
+    async fn remove_if_deserted(&self, room_id: &str) -> bool {
+        let reserved = self
+            .rooms
+            .get(room_id)
+            .is_some_and(|room| self.is_reserved(&room, now_millis()));
+        if reserved || self.remote_counts(room_id) != (0, 0) {
+            return false;
+        }
+        let members_lock = self
+            .room_members
+            .get(room_id)
+            .map(|entry| Arc::clone(&*entry));
+        if let Some(members_lock) = members_lock {
+            // Hold the lock so nobody joins while the room is being removed
+            let members = members_lock.write().await;
+            if !members.is_empty() {
+                return false;
+            }
+            self.room_members.remove(room_id);
+        }
+        if self.rooms.remove(room_id).is_none() {
+            return false;
+        }
+        self.remote_members.remove(room_id);
+        self.clear_tokens_for_room(room_id);
+        self.close_feed(room_id);
+        tracing::info!(%room_id, "Room deleted (empty)");
+        true
+    }
+
+    /// Keeps the client's address out of the room for a while after an admin kicks them.
+    /// Call before the client leaves, while its address is still known.
+    pub fn block_rejoin(&self, room_id: &str, client_id: Uuid) {
+        let Some(ip) = self.remote_ip(client_id) else {
+            return;
+        };
+        if let Some(mut room) = self.rooms.get_mut(room_id) {
+            let now = now_millis();
+            room.kicked.retain(|_, until| *until > now);
+            room.kicked.insert(ip, now + KICK_BLOCK_MS);
+        }
+    }
+
+    /// Forcefully remove a room, returning the members that were in it
+    pub async fn close_room(&self, room_id: &str) -> Option<Vec<Uuid>> {
+        self.rooms.remove(room_id)?;
+        self.remote_members.remove(room_id);
+        let members = match self.room_members.remove(room_id) {
+            Some((_, members)) => members.read().await.clone(),
+            None => Vec::new(),
+        };
+        for member_id in &members {
+            if let Some(mut client) = self.clients.get_mut(member_id) {
+                client.room_id = None;
+            }
+        }
+        self.clear_tokens_for_room(room_id);
+        self.close_feed(room_id);
+        tracing::info!(%room_id, members = members.len(), "Room closed");
+        Some(members)
+    }
+
+    /// Scheduled rooms stay alive while empty until their start plus the grace window
+    fn is_reserved(&self, room: &Room, now_ms: u64) -> bool {
+        room.schedule.as_ref().is_some_and(|schedule| {
+            now_ms < schedule.starts_at_ms.saturating_add(self.schedule_grace_ms)
+        })
+    }
+
+    pub fn room_schedule(&self, room_id: &str) -> Option<RoomSchedule> {
+        self.rooms
+            .get(room_id)
+            .and_then(|room| room.schedule.clone())
+    }
+
+    /// Marks a scheduled room as started and records playback from the beginning.
+    /// Returns false if the room is gone or was rescheduled in the meantime.
+    pub fn begin_scheduled_start(&self, room_id: &str, starts_at_ms: u64) -> bool {
+        let Some(mut room) = self.rooms.get_mut(room_id) else {
+            return false;
+        };
+        match room.schedule.as_mut() {
+            Some(schedule) if schedule.starts_at_ms == starts_at_ms && !schedule.started => {
+                schedule.started = true;
+            }
+            _ => return false,
+        }
+        room.playback
+            .apply(&SyncCommand::Play { timestamp: 0.0 }, now_millis());
+        true
+    }
+
+    /// Drops empty scheduled rooms whose grace window has passed
+    pub async fn reap_expired_reservations(&self) -> Vec<String> {
+        let now = now_millis();
+        let expired: Vec<String> = self
+            .rooms
+            .iter()
+            .filter(|entry| entry.schedule.is_some() && !self.is_reserved(entry.value(), now))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        let mut reaped = Vec::new();
+        for room_id in expired {
+            if self.get_room_members(&room_id).await.is_empty()
+                && self.remote_counts(&room_id) == (0, 0)
+            {
+                self.rooms.remove(&room_id);
+                self.room_members.remove(&room_id);
+                self.remote_members.remove(&room_id);
+                self.clear_tokens_for_room(&room_id);
+                self.close_feed(&room_id);
+                tracing::info!(%room_id, "Room expired (scheduled, empty)");
+                reaped.push(room_id);
+            }
+        }
+        reaped
+    }
+
+    /// Track the room's playback state from a relayed sync command
+    pub fn record_playback(&self, room_id: &str, command: &SyncCommand) {
+        if let Some(mut room) = self.rooms.get_mut(room_id) {
+            room.playback.apply(command, now_millis());
+        }
+    }
+
+    /// Checks a feed subscriber's passcode and subscribes them to the room's event feed.
+    /// Members may present the room's feed token instead, e.g. after joining with an invite.
+    /// Resume tokens are never accepted: they would end up in access logs.
+    pub fn subscribe_feed(
+        &self,
+        room_id: &str,
+        token: Option<&str>,
+    ) -> Result<broadcast::Receiver<Message>, String> {
+        {
+            let room = self
+                .rooms
+                .get(room_id)
+                .ok_or_else(|| "Room not found".to_string())?;
+            let has_feed_token =
+                !room.feed_token.is_empty() && token == Some(room.feed_token.as_str());
+            if !has_feed_token {
+                Self::check_passcode(&room, room_id, token)?;
+            }
+        }
+        let receiver = self
+            .room_feeds
+            .entry(room_id.to_string())
+            .or_insert_with(|| broadcast::channel(FEED_CAPACITY).0)
+            .subscribe();
+        // The room may have closed between the check and the insert above
+        if !self.rooms.contains_key(room_id) {
+            self.room_feeds.remove(room_id);
+            return Err("Room not found".to_string());
+        }
+        Ok(receiver)
+    }
+
+    /// Forwards a message to the room's spectators, if it has any
+    pub fn publish_to_feed(&self, room_id: &str, message: &Message) {
+        if let Some(feed) = self.room_feeds.get(room_id) {
+            let _ = feed.send(message.clone());
+        }
+    }
+
+    /// Tells spectators the room is gone and ends their streams
+    fn close_feed(&self, room_id: &str) {
+        if let Some((_, feed)) = self.room_feeds.remove(room_id) {
+            let _ = feed.send(Message::RoomClosed {
+                room_id: room_id.to_string(),
+                reason: None,
+            });
+        }
+    }
+
+    /// Read-only credential members get for the room's event feed
+    pub fn feed_token(&self, room_id: &str) -> Option<String> {
+        self.rooms
+            .get(room_id)
+            .map(|room| room.feed_token.clone())
+            .filter(|token| !token.is_empty())
+    }
+
+    pub fn room_playback(&self, room_id: &str) -> Option<PlaybackState> {
+        self.rooms.get(room_id).map(|room| room.playback.clone())
+    }
+
     pub async fn get_room_members(&self, room_id: &str) -> Vec<Uuid> {
         if let Some(members_ref) = self.room_members.get(room_id) {
             let members_lock = Arc::clone(&*members_ref);
@@ -180,21 +518,21 @@ impl ServerState {
         }
     }
 
-    pub fn add_client(&self, client_id: Uuid) {
+    pub fn add_client(&self, client_id: Uuid, remote_ip: Option<IpAddr>) {
         self.clients.insert(
             client_id,
             ClientInfo {
                 room_id: None,
                 display_name: Self::default_display_name(client_id),
+                remote_ip,
             },
         );
-        tracing::info!("{LOG_TAG} Client {} connected", client_id);
     }
 
     pub async fn remove_client(&self, client_id: Uuid) {
         let _ = self.leave_room(client_id).await;
         self.clients.remove(&client_id);
-        tracing::info!("{LOG_TAG} Client {} disconnected", client_id);
+        tracing::info!(%client_id, "Client disconnected");
     }
 
     pub fn remember_session(
@@ -210,6 +548,7 @@ impl ServerState {
         }
 
         let display_name = self.clients.get(&client_id).map(|c| c.display_name.clone());
+        let spectator = self.is_spectator(room_id, client_id);
 
         self.resume_tokens.insert(
             token.clone(),
@@ -218,6 +557,7 @@ impl ServerState {
                 room_id: room_id.to_string(),
                 file_hash: file_hash.to_string(),
                 was_host,
+                spectator,
                 display_name,
             },
         );
@@ -225,6 +565,12 @@ impl ServerState {
         token
     }
 
+    pub fn is_spectator(&self, room_id: &str, client_id: Uuid) -> bool {
+        self.rooms
+            .get(room_id)
+            .is_some_and(|room| room.spectators.contains(&client_id))
+    }
+
     pub fn clear_session(&self, client_id: Uuid) {
         if let Some((_, token)) = self.client_tokens.remove(&client_id) {
             self.resume_tokens.remove(&token);
@@ -270,9 +616,14 @@ impl ServerState {
             .map(|room| (room.passcode_hash.is_some(), room.capacity))
             .ok_or_else(|| "Room not found".to_string())?;
 
-        if record.was_host {
-            if let Some(mut room) = self.rooms.get_mut(&record.room_id) {
-                room.host_id = client_id;
+        let mut previous_host_id = None;
+        if let Some(mut room) = self.rooms.get_mut(&record.room_id) {
+            if record.was_host {
+                previous_host_id = Some(std::mem::replace(&mut room.host_id, client_id));
+            }
+            if record.spectator {
+                room.spectators.remove(&record.client_id);
+                room.spectators.insert(client_id);
             }
         }
 
@@ -297,6 +648,7 @@ impl ServerState {
                 ClientInfo {
                     room_id: Some(record.room_id.clone()),
                     display_name: resolved_name.clone(),
+                    remote_ip: None,
                 },
             );
         }
@@ -316,19 +668,260 @@ impl ServerState {
             file_hash: record.file_hash,
             capacity,
             display_name: resolved_name,
+            previous_host_id,
+            spectator: record.spectator,
         })
     }
 
     fn generate_room_code(&self) -> String {
         loop {
-            let raw = (Uuid::new_v4().as_u128() % 1_000_000) as u32;
-            let code = format!("{:03}-{:03}", raw / 1000, raw % 1000);
+            let code = self.code_format.generate();
             if !self.rooms.contains_key(&code) {
                 break code;
             }
         }
     }
 
+    /// Returns the requested vanity code if it is valid and free
+    fn claim_vanity_code(&self, requested: &str) -> Option<String> {
+        match self.code_format.validate_vanity(requested) {
+            Ok(code) if !self.rooms.contains_key(&code) => Some(code),
+            Ok(code) => {
+                tracing::info!(%code, "Vanity code is taken");
+                None
+            }
+            Err(reason) => {
+                tracing::info!(?requested, %reason, "Rejected vanity code");
+                None
+            }
+        }
+    }
+
+    /// Mints an invite token for the room the host is in, returning it with its expiry
+    pub fn create_invite(
+        &self,
+        client_id: Uuid,
+        max_uses: Option<u32>,
+        expires_in_secs: Option<u64>,
+    ) -> Result<(String, String, Option<u64>), String> {
+        let room_id = self.host_room(client_id, "Only the host can manage invites")?;
+        let now = now_millis();
+        let mut room = self
+            .rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| "Room not found".to_string())?;
+        room.invites.retain(|_, invite| !invite.is_expired(now));
+        if room.invites.len() >= MAX_INVITES_PER_ROOM {
+            return Err("Too many active invites; revoke one first".to_string());
+        }
+        let expires_at_ms = expires_in_secs
+            .map(|secs| now.saturating_add(secs.clamp(1, MAX_INVITE_LIFETIME_SECS) * 1000));
+        let token = Uuid::new_v4().simple().to_string();
+        room.invites.insert(
+            Self::invite_key(&token),
+            Invite {
+                uses_left: max_uses.map(|uses| uses.max(1)),
+                expires_at_ms,
+            },
+        );
+        Ok((room_id, token, expires_at_ms))
+    }
+
+    /// Stores the host's track choice for later joiners, returning the room to relay it to
+    pub fn select_tracks(
+        &self,
+        client_id: Uuid,
+        selection: TrackSelection,
+    ) -> Result<(String, TrackSelection), String> {
+        let room_id = self.host_room(client_id, "Only the host can choose tracks for the room")?;
+        let mut room = self
+            .rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| "Room not found".to_string())?;
+        if !room.sync_tracks {
+            return Err("Track sync is off for this room".to_string());
+        }
+        let clean = |label: TrackLabel| TrackLabel {
+            title: label.title.chars().take(MAX_TRACK_LABEL_LEN).collect(),
+            lang: label.lang.chars().take(MAX_TRACK_LABEL_LEN).collect(),
+        };
+        let selection = TrackSelection {
+            audio: selection.audio.map(clean),
+            subtitle: selection.subtitle.map(clean),
+        };
+        room.track_selection = Some(selection.clone());
+        Ok((room_id, selection))
+    }
+
+    /// Applies a track choice relayed from another instance
+    pub fn record_track_selection(&self, room_id: &str, selection: &TrackSelection) {
+        if let Some(mut room) = self.rooms.get_mut(room_id) {
+            room.track_selection = Some(selection.clone());
+        }
+    }
+
+    /// Whether the room follows the host's tracks, and the host's latest choice
+    pub fn track_sync(&self, room_id: &str) -> (bool, Option<TrackSelection>) {
+        self.rooms
+            .get(room_id)
+            .map(|room| (room.sync_tracks, room.track_selection.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Stores a bookmark for the sender's room, signed with their display name.
+    /// Returns the room to relay the cleaned-up bookmark to.
+    pub fn share_bookmark(
+        &self,
+        client_id: Uuid,
+        bookmark: Bookmark,
+    ) -> Result<(String, Bookmark), String> {
+        let room_id = self
+            .clients
+            .get(&client_id)
+            .and_then(|client| client.room_id.clone())
+            .ok_or_else(|| "Join a room before sharing bookmarks".to_string())?;
+        let label: String = bookmark
+            .label
+            .trim()
+            .chars()
+            .filter(|ch| !ch.is_control())
+            .take(MAX_BOOKMARK_LABEL_LEN)
+            .collect();
+        let label = label.trim_end();
+        if label.is_empty() {
+            return Err("Bookmarks need a label".to_string());
+        }
+        if !bookmark.position.is_finite() || bookmark.position < 0.0 {
+            return Err("Invalid bookmark position".to_string());
+        }
+        if bookmark.id.is_empty() || bookmark.id.len() > MAX_BOOKMARK_ID_LEN {
+            return Err("Invalid bookmark id".to_string());
+        }
+        let bookmark = Bookmark {
+            id: bookmark.id,
+            position: bookmark.position,
+            label: label.to_string(),
+            author: self.display_name(client_id),
+        };
+        let mut room = self
+            .rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| "Room not found".to_string())?;
+        if let Some(existing) = room.bookmarks.iter_mut().find(|b| b.id == bookmark.id) {
+            *existing = bookmark.clone();
+        } else if room.bookmarks.len() >= MAX_BOOKMARKS_PER_ROOM {
+            return Err(format!(
+                "This room already has {} bookmarks",
+                MAX_BOOKMARKS_PER_ROOM
+            ));
+        } else {
+            room.bookmarks.push(bookmark.clone());
+        }
+        Ok((room_id, bookmark))
+    }
+
+    /// Applies a bookmark relayed from another instance
+    pub fn record_bookmark(&self, room_id: &str, bookmark: &Bookmark) {
+        if let Some(mut room) = self.rooms.get_mut(room_id) {
+            if let Some(existing) = room.bookmarks.iter_mut().find(|b| b.id == bookmark.id) {
+                *existing = bookmark.clone();
+            } else {
+                room.bookmarks.push(bookmark.clone());
+            }
+        }
+    }
+
+    pub fn room_bookmarks(&self, room_id: &str) -> Vec<Bookmark> {
+        self.rooms
+            .get(room_id)
+            .map(|room| room.bookmarks.clone())
+            .unwrap_or_default()
+    }
+
+    /// Removes an invite from the host's room, returning the room id
+    pub fn revoke_invite(&self, client_id: Uuid, token: &str) -> Result<String, String> {
+        let room_id = self.host_room(client_id, "Only the host can manage invites")?;
+        let mut room = self
+            .rooms
+            .get_mut(&room_id)
+            .ok_or_else(|| "Room not found".to_string())?;
+        room.invites
+            .remove(&Self::invite_key(token))
+            .ok_or_else(|| "Invite not found".to_string())?;
+        Ok(room_id)
+    }
+
+    /// The room `client_id` hosts, or `denied` if they are a member but not the host
+    fn host_room(&self, client_id: Uuid, denied: &str) -> Result<String, String> {
+        let room_id = self
+            .clients
+            .get(&client_id)
+            .and_then(|client| client.room_id.clone())
+            .ok_or_else(|| "Not in a room".to_string())?;
+        let is_host = self
+            .rooms
+            .get(&room_id)
+            .is_some_and(|room| room.host_id == client_id);
+        if !is_host {
+            return Err(denied.to_string());
+        }
+        Ok(room_id)
+    }
+
+    /// Admits a valid invite token or else the passcode.
+    /// Returns the invite's key when that is what let the client in.
+    fn check_access(
+        room: &Room,
+        room_id: &str,
+        passcode: Option<&str>,
+        invite: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        if let Some(token) = invite.filter(|token| !token.is_empty()) {
+            let key = Self::invite_key(token);
+            let valid = room
+                .invites
+                .get(&key)
+                .is_some_and(|invite| !invite.is_expired(now_millis()));
+            if valid {
+                return Ok(Some(key));
+            }
+            if room.passcode_hash.is_some() && passcode.is_none_or(str::is_empty) {
+                return Err("Invite link is invalid or has expired".to_string());
+            }
+        }
+        Self::check_passcode(room, room_id, passcode).map(|_| None)
+    }
+
+    fn consume_invite(room: &mut Room, key: &str) {
+        let Some(invite) = room.invites.get_mut(key) else {
+            return;
+        };
+        if let Some(uses_left) = invite.uses_left.as_mut() {
+            *uses_left = uses_left.saturating_sub(1);
+            if *uses_left == 0 {
+                room.invites.remove(key);
+            }
+        }
+    }
+
+    /// Invites are stored under a digest so room announcements never carry a usable token
+    fn invite_key(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    fn check_passcode(room: &Room, room_id: &str, passcode: Option<&str>) -> Result<(), String> {
+        let Some(expected) = &room.passcode_hash else {
+            return Ok(());
+        };
+        let provided = passcode
+            .filter(|code| !code.is_empty())
+            .ok_or_else(|| "Passcode required".to_string())?;
+        if &Self::hash_passcode(provided, room_id) != expected {
+            return Err("Invalid passcode".to_string());
+        }
+        Ok(())
+    }
+
     fn hash_passcode(passcode: &str, room_id: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(room_id.as_bytes());
@@ -355,6 +948,7 @@ impl ServerState {
                 ClientInfo {
                     room_id: None,
                     display_name: resolved.clone(),
+                    remote_ip: None,
                 },
             );
         }
@@ -362,6 +956,17 @@ impl ServerState {
         resolved
     }
 
+    pub fn remote_ip(&self, client_id: Uuid) -> Option<IpAddr> {
+        self.clients.get(&client_id).and_then(|info| info.remote_ip)
+    }
+
+    pub fn display_name(&self, client_id: Uuid) -> String {
+        self.clients
+            .get(&client_id)
+            .map(|info| info.display_name.clone())
+            .unwrap_or_else(|| Self::default_display_name(client_id))
+    }
+
     fn sanitize_display_name(raw: &str) -> Option<String> {
         let trimmed = raw.trim();
         if trimmed.is_empty() {
@@ -384,6 +989,28 @@ impl ServerState {
         }
     }
 
+    fn sanitize_listing(listing: RoomListing, host_name: &str) -> RoomListing {
+        let clean = |raw: &str, limit: usize| -> String {
+            raw.trim()
+                .chars()
+                .filter(|ch| !ch.is_control())
+                .take(limit)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        let title = clean(&listing.title, MAX_LISTING_TITLE_LEN);
+        let title = if title.is_empty() {
+            format!("{host_name}'s room")
+        } else {
+            title
+        };
+        RoomListing {
+            title,
+            description: clean(&listing.description, MAX_LISTING_DESCRIPTION_LEN),
+        }
+    }
+
     fn default_display_name(client_id: Uuid) -> String {
         let short = &client_id.to_string()[..8];
         format!("Guest {short}")
@@ -395,18 +1022,25 @@ impl ServerState {
             .unwrap_or(DEFAULT_CAPACITY)
     }
 
-    pub fn room_capacity(&self, room_id: &str) -> usize {
-        self.rooms
-            .get(room_id)
-            .map(|room| room.capacity)
-            .unwrap_or(DEFAULT_CAPACITY)
+    /// Roster across all instances, along with the room's capacity
+    pub async fn room_snapshot(&self, room_id: &str) -> Option<(Vec<MemberSummary>, usize)> {
+        let capacity = self.rooms.get(room_id).map(|room| room.capacity)?;
+        let mut roster = self.local_roster(room_id).await;
+        if let Some(remote) = self.remote_members.get(room_id) {
+            roster.extend(remote.values().flatten().cloned());
+        }
+        Some((roster, capacity))
     }
 
-    pub async fn room_snapshot(&self, room_id: &str) -> Option<(Vec<MemberSummary>, usize)> {
-        let (host_id, capacity) = self
+    /// Members connected to this instance
+    pub async fn local_roster(&self, room_id: &str) -> Vec<MemberSummary> {
+        let Some((host_id, spectators)) = self
             .rooms
             .get(room_id)
-            .map(|room| (room.host_id, room.capacity))?;
+            .map(|room| (room.host_id, room.spectators.clone()))
+        else {
+            return Vec::new();
+        };
         let members = self.get_room_members(room_id).await;
         let mut roster = Vec::with_capacity(members.len());
         for member_id in members {
@@ -419,10 +1053,226 @@ impl ServerState {
                 client_id: member_id,
                 display_name,
                 is_host: member_id == host_id,
+                is_spectator: spectators.contains(&member_id),
             });
         }
-        Some((roster, capacity))
+        roster
+    }
+
+    fn remote_display_name(&self, room_id: &str, client_id: Uuid) -> Option<String> {
+        let remote = self.remote_members.get(room_id)?;
+        remote
+            .values()
+            .flatten()
+            .find(|member| member.client_id == client_id)
+            .map(|member| member.display_name.clone())
     }
+
+    /// Seated members and spectators of a room connected to other instances
+    fn remote_counts(&self, room_id: &str) -> (usize, usize) {
+        let Some(remote) = self.remote_members.get(room_id) else {
+            return (0, 0);
+        };
+        let spectators = remote
+            .values()
+            .flatten()
+            .filter(|member| member.is_spectator)
+            .count();
+        let total: usize = remote.values().map(Vec::len).sum();
+        (total - spectators, spectators)
+    }
+
+    /// Room settings to announce to other instances
+    pub fn room_for_mirror(&self, room_id: &str) -> Option<RoomMirror> {
+        self.rooms.get(room_id).map(|room| RoomMirror {
+            host_id: room.host_id,
+            file_hash: room.file_hash.clone(),
+            passcode_hash: room.passcode_hash.clone(),
+            capacity: room.capacity,
+            spectator_capacity: room.spectator_capacity,
+            playback: room.playback.clone(),
+            listing: room.listing.clone(),
+            schedule: room.schedule.clone(),
+            invites: room.invites.clone(),
+            sync_tracks: room.sync_tracks,
+            track_selection: room.track_selection.clone(),
+            bookmarks: room.bookmarks.clone(),
+            uid: room.uid,
+        })
+    }
+
+    pub fn room_uid(&self, room_id: &str) -> Option<Uuid> {
+        self.rooms.get(room_id).map(|room| room.uid)
+    }
+
+    /// Stores or refreshes a room announced by another instance.
+    /// Returns false if the code already belongs to a different room here, which happens when
+    /// two instances hand out the same code before hearing of each other. Both rooms then
+    /// stay local to their instance rather than one taking over the other.
+    pub fn mirror_room(&self, room_id: &str, announced: RoomMirror) -> bool {
+        match self.rooms.get_mut(room_id) {
+            Some(room) if !Self::same_uid(room.uid, announced.uid) => {
+                tracing::warn!(%room_id, "Room code is already in use here; ignoring the announced room");
+                false
+            }
+            Some(mut room) => {
+                room.host_id = announced.host_id;
+                room.capacity = announced.capacity;
+                room.spectator_capacity = announced.spectator_capacity;
+                room.listing = announced.listing;
+                room.schedule = announced.schedule;
+                room.invites = announced.invites;
+                true
+            }
+            None => {
+                let room = Room {
+                    host_id: announced.host_id,
+                    file_hash: announced.file_hash,
+                    passcode_hash: announced.passcode_hash,
+                    capacity: announced.capacity,
+                    spectator_capacity: announced.spectator_capacity,
+                    spectators: HashSet::new(),
+                    playback: announced.playback,
+                    listing: announced.listing,
+                    schedule: announced.schedule,
+                    invites: announced.invites,
+                    sync_tracks: announced.sync_tracks,
+                    track_selection: announced.track_selection,
+                    bookmarks: announced.bookmarks,
+                    feed_token: String::new(),
+                    uid: announced.uid,
+                    kicked: HashMap::new(),
+                };
+                self.rooms.insert(room_id.to_string(), room);
+                self.room_members
+                    .entry(room_id.to_string())
+                    .or_insert_with(|| Arc::new(RwLock::new(Vec::new())));
+                tracing::debug!(%room_id, "Mirrored room from another instance");
+                true
+            }
+        }
+    }
+
+    /// Whether a cluster event tagged with `room_uid` is about our room with this code
+    pub fn is_same_room(&self, room_id: &str, room_uid: Uuid) -> bool {
+        self.room_uid(room_id)
+            .is_some_and(|uid| Self::same_uid(uid, room_uid))
+    }
+
+    /// Nil stands for an instance that doesn't tag its rooms and is trusted as before
+    fn same_uid(ours: Uuid, theirs: Uuid) -> bool {
+        ours.is_nil() || theirs.is_nil() || ours == theirs
+    }
+
+    /// Replaces another instance's roster for a room.
+    /// Returns false if the room is unknown or was deleted because everyone has left.
+    pub async fn set_remote_members(
+        &self,
+        room_id: &str,
+        instance_id: Uuid,
+        members: Vec<MemberSummary>,
+    ) -> bool {
+        if !self.rooms.contains_key(room_id) {
+            return false;
+        }
+        {
+            let mut remote = self.remote_members.entry(room_id.to_string()).or_default();
+            if members.is_empty() {
+                remote.remove(&instance_id);
+            } else {
+                remote.insert(instance_id, members);
+            }
+        }
+        !self.remove_if_deserted(room_id).await
+    }
+
+    /// Drops the rosters of an instance that stopped responding.
+    /// Returns the rooms that lost members and still exist.
+    pub async fn forget_instance(&self, instance_id: Uuid) -> Vec<String> {
+        let affected: Vec<String> = self
+            .remote_members
+            .iter_mut()
+            .filter_map(|mut entry| entry.remove(&instance_id).map(|_| entry.key().clone()))
+            .collect();
+        let mut remaining = Vec::with_capacity(affected.len());
+        for room_id in affected {
+            if !self.remove_if_deserted(&room_id).await {
+                remaining.push(room_id);
+            }
+        }
+        remaining
+    }
+
+    /// Rooms with members on this instance, for peers that need to catch up
+    pub async fn rooms_with_local_members(&self) -> Vec<String> {
+        let candidates: Vec<(String, Arc<RwLock<Vec<Uuid>>>)> = self
+            .room_members
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+            .collect();
+        let mut rooms = Vec::new();
+        for (room_id, members) in candidates {
+            if !members.read().await.is_empty() {
+                rooms.push(room_id);
+            }
+        }
+        rooms
+    }
+
+    /// Directory entries for every room that opted into the public listing
+    pub async fn public_rooms(&self) -> Vec<PublicRoomSummary> {
+        // Snapshot listed rooms first so no map guard is held across the awaits below
+        let listed: Vec<(PublicRoomSummary, Uuid, usize)> = self
+            .rooms
+            .iter()
+            .filter_map(|entry| {
+                let room = entry.value();
+                let listing = room.listing.clone()?;
+                let summary = PublicRoomSummary {
+                    room_id: entry.key().clone(),
+                    title: listing.title,
+                    description: listing.description,
+                    host_name: String::new(),
+                    member_count: 0,
+                    capacity: room.capacity,
+                    passcode_protected: room.passcode_hash.is_some(),
+                    is_playing: room.playback.is_playing,
+                    starts_at_ms: room.schedule.as_ref().map(|schedule| schedule.starts_at_ms),
+                };
+                Some((summary, room.host_id, room.spectators.len()))
+            })
+            .collect();
+
+        let mut summaries = Vec::with_capacity(listed.len());
+        for (mut summary, host_id, spectator_count) in listed {
+            summary.member_count = self
+                .get_room_members(&summary.room_id)
+                .await
+                .len()
+                .saturating_sub(spectator_count)
+                + self.remote_counts(&summary.room_id).0;
+            summary.host_name = self
+                .clients
+                .get(&host_id)
+                .map(|info| info.display_name.clone())
+                .or_else(|| self.remote_display_name(&summary.room_id, host_id))
+                .unwrap_or_else(|| Self::default_display_name(host_id));
+            summaries.push(summary);
+        }
+        summaries.sort_by(|a, b| {
+            b.member_count
+                .cmp(&a.member_count)
+                .then_with(|| a.title.cmp(&b.title))
+        });
+        summaries
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|dur| dur.as_millis() as u64)
+        .unwrap_or(0)
 }
 
 #[derive(Clone)]
@@ -431,6 +1281,7 @@ pub struct ResumeRecord {
     pub room_id: String,
     pub file_hash: String,
     pub was_host: bool,
+    pub spectator: bool,
     pub display_name: Option<String>,
 }
 
@@ -442,4 +1293,7 @@ pub struct ResumeOutcome {
     pub file_hash: String,
     pub capacity: usize,
     pub display_name: String,
+    /// Host the room had before this resume reclaimed it
+    pub previous_host_id: Option<Uuid>,
+    pub spectator: bool,
 }
@@ -0,0 +1,80 @@
+/// Text subtitle formats members may share through the server
+const SHAREABLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt"];
+const MAX_FILE_NAME_LEN: usize = 128;
+
+/// Checks a `ShareSubtitle` upload against the server's limits and returns the file name to
+/// relay, stripped of any directories
+pub fn validate_shared_subtitle(
+    file_name: &str,
+    content: &str,
+    max_bytes: usize,
+) -> Result<String, String> {
+    if max_bytes == 0 {
+        return Err("Subtitle sharing is disabled on this server".to_string());
+    }
+    if content.len() > max_bytes {
+        return Err(format!(
+            "Subtitle file is too large (limit {} KB)",
+            max_bytes.div_ceil(1024)
+        ));
+    }
+    if content.trim().is_empty() {
+        return Err("Subtitle file is empty".to_string());
+    }
+    if content.contains('\0') {
+        return Err("Subtitle file is not text".to_string());
+    }
+
+    let name: String = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .collect();
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.chars().count() > MAX_FILE_NAME_LEN {
+        return Err("Invalid subtitle file name".to_string());
+    }
+    let shareable = name.rsplit_once('.').is_some_and(|(_, ext)| {
+        SHAREABLE_EXTENSIONS
+            .iter()
+            .any(|allowed| ext.eq_ignore_ascii_case(allowed))
+    });
+    if !shareable {
+        return Err("Only .srt, .ass, .ssa, and .vtt subtitles can be shared".to_string());
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRT: &str = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+    #[test]
+    fn strips_directories_and_checks_the_extension() {
+        assert_eq!(
+            validate_shared_subtitle("C:\\Users\\me\\Movie.EN.srt", SRT, 1024).unwrap(),
+            "Movie.EN.srt"
+        );
+        assert_eq!(
+            validate_shared_subtitle("../../etc/Movie.vtt", SRT, 1024).unwrap(),
+            "Movie.vtt"
+        );
+        assert!(validate_shared_subtitle("Movie.sub", SRT, 1024).is_err());
+        assert!(validate_shared_subtitle("Movie", SRT, 1024).is_err());
+        assert!(validate_shared_subtitle("dir/", SRT, 1024).is_err());
+        assert!(validate_shared_subtitle(".srt", SRT, 1024).is_err());
+    }
+
+    #[test]
+    fn enforces_size_and_text_content() {
+        assert!(validate_shared_subtitle("a.srt", SRT, SRT.len()).is_ok());
+        assert!(validate_shared_subtitle("a.srt", SRT, SRT.len() - 1).is_err());
+        assert!(validate_shared_subtitle("a.srt", SRT, 0).is_err());
+        assert!(validate_shared_subtitle("a.srt", " \n", 1024).is_err());
+        assert!(validate_shared_subtitle("a.srt", "1\0", 1024).is_err());
+    }
+}
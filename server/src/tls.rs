@@ -0,0 +1,293 @@
+use anyhow::Context;
+use axum::{extract::ConnectInfo, Router};
+use hyper::body::Incoming;
+use hyper_util::rt::TokioIo;
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+/// Connections that have not finished the handshake by then are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Certificate and key locations, re-read whenever either file changes
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// How often the files are checked for changes
+    pub reload_interval: Duration,
+}
+
+/// Hands out whichever certificate was loaded last, so reloads apply to new handshakes only
+#[derive(Debug)]
+struct ReloadingResolver {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for ReloadingResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.current.read().ok().map(|key| Arc::clone(&key))
+    }
+}
+
+/// Serves `app` over HTTPS/WSS until `shutdown` resolves, then waits for in-flight requests.
+/// Upgraded WebSockets are detached from their connection and handle shutdown themselves.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    files: TlsFiles,
+    shutdown: impl Future<Output = ()>,
+) -> anyhow::Result<()> {
+    // Taken before loading, so a swap that lands in between is picked up by the first poll
+    let loaded_times = modified_times(&files);
+    let resolver = Arc::new(ReloadingResolver {
+        current: RwLock::new(Arc::new(load_certified_key(&files)?)),
+    });
+    let mut tls_config =
+        rustls::ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(resolver.clone());
+    // Only HTTP/1.1 is served; WebSocket upgrades need it anyway
+    tls_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    let acceptor = TlsAcceptor::from(Arc::new(tls_config));
+    let reloader = tokio::spawn(watch_for_changes(resolver, files, loaded_times));
+
+    // Every connection task holds a receiver: the first channel tells them to wind down,
+    // the second reports when the last one has gone
+    let (stop_tx, stop_rx) = watch::channel(());
+    let (closed_tx, closed_rx) = watch::channel(());
+    tokio::pin!(shutdown);
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::warn!(error = %err, "Failed to accept connection");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        let mut stop_rx = stop_rx.clone();
+        let closed_rx = closed_rx.clone();
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(err)) => {
+                        tracing::debug!(%remote_addr, error = %err, "TLS handshake failed");
+                        return;
+                    }
+                    Err(_) => {
+                        tracing::debug!(%remote_addr, "TLS handshake timed out");
+                        return;
+                    }
+                };
+
+            // Same extension `into_make_service_with_connect_info` would add for plain HTTP
+            let service =
+                hyper::service::service_fn(move |mut request: hyper::Request<Incoming>| {
+                    request.extensions_mut().insert(ConnectInfo(remote_addr));
+                    app.clone().oneshot(request)
+                });
+            let connection = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            tokio::pin!(connection);
+            let result = tokio::select! {
+                result = connection.as_mut() => result,
+                _ = stop_rx.changed() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.as_mut().await
+                }
+            };
+            if let Err(err) = result {
+                tracing::debug!(%remote_addr, error = %err, "Connection error");
+            }
+            drop(closed_rx);
+        });
+    }
+
+    reloader.abort();
+    drop(listener);
+    drop(stop_rx);
+    drop(closed_rx);
+    let _ = stop_tx.send(());
+    closed_tx.closed().await;
+    Ok(())
+}
+
+fn load_certified_key(files: &TlsFiles) -> anyhow::Result<CertifiedKey> {
+    let certs = CertificateDer::pem_file_iter(&files.cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| {
+            format!(
+                "Failed to read certificates from {}",
+                files.cert_path.display()
+            )
+        })?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in {}", files.cert_path.display());
+    }
+    let key = PrivateKeyDer::from_pem_file(&files.key_path).with_context(|| {
+        format!(
+            "Failed to read private key from {}",
+            files.key_path.display()
+        )
+    })?;
+    let signing_key = ring::sign::any_supported_type(&key)
+        .with_context(|| format!("Unsupported private key in {}", files.key_path.display()))?;
+
+    let certified = CertifiedKey::new(certs, signing_key);
+    certified
+        .keys_match()
+        .context("TLS certificate does not match the private key")?;
+    Ok(certified)
+}
+
+/// Polls the certificate files and swaps in the new pair once both load cleanly.
+/// A half-written or mismatched pair is logged and retried on the next change.
+async fn watch_for_changes(
+    resolver: Arc<ReloadingResolver>,
+    files: TlsFiles,
+    loaded_times: FileTimes,
+) {
+    let mut last_seen = loaded_times;
+    let mut interval = tokio::time::interval(files.reload_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let seen = modified_times(&files);
+        if seen == last_seen {
+            continue;
+        }
+        last_seen = seen;
+
+        match load_certified_key(&files) {
+            Ok(key) => {
+                if let Ok(mut current) = resolver.current.write() {
+                    *current = Arc::new(key);
+                }
+                tracing::info!(cert = %files.cert_path.display(), "Reloaded TLS certificate");
+            }
+            Err(err) => {
+                tracing::warn!(
+                    error = format!("{:#}", err),
+                    "Keeping previous TLS certificate"
+                );
+            }
+        }
+    }
+}
+
+/// Modification times of the certificate and key files
+type FileTimes = (Option<SystemTime>, Option<SystemTime>);
+
+fn modified_times(files: &TlsFiles) -> FileTimes {
+    let modified = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    };
+    (modified(&files.cert_path), modified(&files.key_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST_CERT: &[u8] = include_bytes!("../testdata/tls/first.crt");
+    const FIRST_KEY: &[u8] = include_bytes!("../testdata/tls/first.key");
+    const SECOND_CERT: &[u8] = include_bytes!("../testdata/tls/second.crt");
+    const SECOND_KEY: &[u8] = include_bytes!("../testdata/tls/second.key");
+
+    /// Writes the first pair to a scratch directory and starts watching it
+    fn watched_files() -> (TlsFiles, Arc<ReloadingResolver>) {
+        let dir = std::env::temp_dir().join(format!("hang-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = TlsFiles {
+            cert_path: dir.join("cert.pem"),
+            key_path: dir.join("key.pem"),
+            reload_interval: Duration::from_millis(10),
+        };
+        write_pair(&files, FIRST_CERT, FIRST_KEY);
+        let loaded_times = modified_times(&files);
+        let resolver = Arc::new(ReloadingResolver {
+            current: RwLock::new(Arc::new(load_certified_key(&files).unwrap())),
+        });
+        tokio::spawn(watch_for_changes(
+            resolver.clone(),
+            files.clone(),
+            loaded_times,
+        ));
+        (files, resolver)
+    }
+
+    fn write_pair(files: &TlsFiles, cert: &[u8], key: &[u8]) {
+        std::fs::write(&files.cert_path, cert).unwrap();
+        std::fs::write(&files.key_path, key).unwrap();
+    }
+
+    fn serves(resolver: &ReloadingResolver, cert: &[u8]) -> bool {
+        let expected = CertificateDer::from_pem_slice(cert).unwrap();
+        resolver.current.read().unwrap().cert[0] == expected
+    }
+
+    async fn wait_until_serving(resolver: &ReloadingResolver, cert: &[u8]) {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while !serves(resolver, cert) {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "certificate was never reloaded"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn swapped_files_are_picked_up_by_the_resolver() {
+        let (files, resolver) = watched_files();
+        assert!(serves(&resolver, FIRST_CERT));
+
+        write_pair(&files, SECOND_CERT, SECOND_KEY);
+        wait_until_serving(&resolver, SECOND_CERT).await;
+
+        std::fs::remove_dir_all(files.cert_path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn broken_files_keep_the_previous_certificate() {
+        let (files, resolver) = watched_files();
+
+        std::fs::write(&files.cert_path, b"-----BEGIN CERTIFICATE-----\ntruncated").unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(serves(&resolver, FIRST_CERT));
+
+        // Half of a renewal: the new certificate is in place but the key is still the old one
+        write_pair(&files, SECOND_CERT, FIRST_KEY);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(serves(&resolver, FIRST_CERT));
+
+        // The watcher is still running and takes the pair once it is complete
+        std::fs::write(&files.key_path, SECOND_KEY).unwrap();
+        wait_until_serving(&resolver, SECOND_CERT).await;
+
+        std::fs::remove_dir_all(files.cert_path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use uuid::Uuid;
+
+use crate::config::ServerConfig;
+use crate::state::now_millis;
+
+/// Events buffered before new ones are dropped; keeps a dead endpoint from growing memory
+const QUEUE_CAPACITY: usize = 1024;
+/// Deliveries in flight at once across all endpoints
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const SIGNATURE_HEADER: &str = "X-Hang-Signature";
+
+/// Room lifecycle events delivered to webhook subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum WebhookEvent {
+    #[serde(rename = "room.created")]
+    RoomCreated {
+        host_id: Uuid,
+        host_name: String,
+        capacity: usize,
+        public: bool,
+        scheduled_start_ms: Option<u64>,
+    },
+    #[serde(rename = "room.closed")]
+    RoomClosed { reason: CloseReason },
+    #[serde(rename = "member.joined")]
+    MemberJoined {
+        client_id: Uuid,
+        display_name: String,
+        member_count: usize,
+    },
+    #[serde(rename = "member.left")]
+    MemberLeft {
+        client_id: Uuid,
+        display_name: String,
+        member_count: usize,
+    },
+    #[serde(rename = "host.changed")]
+    HostChanged {
+        previous_host_id: Option<Uuid>,
+        host_id: Uuid,
+    },
+    #[serde(rename = "playback.play")]
+    Play {
+        /// `None` when the server started a scheduled watch party
+        client_id: Option<Uuid>,
+        position: f64,
+    },
+    #[serde(rename = "playback.pause")]
+    Pause { client_id: Uuid, position: f64 },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// The last member left
+    Empty,
+    /// Closed through the admin API
+    Admin,
+    /// A scheduled room passed its grace window with nobody in it
+    Expired,
+}
+
+#[derive(Debug, Serialize)]
+struct Envelope {
+    id: Uuid,
+    timestamp_ms: u64,
+    room_id: String,
+    #[serde(flatten)]
+    event: WebhookEvent,
+}
+
+/// Handle for queueing webhook deliveries; cheap to clone and never blocks the caller
+#[derive(Clone)]
+pub struct Webhooks {
+    tx: Option<mpsc::Sender<Envelope>>,
+}
+
+impl Webhooks {
+    /// Starts the delivery worker, or returns a no-op handle when no URLs are configured
+    pub fn spawn(config: &ServerConfig) -> Self {
+        if config.webhook_urls.is_empty() {
+            return Self { tx: None };
+        }
+        if config.webhook_secret.is_none() {
+            tracing::warn!(
+                "Webhooks are enabled without HANG_WEBHOOK_SECRET; payloads will be unsigned"
+            );
+        }
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let worker = Worker {
+            urls: config.webhook_urls.clone(),
+            secret: config.webhook_secret.clone(),
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES)),
+        };
+        tokio::spawn(worker.run(rx));
+        tracing::info!(endpoints = config.webhook_urls.len(), "Webhooks enabled");
+        Self { tx: Some(tx) }
+    }
+
+    pub fn emit(&self, room_id: &str, event: WebhookEvent) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        let envelope = Envelope {
+            id: Uuid::new_v4(),
+            timestamp_ms: now_millis(),
+            room_id: room_id.to_string(),
+            event,
+        };
+        if let Err(err) = tx.try_send(envelope) {
+            tracing::warn!(%room_id, error = %err, "Dropping webhook event");
+        }
+    }
+}
+
+struct Worker {
+    urls: Vec<String>,
+    secret: Option<String>,
+    http: reqwest::Client,
+    permits: Arc<Semaphore>,
+}
+
+impl Worker {
+    async fn run(self, mut rx: mpsc::Receiver<Envelope>) {
+        while let Some(envelope) = rx.recv().await {
+            let body = match serde_json::to_vec(&envelope) {
+                Ok(body) => Arc::new(body),
+                Err(err) => {
+                    tracing::error!(error = %err, "Failed to serialize webhook event");
+                    continue;
+                }
+            };
+            let event: Arc<str> = event_name(&body).into();
+            let signature = self
+                .secret
+                .as_ref()
+                .map(|secret| format!("sha256={}", to_hex(&hmac_sha256(secret.as_bytes(), &body))));
+
+            for url in &self.urls {
+                let Ok(permit) = self.permits.clone().acquire_owned().await else {
+                    return;
+                };
+                let delivery = Delivery {
+                    http: self.http.clone(),
+                    url: url.clone(),
+                    body: body.clone(),
+                    signature: signature.clone(),
+                    event: event.clone(),
+                    delivery_id: envelope.id,
+                };
+                tokio::spawn(async move {
+                    delivery.send_with_retries().await;
+                    drop(permit);
+                });
+            }
+        }
+    }
+}
+
+struct Delivery {
+    http: reqwest::Client,
+    url: String,
+    body: Arc<Vec<u8>>,
+    signature: Option<String>,
+    event: Arc<str>,
+    delivery_id: Uuid,
+}
+
+impl Delivery {
+    async fn send_with_retries(&self) {
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match self.send_once().await {
+                Ok(()) => return,
+                Err((reason, retryable)) => {
+                    if !retryable || attempt == MAX_ATTEMPTS {
+                        tracing::warn!(
+                            event = &*self.event,
+                            url = %self.url,
+                            %reason,
+                            attempt,
+                            "Webhook delivery failed"
+                        );
+                        return;
+                    }
+                    tracing::debug!(event = &*self.event, url = %self.url, %reason, attempt, "Webhook delivery failed, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+
+    /// Returns the failure reason and whether it is worth retrying
+    async fn send_once(&self) -> Result<(), (String, bool)> {
+        let mut request = self
+            .http
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Hang-Event", self.event.as_ref())
+            .header("X-Hang-Delivery", self.delivery_id.to_string())
+            .body(self.body.as_ref().clone());
+        if let Some(signature) = &self.signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| (err.to_string(), true))?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let retryable = status.is_server_error() || status.as_u16() == 429;
+            Err((format!("HTTP {}", status), retryable))
+        }
+    }
+}
+
+/// The `event` field of a serialized envelope, so the header always names what the body says
+fn event_name(body: &[u8]) -> String {
+    #[derive(Deserialize)]
+    struct Tagged {
+        event: String,
+    }
+    serde_json::from_slice::<Tagged>(body)
+        .map(|tagged| tagged.event)
+        .unwrap_or_default()
+}
+
+/// HMAC-SHA256 as defined in RFC 2104
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::http::{HeaderMap, StatusCode};
+
+    fn hex_hmac(key: &[u8], message: &[u8]) -> String {
+        to_hex(&hmac_sha256(key, message))
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_over_the_exact_body() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let receiver = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move |headers: HeaderMap, body: Bytes| {
+                let _ = tx.send((headers, body));
+                async { StatusCode::NO_CONTENT }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let webhooks = Webhooks::spawn(&ServerConfig {
+            webhook_urls: vec![format!("http://{addr}/hook")],
+            webhook_secret: Some("s3cret".to_string()),
+            ..ServerConfig::default()
+        });
+        webhooks.emit(
+            "123-456",
+            WebhookEvent::RoomClosed {
+                reason: CloseReason::Admin,
+            },
+        );
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("webhook was not delivered")
+            .unwrap();
+
+        let expected = format!("sha256={}", hex_hmac(b"s3cret", &body));
+        assert_eq!(headers[SIGNATURE_HEADER], expected.as_str());
+        assert_eq!(headers["X-Hang-Event"], "room.closed");
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["event"], "room.closed");
+        assert_eq!(payload["room_id"], "123-456");
+        assert_eq!(payload["data"]["reason"], "admin");
+    }
+}